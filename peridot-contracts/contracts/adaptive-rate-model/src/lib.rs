@@ -0,0 +1,626 @@
+#![no_std]
+use soroban_sdk::{
+    contract, contractevent, contractimpl, contracttype, Address, BytesN, Env, String,
+};
+
+const SCALE_1E6: u128 = 1_000_000u128;
+const WAD: i128 = 1_000_000_000_000_000_000i128;
+// Converts a 1e6-scaled value to WAD (1e18) precision.
+const SCALE_1E6_TO_WAD: i128 = 1_000_000_000_000i128;
+const SECONDS_PER_YEAR: i128 = 365 * 24 * 60 * 60;
+const LN_2_WAD: i128 = 693_147_180_559_945_309i128;
+// exp(40) * 1e18 still fits in i128 after the final shift.
+const MAX_LINEAR_ADAPTATION_WAD: i128 = 40 * WAD;
+// Matches the ReceiptVault yearly rate cap so the vault never rejects a quote.
+const MAX_YEARLY_RATE_SCALED: u128 = 10_000_000u128;
+const MAX_CURVE_STEEPNESS: u128 = 100 * SCALE_1E6;
+const MAX_ADJUSTMENT_SPEED: u128 = 1_000 * SCALE_1E6;
+pub const DEFAULT_INIT_ADMIN: &str = "GATFXAP3AVUYRJJCXZ65EPVJEWRW6QYE3WOAFEXAIASFGZV7V7HMABPJ";
+const TTL_THRESHOLD: u32 = 500_000;
+const TTL_EXTEND_TO: u32 = 1_000_000;
+const UPGRADE_TIMELOCK_SECS: u64 = 24 * 60 * 60;
+
+#[contracttype]
+pub enum DataKey {
+    Params,             // AdaptiveCurveParams
+    State,              // CurveState
+    Market,             // Address allowed to advance the curve
+    Admin,              // Address
+    PendingUpgradeHash, // BytesN<32>
+    PendingUpgradeEta,  // u64 unix timestamp
+}
+
+/// Curve parameters; every field is scaled 1e6.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdaptiveCurveParams {
+    pub target_utilization: u128,
+    // Rate at full utilization as a multiple of the rate at target.
+    pub curve_steepness: u128,
+    // Max yearly drift of ln(rate_at_target) at 0% or 100% utilization.
+    pub adjustment_speed: u128,
+    pub min_rate_at_target: u128,
+    pub max_rate_at_target: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CurveState {
+    pub rate_at_target: u128, // yearly, scaled 1e18
+    pub last_update: u64,     // 0 until the market first reads a rate
+}
+
+#[contract]
+pub struct AdaptiveRateModel;
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ModelInitialized {
+    pub market: Address,
+    pub target_utilization: u128,
+    pub initial_rate_at_target: u128,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ModelParamsUpdated {
+    pub target_utilization: u128,
+    pub curve_steepness: u128,
+    pub adjustment_speed: u128,
+    pub min_rate_at_target: u128,
+    pub max_rate_at_target: u128,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateAtTargetUpdated {
+    pub rate_at_target: u128,
+    pub borrow_rate: u128,
+}
+
+#[contractimpl]
+impl AdaptiveRateModel {
+    /// One model instance serves one market; only `market` advances the curve.
+    pub fn initialize(
+        env: Env,
+        market: Address,
+        params: AdaptiveCurveParams,
+        initial_rate_at_target: u128,
+        admin: Address,
+    ) {
+        admin.require_auth();
+        if env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::Admin)
+            .is_some()
+        {
+            panic!("already initialized");
+        }
+        assert_expected_admin(&env, &admin);
+        validate_params(&params);
+        if initial_rate_at_target < params.min_rate_at_target
+            || initial_rate_at_target > params.max_rate_at_target
+        {
+            panic!("invalid initial rate");
+        }
+        let persistent = env.storage().persistent();
+        persistent.set(&DataKey::Admin, &admin);
+        persistent.set(&DataKey::Market, &market);
+        persistent.set(&DataKey::Params, &params);
+        persistent.set(
+            &DataKey::State,
+            &CurveState {
+                rate_at_target: initial_rate_at_target * SCALE_1E6_TO_WAD as u128,
+                last_update: 0,
+            },
+        );
+        bump_ttl(&env);
+        ModelInitialized {
+            market,
+            target_utilization: params.target_utilization,
+            initial_rate_at_target,
+        }
+        .publish(&env);
+    }
+
+    /// Returns the average borrow rate since the previous call and moves the
+    /// rate at target toward the current utilization. Market-only.
+    pub fn get_borrow_rate(env: Env, cash: u128, borrows: u128, reserves: u128) -> u128 {
+        ensure_initialized(&env);
+        bump_ttl(&env);
+        let market: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Market)
+            .expect("market missing");
+        market.require_auth();
+        let now = env.ledger().timestamp();
+        let state = read_state(&env);
+        let (rate, next) = Self::advance(&env, &state, cash, borrows, reserves, now);
+        if state.last_update != now {
+            env.storage().persistent().set(&DataKey::State, &next);
+            RateAtTargetUpdated {
+                rate_at_target: to_scaled_1e6(next.rate_at_target as i128),
+                borrow_rate: rate,
+            }
+            .publish(&env);
+        }
+        rate
+    }
+
+    /// Same as `get_borrow_rate` without advancing the stored curve.
+    pub fn peek_borrow_rate(env: Env, cash: u128, borrows: u128, reserves: u128) -> u128 {
+        ensure_initialized(&env);
+        bump_ttl(&env);
+        let state = read_state(&env);
+        let now = env.ledger().timestamp();
+        Self::advance(&env, &state, cash, borrows, reserves, now).0
+    }
+
+    pub fn get_supply_rate(
+        env: Env,
+        cash: u128,
+        borrows: u128,
+        reserves: u128,
+        reserve_factor: u128,
+    ) -> u128 {
+        ensure_initialized(&env);
+        bump_ttl(&env);
+        if reserve_factor > SCALE_1E6 {
+            panic!("invalid reserve_factor");
+        }
+        let one_minus_rf = SCALE_1E6.saturating_sub(reserve_factor);
+        let borrow_rate = Self::peek_borrow_rate(env.clone(), cash, borrows, reserves);
+        let rate_to_pool = borrow_rate.saturating_mul(one_minus_rf) / SCALE_1E6;
+        let util = Self::utilization(cash, borrows, reserves);
+        util.saturating_mul(rate_to_pool) / SCALE_1E6
+    }
+
+    /// Stored rate at target utilization (yearly, scaled 1e6).
+    pub fn get_rate_at_target(env: Env) -> u128 {
+        ensure_initialized(&env);
+        to_scaled_1e6(read_state(&env).rate_at_target as i128)
+    }
+
+    pub fn get_params(env: Env) -> AdaptiveCurveParams {
+        ensure_initialized(&env);
+        read_params(&env)
+    }
+
+    pub fn get_market(env: Env) -> Address {
+        ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&DataKey::Market)
+            .expect("market missing")
+    }
+
+    /// Admin: replace the curve parameters. The stored rate at target is
+    /// clamped into the new bounds.
+    pub fn set_params(env: Env, admin: Address, params: AdaptiveCurveParams) {
+        require_admin(&env, &admin);
+        validate_params(&params);
+        let mut state = read_state(&env);
+        let min_wad = params.min_rate_at_target * SCALE_1E6_TO_WAD as u128;
+        let max_wad = params.max_rate_at_target * SCALE_1E6_TO_WAD as u128;
+        state.rate_at_target = state.rate_at_target.clamp(min_wad, max_wad);
+        env.storage().persistent().set(&DataKey::State, &state);
+        env.storage().persistent().set(&DataKey::Params, &params);
+        bump_ttl(&env);
+        ModelParamsUpdated {
+            target_utilization: params.target_utilization,
+            curve_steepness: params.curve_steepness,
+            adjustment_speed: params.adjustment_speed,
+            min_rate_at_target: params.min_rate_at_target,
+            max_rate_at_target: params.max_rate_at_target,
+        }
+        .publish(&env);
+    }
+
+    pub fn propose_upgrade_wasm(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        require_admin(&env, &admin);
+        let execute_after = env
+            .ledger()
+            .timestamp()
+            .saturating_add(UPGRADE_TIMELOCK_SECS);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingUpgradeHash, &new_wasm_hash);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingUpgradeEta, &execute_after);
+        bump_pending_upgrade_ttl(&env);
+    }
+
+    pub fn upgrade_wasm(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        require_admin(&env, &admin);
+        bump_pending_upgrade_ttl(&env);
+        let pending_hash: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingUpgradeHash)
+            .expect("pending upgrade not set");
+        let execute_after: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingUpgradeEta)
+            .expect("pending upgrade eta not set");
+        if pending_hash != new_wasm_hash {
+            panic!("upgrade hash mismatch");
+        }
+        if env.ledger().timestamp() < execute_after {
+            panic!("upgrade timelocked");
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingUpgradeHash);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingUpgradeEta);
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    /// Adapts the rate at target over the elapsed time and prices the curve
+    /// at the trapezoidal average of the start, mid and end rates at target.
+    fn advance(
+        env: &Env,
+        state: &CurveState,
+        cash: u128,
+        borrows: u128,
+        reserves: u128,
+        now: u64,
+    ) -> (u128, CurveState) {
+        let params = read_params(env);
+        let util = Self::utilization(cash, borrows, reserves);
+        let err = error_wad(util, params.target_utilization);
+        let min_wad = params.min_rate_at_target as i128 * SCALE_1E6_TO_WAD;
+        let max_wad = params.max_rate_at_target as i128 * SCALE_1E6_TO_WAD;
+        let start = state.rate_at_target as i128;
+        let elapsed = if state.last_update == 0 {
+            0
+        } else {
+            now.saturating_sub(state.last_update) as i128
+        };
+        let speed = params.adjustment_speed as i128 * err / SCALE_1E6 as i128;
+        let linear = (speed.saturating_mul(elapsed) / SECONDS_PER_YEAR)
+            .clamp(-MAX_LINEAR_ADAPTATION_WAD, MAX_LINEAR_ADAPTATION_WAD);
+        let (end, avg) = if linear == 0 {
+            (start, start)
+        } else {
+            let end = adapt(start, linear, min_wad, max_wad);
+            let mid = adapt(start, linear / 2, min_wad, max_wad);
+            (end, (start + end + 2 * mid) / 4)
+        };
+        let rate_wad = curve(avg, err, params.curve_steepness as i128 * SCALE_1E6_TO_WAD);
+        let rate = to_scaled_1e6(rate_wad).min(MAX_YEARLY_RATE_SCALED);
+        (
+            rate,
+            CurveState {
+                rate_at_target: end as u128,
+                last_update: now,
+            },
+        )
+    }
+
+    fn utilization(cash: u128, borrows: u128, reserves: u128) -> u128 {
+        if borrows == 0 {
+            return 0;
+        }
+        let assets = cash.saturating_add(borrows);
+        if reserves > assets {
+            panic!("reserves exceed total assets");
+        }
+        let denom = assets - reserves;
+        if denom == 0 {
+            return 0;
+        }
+        let util = borrows.saturating_mul(SCALE_1E6) / denom;
+        util.min(SCALE_1E6)
+    }
+}
+
+/// Distance from target utilization normalized to [-1, 1] (WAD).
+fn error_wad(util: u128, target: u128) -> i128 {
+    let u = util as i128 * SCALE_1E6_TO_WAD;
+    let t = target as i128 * SCALE_1E6_TO_WAD;
+    if u > t {
+        (u - t) * WAD / (WAD - t)
+    } else {
+        (u - t) * WAD / t
+    }
+}
+
+/// Rate at `err`: from rate_at_target / steepness at 0% utilization up to
+/// rate_at_target * steepness at 100%.
+fn curve(rate_at_target: i128, err: i128, steepness: i128) -> i128 {
+    let coeff = if err < 0 {
+        WAD - WAD * WAD / steepness
+    } else {
+        steepness - WAD
+    };
+    (coeff * err / WAD + WAD) * rate_at_target / WAD
+}
+
+fn adapt(start: i128, linear: i128, min_wad: i128, max_wad: i128) -> i128 {
+    start
+        .checked_mul(wexp(linear))
+        .map(|v| v / WAD)
+        .unwrap_or(max_wad)
+        .clamp(min_wad, max_wad)
+}
+
+/// e^x in WAD using exp(x) = 2^q * exp(r) with |r| <= ln(2) / 2 and a
+/// second-order expansion of exp(r).
+fn wexp(x: i128) -> i128 {
+    let rounding = if x < 0 { -(LN_2_WAD / 2) } else { LN_2_WAD / 2 };
+    let q = (x + rounding) / LN_2_WAD;
+    let r = x - q * LN_2_WAD;
+    let exp_r = WAD + r + r * r / WAD / 2;
+    if q >= 0 {
+        exp_r << q
+    } else {
+        exp_r >> -q
+    }
+}
+
+fn to_scaled_1e6(value_wad: i128) -> u128 {
+    (value_wad.max(0) / SCALE_1E6_TO_WAD) as u128
+}
+
+fn validate_params(params: &AdaptiveCurveParams) {
+    if params.target_utilization == 0 || params.target_utilization >= SCALE_1E6 {
+        panic!("invalid target utilization");
+    }
+    if params.curve_steepness < SCALE_1E6 || params.curve_steepness > MAX_CURVE_STEEPNESS {
+        panic!("invalid curve steepness");
+    }
+    if params.adjustment_speed > MAX_ADJUSTMENT_SPEED {
+        panic!("invalid adjustment speed");
+    }
+    if params.min_rate_at_target == 0
+        || params.min_rate_at_target > params.max_rate_at_target
+        || params
+            .max_rate_at_target
+            .saturating_mul(params.curve_steepness)
+            / SCALE_1E6
+            > MAX_YEARLY_RATE_SCALED
+    {
+        panic!("invalid rate bounds");
+    }
+}
+
+fn read_params(env: &Env) -> AdaptiveCurveParams {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Params)
+        .expect("params missing")
+}
+
+fn read_state(env: &Env) -> CurveState {
+    env.storage()
+        .persistent()
+        .get(&DataKey::State)
+        .expect("curve state missing")
+}
+
+fn ensure_initialized(env: &Env) {
+    let persistent = env.storage().persistent();
+    if persistent.get::<_, Address>(&DataKey::Admin).is_none()
+        || persistent.get::<_, Address>(&DataKey::Market).is_none()
+        || !persistent.has(&DataKey::Params)
+        || !persistent.has(&DataKey::State)
+    {
+        panic!("model not initialized");
+    }
+}
+
+fn assert_expected_admin(_env: &Env, _admin: &Address) {
+    let expected_admin_str = expected_admin_config();
+    let expected_admin = Address::from_string(&String::from_str(_env, expected_admin_str));
+    if _admin != &expected_admin {
+        panic!("unexpected admin");
+    }
+}
+
+fn expected_admin_config() -> &'static str {
+    if cfg!(any(
+        test,
+        all(debug_assertions, feature = "test-default-admin")
+    )) {
+        option_env!("ADAPTIVE_RATE_MODEL_INIT_ADMIN").unwrap_or(DEFAULT_INIT_ADMIN)
+    } else {
+        option_env!("ADAPTIVE_RATE_MODEL_INIT_ADMIN")
+            .expect("ADAPTIVE_RATE_MODEL_INIT_ADMIN must be set at build time")
+    }
+}
+
+fn require_admin(env: &Env, admin: &Address) {
+    let stored: Address = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Admin)
+        .expect("admin not set");
+    bump_ttl(env);
+    if stored != *admin {
+        panic!("not admin");
+    }
+    admin.require_auth();
+}
+
+fn bump_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    for key in [
+        DataKey::Admin,
+        DataKey::Market,
+        DataKey::Params,
+        DataKey::State,
+    ] {
+        if persistent.has(&key) {
+            persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+        }
+    }
+}
+
+fn bump_pending_upgrade_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    if persistent.has(&DataKey::PendingUpgradeHash) {
+        persistent.extend_ttl(&DataKey::PendingUpgradeHash, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    if persistent.has(&DataKey::PendingUpgradeEta) {
+        persistent.extend_ttl(&DataKey::PendingUpgradeEta, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Ledger};
+
+    fn default_params() -> AdaptiveCurveParams {
+        AdaptiveCurveParams {
+            target_utilization: 900_000u128,
+            curve_steepness: 4_000_000u128,
+            adjustment_speed: 50_000_000u128,
+            min_rate_at_target: 1_000u128,
+            max_rate_at_target: 2_000_000u128,
+        }
+    }
+
+    fn setup(env: &Env) -> (AdaptiveRateModelClient<'_>, Address) {
+        let admin = Address::from_string(&String::from_str(env, DEFAULT_INIT_ADMIN));
+        let market = Address::generate(env);
+        // `last_update == 0` marks an unstarted curve
+        env.ledger().set_timestamp(1_000);
+        let id = env.register(AdaptiveRateModel, ());
+        let client = AdaptiveRateModelClient::new(env, &id);
+        client.initialize(&market, &default_params(), &40_000u128, &admin);
+        (client, admin)
+    }
+
+    #[test]
+    fn curve_prices_around_rate_at_target() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, _) = setup(&env);
+        // 90% utilization sits on the target
+        assert_eq!(
+            client.get_borrow_rate(&100u128, &900u128, &0u128),
+            40_000u128
+        );
+        // 0% -> rate / 4, 100% -> rate * 4
+        assert_eq!(
+            client.peek_borrow_rate(&1_000u128, &0u128, &0u128),
+            10_000u128
+        );
+        assert_eq!(
+            client.peek_borrow_rate(&0u128, &1_000u128, &0u128),
+            160_000u128
+        );
+        let sr = client.get_supply_rate(&100u128, &900u128, &0u128, &100_000u128);
+        assert_eq!(sr, 32_400u128);
+    }
+
+    #[test]
+    fn rate_at_target_drifts_toward_target_utilization() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, _) = setup(&env);
+        client.get_borrow_rate(&0u128, &1_000u128, &0u128);
+
+        // Fully utilized for a day: ln drift = 50 / 365, rate_at_target ~ x1.147
+        env.ledger().with_mut(|li| li.timestamp += 86_400);
+        let rate = client.get_borrow_rate(&0u128, &1_000u128, &0u128);
+        let rat_up = client.get_rate_at_target();
+        assert!(rat_up > 45_800u128 && rat_up < 46_000u128);
+        // Averaged over the period, so between the start and end curves.
+        assert!(rate > 160_000u128 && rate < rat_up * 4);
+        // Peeking does not move the stored curve.
+        env.ledger().with_mut(|li| li.timestamp += 86_400);
+        client.peek_borrow_rate(&0u128, &1_000u128, &0u128);
+        assert_eq!(client.get_rate_at_target(), rat_up);
+
+        // Idle market drifts the other way.
+        client.get_borrow_rate(&1_000u128, &0u128, &0u128);
+        assert!(client.get_rate_at_target() < rat_up);
+        // On target the curve holds still.
+        let at_target = client.get_rate_at_target();
+        env.ledger().with_mut(|li| li.timestamp += 86_400);
+        client.get_borrow_rate(&100u128, &900u128, &0u128);
+        assert_eq!(client.get_rate_at_target(), at_target);
+    }
+
+    #[test]
+    fn rate_at_target_is_bounded() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, _) = setup(&env);
+        client.get_borrow_rate(&0u128, &1_000u128, &0u128);
+        env.ledger().with_mut(|li| li.timestamp += 365 * 86_400);
+        client.get_borrow_rate(&0u128, &1_000u128, &0u128);
+        assert_eq!(client.get_rate_at_target(), 2_000_000u128);
+        env.ledger()
+            .with_mut(|li| li.timestamp += 10 * 365 * 86_400);
+        client.get_borrow_rate(&1_000u128, &0u128, &0u128);
+        assert_eq!(client.get_rate_at_target(), 1_000u128);
+    }
+
+    #[test]
+    fn only_market_advances_curve() {
+        let env = Env::default();
+        let admin = Address::from_string(&String::from_str(&env, DEFAULT_INIT_ADMIN));
+        let market = Address::generate(&env);
+        let id = env.register(AdaptiveRateModel, ());
+        let client = AdaptiveRateModelClient::new(&env, &id);
+        env.mock_all_auths();
+        client.initialize(&market, &default_params(), &40_000u128, &admin);
+        env.set_auths(&[]);
+        assert!(client
+            .try_get_borrow_rate(&0u128, &1_000u128, &0u128)
+            .is_err());
+        assert_eq!(
+            client.peek_borrow_rate(&0u128, &1_000u128, &0u128),
+            160_000u128
+        );
+    }
+
+    #[test]
+    fn set_params_clamps_rate_at_target() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, admin) = setup(&env);
+        let mut params = default_params();
+        params.min_rate_at_target = 50_000u128;
+        client.set_params(&admin, &params);
+        assert_eq!(client.get_rate_at_target(), 50_000u128);
+        assert_eq!(client.get_params(), params);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid rate bounds")]
+    fn initialize_rejects_curve_above_rate_cap() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::from_string(&String::from_str(&env, DEFAULT_INIT_ADMIN));
+        let id = env.register(AdaptiveRateModel, ());
+        let client = AdaptiveRateModelClient::new(&env, &id);
+        let mut params = default_params();
+        params.max_rate_at_target = 5_000_000u128;
+        client.initialize(&Address::generate(&env), &params, &40_000u128, &admin);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected admin")]
+    fn initialize_rejects_unexpected_admin() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let id = env.register(AdaptiveRateModel, ());
+        let client = AdaptiveRateModelClient::new(&env, &id);
+        client.initialize(
+            &Address::generate(&env),
+            &default_params(),
+            &40_000u128,
+            &Address::generate(&env),
+        );
+    }
+}
@@ -0,0 +1,614 @@
+#![no_std]
+use soroban_sdk::{
+    contract, contractevent, contractimpl, contracttype, Address, BytesN, Env, String,
+};
+
+const SCALE_1E6: u128 = 1_000_000u128;
+const MAX_RATE_PARAM: u128 = 10_000_000u128;
+pub const DEFAULT_INIT_ADMIN: &str = "GATFXAP3AVUYRJJCXZ65EPVJEWRW6QYE3WOAFEXAIASFGZV7V7HMABPJ";
+const TTL_THRESHOLD: u32 = 500_000;
+const TTL_EXTEND_TO: u32 = 1_000_000;
+const UPGRADE_TIMELOCK_SECS: u64 = 24 * 60 * 60;
+pub const CURVE_CHANGE_DELAY_SECS: u64 = 24 * 60 * 60;
+
+#[contracttype]
+pub enum DataKey {
+    Admin,                       // Address
+    MarketCurve(Address),        // RateCurve per market
+    PendingMarketCurve(Address), // PendingCurve per market
+    PendingUpgradeHash,          // BytesN<32>
+    PendingUpgradeEta,           // u64 unix timestamp
+}
+
+/// `base + util * multiplier`. All params scaled 1e6.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LinearCurve {
+    pub base: u128,
+    pub multiplier: u128,
+}
+
+/// Same shape as `JumpRateModel`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JumpCurve {
+    pub base: u128,
+    pub multiplier: u128,
+    pub jump_multiplier: u128,
+    pub kink: u128,
+}
+
+/// `multiplier` up to `kink_1`, `multiplier_2` up to `kink_2`, then
+/// `jump_multiplier`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TwoKinkCurve {
+    pub base: u128,
+    pub multiplier: u128,
+    pub kink_1: u128,
+    pub multiplier_2: u128,
+    pub kink_2: u128,
+    pub jump_multiplier: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RateCurve {
+    Linear(LinearCurve),
+    Jump(JumpCurve),
+    TwoKink(TwoKinkCurve),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingCurve {
+    pub curve: RateCurve,
+    pub execute_after: u64,
+}
+
+#[contract]
+pub struct InterestModelRegistry;
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegistryInitialized {
+    pub admin: Address,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketCurveUpdated {
+    #[topic]
+    pub market: Address,
+    pub curve: RateCurve,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingMarketCurveUpdated {
+    #[topic]
+    pub market: Address,
+    pub curve: RateCurve,
+    pub execute_after: u64,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingMarketCurveCancelled {
+    #[topic]
+    pub market: Address,
+}
+
+#[contractimpl]
+impl InterestModelRegistry {
+    pub fn initialize(env: Env, admin: Address) {
+        admin.require_auth();
+        if env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::Admin)
+            .is_some()
+        {
+            panic!("already initialized");
+        }
+        assert_expected_admin(&env, &admin);
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        bump_ttl(&env);
+        RegistryInitialized { admin }.publish(&env);
+    }
+
+    pub fn get_borrow_rate(
+        env: Env,
+        market: Address,
+        cash: u128,
+        borrows: u128,
+        reserves: u128,
+    ) -> u128 {
+        ensure_initialized(&env);
+        bump_ttl(&env);
+        let curve = read_curve(&env, &market).expect("market curve not set");
+        borrow_rate(&curve, Self::utilization(cash, borrows, reserves))
+    }
+
+    /// Same as `get_borrow_rate`; curves keep no state between calls.
+    pub fn peek_borrow_rate(
+        env: Env,
+        market: Address,
+        cash: u128,
+        borrows: u128,
+        reserves: u128,
+    ) -> u128 {
+        Self::get_borrow_rate(env, market, cash, borrows, reserves)
+    }
+
+    pub fn get_supply_rate(
+        env: Env,
+        market: Address,
+        cash: u128,
+        borrows: u128,
+        reserves: u128,
+        reserve_factor: u128,
+    ) -> u128 {
+        ensure_initialized(&env);
+        bump_ttl(&env);
+        if reserve_factor > SCALE_1E6 {
+            panic!("invalid reserve_factor");
+        }
+        let one_minus_rf = SCALE_1E6.saturating_sub(reserve_factor);
+        let borrow_rate = Self::get_borrow_rate(env.clone(), market, cash, borrows, reserves);
+        let rate_to_pool = borrow_rate.saturating_mul(one_minus_rf) / SCALE_1E6;
+        let util = Self::utilization(cash, borrows, reserves);
+        util.saturating_mul(rate_to_pool) / SCALE_1E6
+    }
+
+    pub fn get_market_curve(env: Env, market: Address) -> Option<RateCurve> {
+        ensure_initialized(&env);
+        read_curve(&env, &market)
+    }
+
+    pub fn get_pending_curve(env: Env, market: Address) -> Option<PendingCurve> {
+        ensure_initialized(&env);
+        let key = DataKey::PendingMarketCurve(market);
+        let pending = env.storage().persistent().get(&key);
+        if pending.is_some() {
+            env.storage()
+                .persistent()
+                .extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+        }
+        pending
+    }
+
+    /// Admin: assign a market's curve. The first curve applies immediately;
+    /// later changes are staged and applied by repeating the call with the
+    /// same curve once `CURVE_CHANGE_DELAY_SECS` have passed.
+    pub fn set_market_curve(env: Env, admin: Address, market: Address, curve: RateCurve) {
+        require_admin(&env, &admin);
+        validate_curve(&curve);
+        let persistent = env.storage().persistent();
+        let curve_key = DataKey::MarketCurve(market.clone());
+        let pending_key = DataKey::PendingMarketCurve(market.clone());
+        let current = read_curve(&env, &market);
+        if current.as_ref() == Some(&curve) {
+            return;
+        }
+        if current.is_none() {
+            persistent.remove(&pending_key);
+            persistent.set(&curve_key, &curve);
+            bump_curve_ttl(&env, &market);
+            MarketCurveUpdated { market, curve }.publish(&env);
+            return;
+        }
+
+        let now = env.ledger().timestamp();
+        let pending: Option<PendingCurve> = persistent.get(&pending_key);
+        if let Some(pending) = pending {
+            if pending.curve == curve {
+                if now < pending.execute_after {
+                    panic!("market curve timelocked");
+                }
+                persistent.remove(&pending_key);
+                persistent.set(&curve_key, &curve);
+                bump_curve_ttl(&env, &market);
+                MarketCurveUpdated { market, curve }.publish(&env);
+                return;
+            }
+        }
+
+        let execute_after = now.saturating_add(CURVE_CHANGE_DELAY_SECS);
+        persistent.set(
+            &pending_key,
+            &PendingCurve {
+                curve: curve.clone(),
+                execute_after,
+            },
+        );
+        bump_curve_ttl(&env, &market);
+        PendingMarketCurveUpdated {
+            market,
+            curve,
+            execute_after,
+        }
+        .publish(&env);
+    }
+
+    pub fn cancel_market_curve(env: Env, admin: Address, market: Address) {
+        require_admin(&env, &admin);
+        let pending_key = DataKey::PendingMarketCurve(market.clone());
+        if !env.storage().persistent().has(&pending_key) {
+            panic!("no pending curve");
+        }
+        env.storage().persistent().remove(&pending_key);
+        PendingMarketCurveCancelled { market }.publish(&env);
+    }
+
+    pub fn propose_upgrade_wasm(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        require_admin(&env, &admin);
+        let execute_after = env
+            .ledger()
+            .timestamp()
+            .saturating_add(UPGRADE_TIMELOCK_SECS);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingUpgradeHash, &new_wasm_hash);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingUpgradeEta, &execute_after);
+        bump_pending_upgrade_ttl(&env);
+    }
+
+    pub fn upgrade_wasm(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        require_admin(&env, &admin);
+        bump_pending_upgrade_ttl(&env);
+        let pending_hash: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingUpgradeHash)
+            .expect("pending upgrade not set");
+        let execute_after: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingUpgradeEta)
+            .expect("pending upgrade eta not set");
+        if pending_hash != new_wasm_hash {
+            panic!("upgrade hash mismatch");
+        }
+        if env.ledger().timestamp() < execute_after {
+            panic!("upgrade timelocked");
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingUpgradeHash);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingUpgradeEta);
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    fn utilization(cash: u128, borrows: u128, reserves: u128) -> u128 {
+        if borrows == 0 {
+            return 0;
+        }
+        let assets = cash.saturating_add(borrows);
+        if reserves > assets {
+            panic!("reserves exceed total assets");
+        }
+        let denom = assets - reserves;
+        if denom == 0 {
+            return 0;
+        }
+        let util = borrows.saturating_mul(SCALE_1E6) / denom;
+        util.min(SCALE_1E6)
+    }
+}
+
+fn borrow_rate(curve: &RateCurve, util: u128) -> u128 {
+    match curve {
+        RateCurve::Linear(c) => c.base.saturating_add(segment(util, c.multiplier)),
+        RateCurve::Jump(c) => {
+            if util <= c.kink {
+                c.base.saturating_add(segment(util, c.multiplier))
+            } else {
+                c.base
+                    .saturating_add(segment(c.kink, c.multiplier))
+                    .saturating_add(segment(util - c.kink, c.jump_multiplier))
+            }
+        }
+        RateCurve::TwoKink(c) => {
+            if util <= c.kink_1 {
+                c.base.saturating_add(segment(util, c.multiplier))
+            } else if util <= c.kink_2 {
+                c.base
+                    .saturating_add(segment(c.kink_1, c.multiplier))
+                    .saturating_add(segment(util - c.kink_1, c.multiplier_2))
+            } else {
+                c.base
+                    .saturating_add(segment(c.kink_1, c.multiplier))
+                    .saturating_add(segment(c.kink_2 - c.kink_1, c.multiplier_2))
+                    .saturating_add(segment(util - c.kink_2, c.jump_multiplier))
+            }
+        }
+    }
+}
+
+fn segment(util: u128, multiplier: u128) -> u128 {
+    util.saturating_mul(multiplier) / SCALE_1E6
+}
+
+fn validate_curve(curve: &RateCurve) {
+    let (rates_ok, kinks_ok) = match curve {
+        RateCurve::Linear(c) => (
+            c.base <= MAX_RATE_PARAM && c.multiplier <= MAX_RATE_PARAM,
+            true,
+        ),
+        RateCurve::Jump(c) => (
+            c.base <= MAX_RATE_PARAM
+                && c.multiplier <= MAX_RATE_PARAM
+                && c.jump_multiplier <= MAX_RATE_PARAM,
+            c.kink <= SCALE_1E6,
+        ),
+        RateCurve::TwoKink(c) => (
+            c.base <= MAX_RATE_PARAM
+                && c.multiplier <= MAX_RATE_PARAM
+                && c.multiplier_2 <= MAX_RATE_PARAM
+                && c.jump_multiplier <= MAX_RATE_PARAM,
+            c.kink_1 <= c.kink_2 && c.kink_2 <= SCALE_1E6,
+        ),
+    };
+    if !kinks_ok {
+        panic!("invalid kink");
+    }
+    if !rates_ok {
+        panic!("invalid rate params");
+    }
+}
+
+fn read_curve(env: &Env, market: &Address) -> Option<RateCurve> {
+    let curve = env
+        .storage()
+        .persistent()
+        .get(&DataKey::MarketCurve(market.clone()));
+    if curve.is_some() {
+        bump_curve_ttl(env, market);
+    }
+    curve
+}
+
+fn ensure_initialized(env: &Env) {
+    if env
+        .storage()
+        .persistent()
+        .get::<_, Address>(&DataKey::Admin)
+        .is_none()
+    {
+        panic!("registry not initialized");
+    }
+}
+
+fn assert_expected_admin(_env: &Env, _admin: &Address) {
+    let expected_admin_str = expected_admin_config();
+    let expected_admin = Address::from_string(&String::from_str(_env, expected_admin_str));
+    if _admin != &expected_admin {
+        panic!("unexpected admin");
+    }
+}
+
+fn expected_admin_config() -> &'static str {
+    if cfg!(any(
+        test,
+        all(debug_assertions, feature = "test-default-admin")
+    )) {
+        option_env!("INTEREST_MODEL_REGISTRY_INIT_ADMIN").unwrap_or(DEFAULT_INIT_ADMIN)
+    } else {
+        option_env!("INTEREST_MODEL_REGISTRY_INIT_ADMIN")
+            .expect("INTEREST_MODEL_REGISTRY_INIT_ADMIN must be set at build time")
+    }
+}
+
+fn require_admin(env: &Env, admin: &Address) {
+    let stored: Address = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Admin)
+        .expect("admin not set");
+    bump_ttl(env);
+    if stored != *admin {
+        panic!("not admin");
+    }
+    admin.require_auth();
+}
+
+fn bump_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    if persistent.has(&DataKey::Admin) {
+        persistent.extend_ttl(&DataKey::Admin, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+fn bump_curve_ttl(env: &Env, market: &Address) {
+    let persistent = env.storage().persistent();
+    let curve_key = DataKey::MarketCurve(market.clone());
+    if persistent.has(&curve_key) {
+        persistent.extend_ttl(&curve_key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    let pending_key = DataKey::PendingMarketCurve(market.clone());
+    if persistent.has(&pending_key) {
+        persistent.extend_ttl(&pending_key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+fn bump_pending_upgrade_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    if persistent.has(&DataKey::PendingUpgradeHash) {
+        persistent.extend_ttl(&DataKey::PendingUpgradeHash, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    if persistent.has(&DataKey::PendingUpgradeEta) {
+        persistent.extend_ttl(&DataKey::PendingUpgradeEta, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Ledger};
+
+    fn setup(env: &Env) -> (InterestModelRegistryClient<'_>, Address) {
+        let admin = Address::from_string(&String::from_str(env, DEFAULT_INIT_ADMIN));
+        let id = env.register(InterestModelRegistry, ());
+        let client = InterestModelRegistryClient::new(env, &id);
+        client.initialize(&admin);
+        (client, admin)
+    }
+
+    fn jump_curve() -> RateCurve {
+        RateCurve::Jump(JumpCurve {
+            base: 20_000u128,
+            multiplier: 180_000u128,
+            jump_multiplier: 4_000_000u128,
+            kink: 800_000u128,
+        })
+    }
+
+    #[test]
+    fn markets_price_their_own_curves() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, admin) = setup(&env);
+        let linear_market = Address::generate(&env);
+        let jump_market = Address::generate(&env);
+        let two_kink_market = Address::generate(&env);
+        client.set_market_curve(
+            &admin,
+            &linear_market,
+            &RateCurve::Linear(LinearCurve {
+                base: 10_000u128,
+                multiplier: 100_000u128,
+            }),
+        );
+        client.set_market_curve(&admin, &jump_market, &jump_curve());
+        client.set_market_curve(
+            &admin,
+            &two_kink_market,
+            &RateCurve::TwoKink(TwoKinkCurve {
+                base: 0u128,
+                multiplier: 50_000u128,
+                kink_1: 400_000u128,
+                multiplier_2: 200_000u128,
+                kink_2: 800_000u128,
+                jump_multiplier: 2_000_000u128,
+            }),
+        );
+
+        // 90% utilization
+        assert_eq!(
+            client.get_borrow_rate(&linear_market, &100u128, &900u128, &0u128),
+            100_000u128
+        );
+        // 2% + 80% * 18% + 10% * 400%
+        assert_eq!(
+            client.get_borrow_rate(&jump_market, &100u128, &900u128, &0u128),
+            564_000u128
+        );
+        // 40% * 5% + 40% * 20% + 10% * 200%
+        assert_eq!(
+            client.get_borrow_rate(&two_kink_market, &100u128, &900u128, &0u128),
+            300_000u128
+        );
+        // Between the kinks: 2% + 10% * 20%
+        assert_eq!(
+            client.get_borrow_rate(&two_kink_market, &500u128, &500u128, &0u128),
+            40_000u128
+        );
+        let sr = client.get_supply_rate(&jump_market, &100u128, &900u128, &0u128, &100_000u128);
+        assert_eq!(sr, 456_840u128);
+    }
+
+    #[test]
+    fn curve_changes_are_timelocked() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, admin) = setup(&env);
+        let market = Address::generate(&env);
+        client.set_market_curve(&admin, &market, &jump_curve());
+
+        let lower = RateCurve::Linear(LinearCurve {
+            base: 0u128,
+            multiplier: 100_000u128,
+        });
+        client.set_market_curve(&admin, &market, &lower);
+        assert_eq!(client.get_market_curve(&market), Some(jump_curve()));
+        let pending = client.get_pending_curve(&market).unwrap();
+        assert_eq!(pending.curve, lower);
+        assert_eq!(
+            pending.execute_after,
+            env.ledger().timestamp() + CURVE_CHANGE_DELAY_SECS
+        );
+        assert!(client.try_set_market_curve(&admin, &market, &lower).is_err());
+
+        env.ledger()
+            .with_mut(|li| li.timestamp += CURVE_CHANGE_DELAY_SECS);
+        client.set_market_curve(&admin, &market, &lower);
+        assert_eq!(client.get_market_curve(&market), Some(lower));
+        assert_eq!(client.get_pending_curve(&market), None);
+    }
+
+    #[test]
+    fn cancel_drops_pending_curve() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, admin) = setup(&env);
+        let market = Address::generate(&env);
+        client.set_market_curve(&admin, &market, &jump_curve());
+        let lower = RateCurve::Linear(LinearCurve {
+            base: 0u128,
+            multiplier: 100_000u128,
+        });
+        client.set_market_curve(&admin, &market, &lower);
+        client.cancel_market_curve(&admin, &market);
+        assert_eq!(client.get_pending_curve(&market), None);
+
+        // A fresh proposal restarts the delay.
+        env.ledger()
+            .with_mut(|li| li.timestamp += CURVE_CHANGE_DELAY_SECS);
+        client.set_market_curve(&admin, &market, &lower);
+        assert_eq!(client.get_market_curve(&market), Some(jump_curve()));
+    }
+
+    #[test]
+    #[should_panic(expected = "market curve not set")]
+    fn unknown_market_has_no_rate() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, _) = setup(&env);
+        client.get_borrow_rate(&Address::generate(&env), &100u128, &900u128, &0u128);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid kink")]
+    fn set_market_curve_rejects_unordered_kinks() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, admin) = setup(&env);
+        client.set_market_curve(
+            &admin,
+            &Address::generate(&env),
+            &RateCurve::TwoKink(TwoKinkCurve {
+                base: 0u128,
+                multiplier: 50_000u128,
+                kink_1: 800_000u128,
+                multiplier_2: 200_000u128,
+                kink_2: 400_000u128,
+                jump_multiplier: 2_000_000u128,
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected admin")]
+    fn initialize_rejects_unexpected_admin() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let id = env.register(InterestModelRegistry, ());
+        let client = InterestModelRegistryClient::new(&env, &id);
+        client.initialize(&Address::generate(&env));
+    }
+}
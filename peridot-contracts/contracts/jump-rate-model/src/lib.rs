@@ -121,6 +121,11 @@ impl JumpRateModel {
         }
     }
 
+    /// Same as `get_borrow_rate`; this curve keeps no state between calls.
+    pub fn peek_borrow_rate(env: Env, cash: u128, borrows: u128, reserves: u128) -> u128 {
+        Self::get_borrow_rate(env, cash, borrows, reserves)
+    }
+
     pub fn get_supply_rate(
         env: Env,
         cash: u128,
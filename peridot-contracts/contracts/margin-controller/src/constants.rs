@@ -1,4 +1,5 @@
 pub const SCALE_1E6: u128 = 1_000_000u128;
+pub const EXCHANGE_RATE_SCALE: u128 = 1_000_000_000_000_000_000u128; // vault rates are 1e18
 pub const DEFAULT_MAX_SLIPPAGE_BPS: u128 = 50_000u128; // 5%
 pub const MAX_SLIPPAGE_BPS_CAP: u128 = 500_000u128; // 50%
 pub const MAX_USER_POSITIONS: u32 = 64;
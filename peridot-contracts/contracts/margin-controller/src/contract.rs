@@ -173,8 +173,8 @@ impl MarginController {
         if pbal == 0 {
             return 0;
         }
-        let rate = ReceiptVaultClient::new(&env, &vault).get_exchange_rate();
-        pbal.saturating_mul(rate) / SCALE_1E6
+        let rate = ReceiptVaultClient::new(&env, &vault).get_exchange_rate_1e18();
+        ptokens_to_underlying(&env, pbal, rate)
     }
 
     pub fn propose_upgrade_wasm(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
@@ -466,8 +466,8 @@ impl MarginController {
         if debt_price.0 == 0 || debt_price.1 == 0 {
             panic!("invalid debt price");
         }
-        let coll_rate = ReceiptVaultClient::new(&env, &collateral_vault).get_exchange_rate();
-        let collateral_underlying = collateral_ptokens.saturating_mul(coll_rate) / SCALE_1E6;
+        let coll_rate = ReceiptVaultClient::new(&env, &collateral_vault).get_exchange_rate_1e18();
+        let collateral_underlying = ptokens_to_underlying(&env, collateral_ptokens, coll_rate);
         let collateral_value = collateral_underlying.saturating_mul(coll_price.0) / coll_price.1;
         let target_value = collateral_value.saturating_mul(leverage);
         let borrow_value = target_value.saturating_sub(collateral_value);
@@ -988,9 +988,9 @@ impl MarginController {
             panic!("invalid market cf");
         }
         let exchange_rate =
-            ReceiptVaultClient::new(&env, &vaults.position_vault).get_exchange_rate();
+            ReceiptVaultClient::new(&env, &vaults.position_vault).get_exchange_rate_1e18();
         let collateral_underlying =
-            position.collateral_ptokens.saturating_mul(exchange_rate) / SCALE_1E6;
+            ptokens_to_underlying(&env, position.collateral_ptokens, exchange_rate);
         let collateral_value_raw =
             collateral_underlying.saturating_mul(coll_price.0) / coll_price.1;
         let collateral_value = collateral_value_raw.saturating_mul(collateral_cf) / SCALE_1E6;
@@ -1113,13 +1113,13 @@ impl MarginController {
         let debt_price = get_price_usd(&env, &position.debt_asset);
         let coll_price = get_price_usd(&env, &position.collateral_asset);
         let exchange_rate =
-            ReceiptVaultClient::new(&env, &vaults.position_vault).get_exchange_rate();
+            ReceiptVaultClient::new(&env, &vaults.position_vault).get_exchange_rate_1e18();
         let collateral_cf = get_peridottroller(&env).get_market_cf(&vaults.position_vault);
         if collateral_cf > SCALE_1E6 {
             panic!("invalid market cf");
         }
         let collateral_underlying =
-            position.collateral_ptokens.saturating_mul(exchange_rate) / SCALE_1E6;
+            ptokens_to_underlying(&env, position.collateral_ptokens, exchange_rate);
         let collateral_value_raw =
             collateral_underlying.saturating_mul(coll_price.0) / coll_price.1;
         let collateral_value = collateral_value_raw.saturating_mul(collateral_cf) / SCALE_1E6;
@@ -1135,7 +1135,7 @@ impl MarginController {
             .saturating_mul(coll_price.1)
             / coll_price.0
             / SCALE_1E6;
-        let mut seize_ptokens = seize_underlying.saturating_mul(SCALE_1E6) / exchange_rate;
+        let mut seize_ptokens = underlying_to_ptokens(&env, seize_underlying, exchange_rate);
         if seize_ptokens == 0 {
             seize_ptokens = 1;
         }
@@ -1179,6 +1179,188 @@ impl MarginController {
         remove_user_position(&env, &position.owner, position_id);
     }
 
+    /// Deleverage a spot borrow by selling collateral into the debt asset.
+    /// The collateral is redeemed with its health check deferred and swapped with an
+    /// oracle-derived floor; the swap output repays up to `repay_amount`, so no spare
+    /// debt-asset balance is needed. Account liquidity is checked once at the end.
+    /// A vault flash loan cannot front the repay: its `on_flash_loan` callback would
+    /// have to re-enter the lending vault. Returns the debt-asset surplus left with the user.
+    pub fn repay_with_collateral(
+        env: Env,
+        user: Address,
+        collateral_asset: Address,
+        debt_asset: Address,
+        repay_amount: u128,
+        collateral_ptokens: u128,
+        swaps_chain: Vec<(Vec<Address>, BytesN<32>, Address)>,
+    ) -> u128 {
+        bump_core_ttl(&env);
+        user.require_auth();
+        if repay_amount == 0 || collateral_ptokens == 0 {
+            panic!("bad amount");
+        }
+        if collateral_asset == debt_asset {
+            panic!("same asset");
+        }
+        let collateral_vault = get_market(&env, &collateral_asset);
+        let debt_vault = get_market(&env, &debt_asset);
+        let swap_adapter = get_swap_adapter(&env);
+        validate_swaps_chain(
+            &env,
+            &swap_adapter,
+            &swaps_chain,
+            &collateral_asset,
+            &debt_asset,
+        );
+
+        let collateral_client = ReceiptVaultClient::new(&env, &collateral_vault);
+        let current_ptokens = collateral_client.get_ptoken_balance(&user);
+        if current_ptokens < collateral_ptokens {
+            panic!("Insufficient pTokens");
+        }
+        let locked =
+            Self::locked_ptokens_in_market(env.clone(), user.clone(), collateral_vault.clone());
+        if current_ptokens.saturating_sub(collateral_ptokens) < locked {
+            panic!("collateral locked");
+        }
+
+        let collateral_token = token::TokenClient::new(&env, &collateral_asset);
+        let bal_before = collateral_token.balance(&user);
+        let controller = env.current_contract_address();
+        Self::begin_margin_withdraw_if_supported(&env, &collateral_vault, &user);
+        collateral_client.defer_withdraw_health_check(&controller, &user);
+        collateral_client.withdraw(&user, &collateral_ptokens);
+        let bal_after = collateral_token.balance(&user);
+        let collateral_underlying = if bal_after <= bal_before {
+            0u128
+        } else {
+            (bal_after - bal_before) as u128
+        };
+        if collateral_underlying == 0 {
+            panic!("zero collateral");
+        }
+        let min_out_oracle =
+            Self::oracle_min_out(&env, &collateral_asset, &debt_asset, collateral_underlying);
+        let received = SwapAdapterClient::new(&env, &swap_adapter).swap_chained(
+            &user,
+            &swaps_chain,
+            &collateral_asset,
+            &collateral_underlying,
+            &min_out_oracle,
+        );
+        if received < min_out_oracle {
+            panic!("slippage too high");
+        }
+
+        let debt_client = ReceiptVaultClient::new(&env, &debt_vault);
+        let debt_token = token::TokenClient::new(&env, &debt_asset);
+        let debt_bal_before = debt_token.balance(&user);
+        debt_client.repay(&user, &repay_amount.min(received));
+        let debt_bal_after = debt_token.balance(&user);
+        let repaid = if debt_bal_after >= debt_bal_before {
+            0u128
+        } else {
+            (debt_bal_before - debt_bal_after) as u128
+        };
+        if repaid == 0 {
+            panic!("no debt");
+        }
+
+        let (_, shortfall) = get_peridottroller(&env).account_liquidity(&user);
+        if shortfall > 0 {
+            panic!("Insufficient collateral");
+        }
+        received - repaid
+    }
+
+    /// Move spot collateral between markets in one call: redeem from `from_market`,
+    /// swap through the swap adapter, and deposit into `to_market`. Redeem health gating
+    /// is deferred and account liquidity is checked once at the end.
+    /// Returns the pTokens minted in `to_market`.
+    pub fn swap_collateral(
+        env: Env,
+        user: Address,
+        from_market: Address,
+        to_market: Address,
+        ptoken_amount: u128,
+        swaps_chain: Vec<(Vec<Address>, BytesN<32>, Address)>,
+        min_out: u128,
+    ) -> u128 {
+        bump_core_ttl(&env);
+        user.require_auth();
+        if ptoken_amount == 0 {
+            panic!("bad amount");
+        }
+        if min_out == 0 {
+            panic!("bad slippage");
+        }
+        if from_market == to_market {
+            panic!("same market");
+        }
+        let from_client = ReceiptVaultClient::new(&env, &from_market);
+        let to_client = ReceiptVaultClient::new(&env, &to_market);
+        let from_asset = from_client.get_underlying_token();
+        let to_asset = to_client.get_underlying_token();
+        if get_market(&env, &from_asset) != from_market || get_market(&env, &to_asset) != to_market
+        {
+            panic!("unsupported market");
+        }
+        let swap_adapter = get_swap_adapter(&env);
+        validate_swaps_chain(&env, &swap_adapter, &swaps_chain, &from_asset, &to_asset);
+
+        let current_ptokens = from_client.get_ptoken_balance(&user);
+        if current_ptokens < ptoken_amount {
+            panic!("Insufficient pTokens");
+        }
+        let locked = Self::locked_ptokens_in_market(env.clone(), user.clone(), from_market.clone());
+        if current_ptokens.saturating_sub(ptoken_amount) < locked {
+            panic!("collateral locked");
+        }
+
+        let from_token = token::TokenClient::new(&env, &from_asset);
+        let bal_before = from_token.balance(&user);
+        let controller = env.current_contract_address();
+        Self::begin_margin_withdraw_if_supported(&env, &from_market, &user);
+        from_client.defer_withdraw_health_check(&controller, &user);
+        from_client.withdraw(&user, &ptoken_amount);
+        let bal_after = from_token.balance(&user);
+        let redeemed = if bal_after <= bal_before {
+            0u128
+        } else {
+            (bal_after - bal_before) as u128
+        };
+        if redeemed == 0 {
+            panic!("zero collateral");
+        }
+
+        let min_out_oracle = Self::oracle_min_out(&env, &from_asset, &to_asset, redeemed);
+        if min_out < min_out_oracle {
+            panic!("slippage too high");
+        }
+        let received = SwapAdapterClient::new(&env, &swap_adapter).swap_chained(
+            &user,
+            &swaps_chain,
+            &from_asset,
+            &redeemed,
+            &min_out,
+        );
+        if received < min_out {
+            panic!("slippage too high");
+        }
+
+        let peridottroller = get_peridottroller(&env);
+        peridottroller.enter_market(&user, &to_market);
+        let p_before = to_client.get_ptoken_balance(&user);
+        to_client.deposit(&user, &received);
+        let minted = to_client.get_ptoken_balance(&user).saturating_sub(p_before);
+
+        let (_, shortfall) = peridottroller.account_liquidity(&user);
+        if shortfall > 0 {
+            panic!("Insufficient collateral");
+        }
+        minted
+    }
+
     pub fn locked_ptokens_in_market(env: Env, user: Address, market: Address) -> u128 {
         bump_core_ttl(&env);
         let position_ids = compact_user_positions(&env, &user);
@@ -1281,9 +1463,9 @@ impl MarginController {
             return u128::MAX;
         }
         let exchange_rate =
-            ReceiptVaultClient::new(&env, &vaults.position_vault).get_exchange_rate();
+            ReceiptVaultClient::new(&env, &vaults.position_vault).get_exchange_rate_1e18();
         let collateral_underlying =
-            position.collateral_ptokens.saturating_mul(exchange_rate) / SCALE_1E6;
+            ptokens_to_underlying(&env, position.collateral_ptokens, exchange_rate);
         let collateral_value_raw =
             collateral_underlying.saturating_mul(coll_price.0) / coll_price.1;
         let collateral_value = collateral_value_raw.saturating_mul(collateral_cf) / SCALE_1E6;
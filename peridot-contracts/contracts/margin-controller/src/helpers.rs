@@ -1,4 +1,4 @@
-use soroban_sdk::{Address, BytesN, Env, Vec};
+use soroban_sdk::{Address, BytesN, Env, Vec, U256};
 
 use crate::constants::*;
 use crate::storage::*;
@@ -10,6 +10,27 @@ pub struct PositionVaults {
     pub position_vault: Address,
 }
 
+/// Underlying value of `ptokens` at a vault's 1e18-scaled exchange rate.
+pub fn ptokens_to_underlying(env: &Env, ptokens: u128, rate: u128) -> u128 {
+    U256::from_u128(env, ptokens)
+        .mul(&U256::from_u128(env, rate))
+        .div(&U256::from_u128(env, EXCHANGE_RATE_SCALE))
+        .to_u128()
+        .expect("exchange rate overflow")
+}
+
+/// pTokens worth `amount` underlying at a vault's 1e18-scaled exchange rate.
+pub fn underlying_to_ptokens(env: &Env, amount: u128, rate: u128) -> u128 {
+    if rate == 0 {
+        panic!("invalid exchange rate");
+    }
+    U256::from_u128(env, amount)
+        .mul(&U256::from_u128(env, EXCHANGE_RATE_SCALE))
+        .div(&U256::from_u128(env, rate))
+        .to_u128()
+        .expect("exchange rate overflow")
+}
+
 pub fn next_position_id(env: &Env) -> u64 {
     let mut id: u64 = env
         .storage()
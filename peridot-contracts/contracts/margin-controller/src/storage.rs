@@ -14,8 +14,9 @@ pub trait ReceiptVaultContract {
     fn init_margin_borrow_state(env: Env, position_id: u64);
     fn borrow_for_margin(env: Env, position_id: u64, receiver: Address, amount: u128);
     fn repay_for_margin(env: Env, position_id: u64, payer: Address, amount: u128);
+    fn defer_withdraw_health_check(env: Env, margin_controller: Address, user: Address);
     fn get_underlying_token(env: Env) -> Address;
-    fn get_exchange_rate(env: Env) -> u128;
+    fn get_exchange_rate_1e18(env: Env) -> u128;
     fn get_ptoken_balance(env: Env, user: Address) -> u128;
     fn get_user_borrow_balance(env: Env, user: Address) -> u128;
     fn get_margin_borrow_balance(env: Env, position_id: u64) -> u128;
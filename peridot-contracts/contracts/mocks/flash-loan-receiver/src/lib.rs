@@ -1,7 +1,7 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Bytes, Env, IntoVal, Symbol, Val,
+    contract, contractimpl, contracttype, token, Address, Bytes, BytesN, Env, IntoVal, Symbol, Val,
 };
 
 #[contracttype]
@@ -24,11 +24,23 @@ impl FlashLoanReceiver {
         let _: Val = env.invoke_contract(
             &vault,
             &Symbol::new(&env, "flash_loan"),
-            (receiver, amount, data).into_val(&env),
+            (receiver.clone(), receiver, amount, data).into_val(&env),
         );
     }
 
-    pub fn on_flash_loan(env: Env, vault: Address, amount: u128, fee: u128, _data: Bytes) {
+    pub fn on_flash_loan(
+        env: Env,
+        vault: Address,
+        initiator: Address,
+        _token: Address,
+        amount: u128,
+        fee: u128,
+        _data: Bytes,
+    ) -> BytesN<32> {
+        // Only honour loans this contract started itself.
+        if initiator != env.current_contract_address() {
+            panic!("untrusted initiator");
+        }
         let token: Address = env
             .storage()
             .persistent()
@@ -41,5 +53,11 @@ impl FlashLoanReceiver {
             &vault,
             &total_i128,
         );
+        env.crypto()
+            .keccak256(&Bytes::from_slice(
+                &env,
+                b"ERC3156FlashBorrower.onFlashLoan",
+            ))
+            .to_bytes()
     }
 }
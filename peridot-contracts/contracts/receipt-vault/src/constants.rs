@@ -2,7 +2,23 @@ pub const SCALE_1E6: u128 = 1_000_000u128;
 pub const INDEX_SCALE_1E18: u128 = 1_000_000_000_000_000_000u128; // 1e18
 pub const PTOKEN_DECIMALS: u32 = 6;
 pub const MAX_YEARLY_RATE_SCALED: u128 = 10_000_000u128; // 1000% APY cap to prevent overflow
+pub const SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
 pub const UPGRADE_TIMELOCK_SECS: u64 = 24 * 60 * 60;
+pub const RATE_CHECKPOINT_INTERVAL_SECS: u64 = 60 * 60;
+// 8 days at the default interval, enough for a trailing 7-day average.
+pub const MAX_RATE_CHECKPOINTS: u32 = 192;
 // DeFindex rejects deposits that cannot mint a non-zero share. Keep dust in
 // live cash so rebalance/deposit paths remain idempotent around the target.
 pub const MIN_BOOSTED_DEPLOY_AMOUNT: u128 = 10_000u128;
+// ERC-3156 borrowers return keccak256 of this message from `on_flash_loan`.
+pub const FLASH_CALLBACK_MESSAGE: &[u8] = b"ERC3156FlashBorrower.onFlashLoan";
+pub const MAX_TERM_LOANS_PER_USER: u32 = 8;
+pub const MAX_TERM_CURVE_POINTS: u32 = 8;
+pub const ADMIN_PARAM_CHANGE_DELAY_SECS: u64 = 24 * 60 * 60;
+// Largest move a single staged change may make per parameter.
+pub const MAX_RESERVE_FACTOR_STEP: u128 = 250_000u128;
+pub const MAX_FLASH_LOAN_FEE_STEP: u128 = 50_000u128;
+pub const MAX_RATE_STEP: u128 = 500_000u128;
+pub const MAX_CAP_STEP_MULTIPLIER: u128 = 2;
+// Live withdraw requests; every fill pass pays all of them.
+pub const MAX_WITHDRAW_QUEUE_LEN: u32 = 16;
@@ -1,7 +1,7 @@
 use soroban_sdk::{
     auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
-    contract, contractimpl, token, Address, Bytes, Env, IntoVal, MuxedAddress, String, Symbol, Val,
-    Vec,
+    contract, contractimpl, token, Address, Bytes, BytesN, Env, IntoVal, MuxedAddress, String,
+    Symbol, Val, Vec, U256,
 };
 use stellar_tokens::fungible::burnable::emit_burn;
 use stellar_tokens::fungible::Base as TokenBase;
@@ -17,6 +17,7 @@ pub struct ReceiptVault;
 const BOOSTED_CACHE_MAX_AGE_SECS: u64 = 60 * 60;
 const BPS_SCALE: u128 = 10_000u128;
 const BOOSTED_MODEL_CASH_TOLERANCE_BPS: u128 = 500u128; // 5%
+const BOOSTED_LOSS_TOLERANCE_BPS: u128 = 10u128; // 0.1%, plus one unit of rounding slack
 
 #[contractimpl]
 impl ReceiptVault {
@@ -72,74 +73,199 @@ impl ReceiptVault {
             .saturating_sub(tracked_cash)
     }
 
-    fn get_boosted_underlying(env: &Env) -> u128 {
-        if let Some(boosted) = env
+    /// Registered boosted strategies in withdrawal priority order.
+    ///
+    /// `BoostedVault` mirrors the first strategy, so markets without boosting
+    /// only pay for that single read. Markets configured before the registry
+    /// existed surface their vault as one uncapped strategy.
+    fn strategies(env: &Env) -> Vec<StrategyConfig> {
+        let Some(primary) = env
             .storage()
             .persistent()
             .get::<_, Address>(&DataKey::BoostedVault)
+        else {
+            return Vec::new(env);
+        };
+        if let Some(strategies) = env
+            .storage()
+            .persistent()
+            .get::<_, Vec<StrategyConfig>>(&DataKey::Strategies)
         {
-            let shares_i =
-                token::TokenClient::new(env, &boosted).balance(&env.current_contract_address());
-            if shares_i > 0 {
-                match try_call_contract::<Vec<i128>, _>(
-                    env,
-                    &boosted,
-                    "get_asset_amounts_per_shares",
-                    (shares_i,),
-                ) {
-                    Ok(amounts) => {
-                        let amt_i = amounts.get(0).unwrap_or(0);
-                        if amt_i <= 0 {
-                            let cached: u128 = env
-                                .storage()
-                                .persistent()
-                                .get(&DataKey::BoostedUnderlyingCached)
-                                .unwrap_or(0u128);
-                            let estimated = Self::estimate_boosted_underlying_from_accounting(env);
-                            return cached.max(estimated);
-                        }
-                        let boosted_underlying = amt_i as u128;
-                        env.storage()
-                            .persistent()
-                            .set(&DataKey::BoostedUnderlyingCached, &boosted_underlying);
-                        env.storage().persistent().set(
-                            &DataKey::BoostedUnderlyingUpdatedAt,
-                            &env.ledger().timestamp(),
-                        );
-                        boosted_underlying
-                    }
-                    Err(err) => {
-                        emit_external_call_failure(env, &boosted, &err, true);
-                        let now = env.ledger().timestamp();
-                        let cached: Option<u128> = env
-                            .storage()
-                            .persistent()
-                            .get(&DataKey::BoostedUnderlyingCached);
-                        let updated_at: Option<u64> = env
-                            .storage()
-                            .persistent()
-                            .get(&DataKey::BoostedUnderlyingUpdatedAt);
-                        if let (Some(cached), Some(updated_at)) = (cached, updated_at) {
-                            if now.saturating_sub(updated_at) <= BOOSTED_CACHE_MAX_AGE_SECS {
-                                return cached;
-                            }
-                            // When cache is stale and external reads fail, avoid dropping to an
-                            // accounting estimate that can be materially lower than the last
-                            // observed boosted value.
-                            let estimated = Self::estimate_boosted_underlying_from_accounting(env);
-                            return cached.max(estimated);
-                        }
-                        Self::estimate_boosted_underlying_from_accounting(env)
+            bump_strategies_ttl(env);
+            return strategies;
+        }
+        let mut strategies = Vec::new(env);
+        strategies.push_back(StrategyConfig {
+            vault: primary,
+            target_bps: BPS_SCALE as u32,
+            cap: 0,
+        });
+        strategies
+    }
+
+    fn write_strategies(env: &Env, strategies: &Vec<StrategyConfig>) {
+        let storage = env.storage().persistent();
+        match strategies.first() {
+            Some(primary) => {
+                storage.set(&DataKey::BoostedVault, &primary.vault);
+                storage.set(&DataKey::Strategies, strategies);
+            }
+            None => {
+                storage.remove(&DataKey::BoostedVault);
+                storage.remove(&DataKey::Strategies);
+            }
+        }
+    }
+
+    /// Last observed underlying for one strategy and when it was observed.
+    /// A lone strategy without its own entry falls back to the market-wide
+    /// cache written before per-strategy accounting existed.
+    fn strategy_cache(
+        env: &Env,
+        strategies: &Vec<StrategyConfig>,
+        vault: &Address,
+    ) -> (Option<u128>, Option<u64>) {
+        bump_strategy_cache_ttl(env, vault);
+        let storage = env.storage().persistent();
+        let cached: Option<u128> = storage.get(&DataKey::StrategyCached(vault.clone()));
+        if cached.is_none() && strategies.len() == 1 {
+            return (
+                storage.get(&DataKey::BoostedUnderlyingCached),
+                storage.get(&DataKey::BoostedUnderlyingUpdatedAt),
+            );
+        }
+        (
+            cached,
+            storage.get(&DataKey::StrategyCachedAt(vault.clone())),
+        )
+    }
+
+    /// Accounting estimate for one strategy: the market-wide estimate less
+    /// what the other strategies were last seen holding.
+    fn estimate_strategy_underlying_from_accounting(
+        env: &Env,
+        strategies: &Vec<StrategyConfig>,
+        vault: &Address,
+    ) -> u128 {
+        let mut estimate = Self::estimate_boosted_underlying_from_accounting(env);
+        for other in strategies.iter() {
+            if other.vault != *vault {
+                let (cached, _) = Self::strategy_cache(env, strategies, &other.vault);
+                estimate = estimate.saturating_sub(cached.unwrap_or(0u128));
+            }
+        }
+        estimate
+    }
+
+    /// Underlying held by one strategy. Returns whether the value is a fresh
+    /// quote that was written to the strategy cache.
+    fn strategy_underlying(
+        env: &Env,
+        strategies: &Vec<StrategyConfig>,
+        vault: &Address,
+    ) -> (u128, bool) {
+        let shares_i = token::TokenClient::new(env, vault).balance(&env.current_contract_address());
+        if shares_i <= 0 {
+            return (0u128, false);
+        }
+        match try_call_contract::<Vec<i128>, _>(
+            env,
+            vault,
+            "get_asset_amounts_per_shares",
+            (shares_i,),
+        ) {
+            Ok(amounts) => {
+                let amt_i = amounts.get(0).unwrap_or(0);
+                if amt_i <= 0 {
+                    let (cached, _) = Self::strategy_cache(env, strategies, vault);
+                    let estimated =
+                        Self::estimate_strategy_underlying_from_accounting(env, strategies, vault);
+                    return (cached.unwrap_or(0u128).max(estimated), false);
+                }
+                let strategy_underlying = amt_i as u128;
+                env.storage().persistent().set(
+                    &DataKey::StrategyCached(vault.clone()),
+                    &strategy_underlying,
+                );
+                env.storage().persistent().set(
+                    &DataKey::StrategyCachedAt(vault.clone()),
+                    &env.ledger().timestamp(),
+                );
+                (strategy_underlying, true)
+            }
+            Err(err) => {
+                emit_external_call_failure(env, vault, &err, true);
+                let now = env.ledger().timestamp();
+                if let (Some(cached), Some(updated_at)) =
+                    Self::strategy_cache(env, strategies, vault)
+                {
+                    if now.saturating_sub(updated_at) <= BOOSTED_CACHE_MAX_AGE_SECS {
+                        return (cached, false);
                     }
+                    // When cache is stale and external reads fail, avoid dropping to an
+                    // accounting estimate that can be materially lower than the last
+                    // observed boosted value.
+                    let estimated =
+                        Self::estimate_strategy_underlying_from_accounting(env, strategies, vault);
+                    return (cached.max(estimated), false);
                 }
-            } else {
-                0u128
+                (
+                    Self::estimate_strategy_underlying_from_accounting(env, strategies, vault),
+                    false,
+                )
             }
-        } else {
-            0u128
         }
     }
 
+    /// Underlying held by each strategy, in registry order, and whether it is a
+    /// fresh quote. Any fresh quote also refreshes the market-wide cache as the
+    /// sum of strategy caches.
+    fn strategy_quotes(env: &Env, strategies: &Vec<StrategyConfig>) -> Vec<(u128, bool)> {
+        let mut quotes = Vec::new(env);
+        let mut refreshed = false;
+        for strategy in strategies.iter() {
+            let (balance, fresh) = Self::strategy_underlying(env, strategies, &strategy.vault);
+            refreshed |= fresh;
+            quotes.push_back((balance, fresh));
+        }
+        if refreshed {
+            let mut cached_total = 0u128;
+            for strategy in strategies.iter() {
+                let (cached, _) = Self::strategy_cache(env, strategies, &strategy.vault);
+                cached_total = cached_total.saturating_add(cached.unwrap_or(0u128));
+            }
+            env.storage()
+                .persistent()
+                .set(&DataKey::BoostedUnderlyingCached, &cached_total);
+            env.storage().persistent().set(
+                &DataKey::BoostedUnderlyingUpdatedAt,
+                &env.ledger().timestamp(),
+            );
+        }
+        quotes
+    }
+
+    /// Underlying held by each strategy, in registry order.
+    fn strategy_balances(env: &Env, strategies: &Vec<StrategyConfig>) -> Vec<u128> {
+        let mut balances = Vec::new(env);
+        for (balance, _) in Self::strategy_quotes(env, strategies).iter() {
+            balances.push_back(balance);
+        }
+        balances
+    }
+
+    fn get_boosted_underlying(env: &Env) -> u128 {
+        let strategies = Self::strategies(env);
+        if strategies.is_empty() {
+            return 0u128;
+        }
+        let mut total = 0u128;
+        for balance in Self::strategy_balances(env, &strategies).iter() {
+            total = total.saturating_add(balance);
+        }
+        total
+    }
+
     fn derive_managed_cash(env: &Env) -> u128 {
         let storage = env.storage().persistent();
         let total_deposited: u128 = storage.get(&DataKey::TotalDeposited).unwrap_or(0u128);
@@ -157,10 +283,15 @@ impl ReceiptVault {
     fn current_live_cash(env: &Env, token_address: &Address) -> u128 {
         let cash_i = token_balance(env, token_address, &env.current_contract_address());
         if cash_i < 0 {
-            0u128
-        } else {
-            cash_i as u128
+            return 0u128;
         }
+        // Underlying held for delayed claims already belongs to its recipients.
+        let delayed: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalDelayedOutflows)
+            .unwrap_or(0u128);
+        (cash_i as u128).saturating_sub(delayed)
     }
 
     fn idle_cash_buffer_bps(env: &Env) -> u32 {
@@ -171,22 +302,106 @@ impl ReceiptVault {
         value.unwrap_or(0u32)
     }
 
+    /// Spread up to `amount` of live cash across strategies toward their
+    /// target weights, never pushing a strategy past its cap.
     fn deposit_into_boosted(env: &Env, token_address: &Address, amount: u128) -> u128 {
         if amount == 0 {
             return 0u128;
         }
-        let Some(boosted) = env
-            .storage()
-            .persistent()
-            .get::<_, Address>(&DataKey::BoostedVault)
-        else {
+        let strategies = Self::strategies(env);
+        if strategies.is_empty() || Self::boosted_impaired(env) {
             return 0u128;
-        };
+        }
         let available_cash = Self::current_live_cash(env, token_address);
-        let deploy_amount = amount.min(available_cash);
-        if deploy_amount == 0 {
+        let budget = amount.min(available_cash);
+        if budget < MIN_BOOSTED_DEPLOY_AMOUNT {
+            return 0u128;
+        }
+        if strategies.len() == 1 {
+            // A lone uncapped strategy takes the whole budget without a quote.
+            let strategy = strategies.get(0).unwrap();
+            if strategy.target_bps > 0 && strategy.cap == 0 {
+                return Self::deposit_into_strategy(env, token_address, &strategy.vault, budget);
+            }
+        }
+
+        let balances = Self::strategy_balances(env, &strategies);
+        let mut deployed = 0u128;
+        let mut total_weight = 0u128;
+        for i in 0..strategies.len() {
+            deployed = deployed.saturating_add(balances.get(i).unwrap_or(0));
+            total_weight =
+                total_weight.saturating_add(strategies.get(i).unwrap().target_bps as u128);
+        }
+        if total_weight == 0 {
             return 0u128;
         }
+
+        // First fill each strategy up to its weighted share of the enlarged
+        // deployment, then hand rounding leftovers and capped-out remainders to
+        // the remaining weighted strategies in priority order.
+        let target_total = deployed.saturating_add(budget);
+        let mut plan: Vec<u128> = Vec::new(env);
+        let mut remaining = budget;
+        for i in 0..strategies.len() {
+            let strategy = strategies.get(i).unwrap();
+            let held = balances.get(i).unwrap_or(0);
+            let mut target =
+                target_total.saturating_mul(strategy.target_bps as u128) / total_weight;
+            if strategy.cap > 0 {
+                target = target.min(strategy.cap);
+            }
+            let fill = target.saturating_sub(held).min(remaining);
+            remaining -= fill;
+            plan.push_back(fill);
+        }
+        for i in 0..strategies.len() {
+            if remaining == 0 {
+                break;
+            }
+            let strategy = strategies.get(i).unwrap();
+            if strategy.target_bps == 0 {
+                continue;
+            }
+            let planned = plan.get(i).unwrap_or(0);
+            let headroom = if strategy.cap > 0 {
+                strategy
+                    .cap
+                    .saturating_sub(balances.get(i).unwrap_or(0))
+                    .saturating_sub(planned)
+            } else {
+                u128::MAX
+            };
+            let extra = headroom.min(remaining);
+            remaining -= extra;
+            plan.set(i, planned.saturating_add(extra));
+        }
+
+        let mut moved = 0u128;
+        for i in 0..strategies.len() {
+            let fill = plan.get(i).unwrap_or(0);
+            if fill < MIN_BOOSTED_DEPLOY_AMOUNT {
+                continue;
+            }
+            let vault = strategies.get(i).unwrap().vault;
+            moved = moved.saturating_add(Self::deposit_into_strategy(
+                env,
+                token_address,
+                &vault,
+                fill,
+            ));
+        }
+        moved
+    }
+
+    fn deposit_into_strategy(
+        env: &Env,
+        token_address: &Address,
+        boosted: &Address,
+        amount: u128,
+    ) -> u128 {
+        let available_cash = Self::current_live_cash(env, token_address);
+        let deploy_amount = amount.min(available_cash);
         if deploy_amount < MIN_BOOSTED_DEPLOY_AMOUNT {
             return 0u128;
         }
@@ -211,7 +426,7 @@ impl ReceiptVault {
         let cash_before_boost = Self::current_live_cash(env, token_address);
         env.authorize_as_current_contract(auths);
         let _: Val = env.invoke_contract(
-            &boosted,
+            boosted,
             &Symbol::new(env, "deposit"),
             (
                 amounts_desired,
@@ -225,47 +440,70 @@ impl ReceiptVault {
         let moved = cash_before_boost.saturating_sub(cash_after_boost);
         if moved > 0 {
             Self::sub_managed_cash(env, moved);
+            let basis = Self::strategy_basis(env, boosted).unwrap_or(0u128);
+            Self::set_strategy_basis(env, boosted, basis.saturating_add(moved));
         }
         moved
     }
 
-    /// Redeem from boosted vault to satisfy a live-cash requirement.
+    /// Redeem from strategies in priority order to satisfy a live-cash requirement.
     fn redeem_from_boosted(env: &Env, token_address: &Address, needed_cash: u128) {
         if needed_cash == 0 {
             return;
         }
-        let Some(boosted) = env
-            .storage()
-            .persistent()
-            .get::<_, Address>(&DataKey::BoostedVault)
-        else {
-            return;
-        };
+        let mut remaining = needed_cash;
+        for strategy in Self::strategies(env).iter() {
+            let received =
+                Self::redeem_from_strategy(env, token_address, &strategy.vault, remaining);
+            remaining = remaining.saturating_sub(received);
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
 
+    /// Redeem up to `needed_cash` from one strategy; returns the cash received.
+    fn redeem_from_strategy(
+        env: &Env,
+        token_address: &Address,
+        boosted: &Address,
+        needed_cash: u128,
+    ) -> u128 {
         let share_balance_i =
-            token::TokenClient::new(env, &boosted).balance(&env.current_contract_address());
+            token::TokenClient::new(env, boosted).balance(&env.current_contract_address());
         if share_balance_i <= 0 {
-            return;
+            return 0u128;
         }
         let share_balance = share_balance_i as u128;
 
-        let total_shares_i: i128 = call_contract_or_panic(env, &boosted, "total_supply", ());
+        let total_shares_i: i128 = call_contract_or_panic(env, boosted, "total_supply", ());
         if total_shares_i <= 0 {
-            return;
+            return 0u128;
         }
         let total_shares = total_shares_i as u128;
         let total_amounts: Vec<i128> = call_contract_or_panic(
             env,
-            &boosted,
+            boosted,
             "get_asset_amounts_per_shares",
             (total_shares_i,),
         );
         let total_underlying_i = total_amounts.get(0).unwrap_or(0);
         if total_underlying_i <= 0 {
-            return;
+            return 0u128;
         }
         let total_underlying = total_underlying_i as u128;
 
+        // Ask a strategy for no more than our shares are worth so later
+        // strategies can cover the rest instead of tripping slippage checks.
+        let held = share_balance
+            .checked_mul(total_underlying)
+            .map(|value| value / total_shares)
+            .unwrap_or(u128::MAX);
+        let needed_cash = needed_cash.min(held);
+        if needed_cash == 0 {
+            return 0u128;
+        }
+
         // Add a tiny buffer for share rounding so downstream payout paths are
         // less brittle to 1-unit quote/withdraw drift in boosted vault math.
         let target_cash = needed_cash.saturating_add(1);
@@ -281,8 +519,25 @@ impl ReceiptVault {
             shares_to_withdraw = share_balance;
         }
 
+        Self::withdraw_strategy_shares(
+            env,
+            token_address,
+            boosted,
+            shares_to_withdraw,
+            needed_cash.saturating_sub(1),
+        )
+    }
+
+    /// Redeem `shares` of a strategy for at least `min_out`; returns the cash received.
+    fn withdraw_strategy_shares(
+        env: &Env,
+        token_address: &Address,
+        boosted: &Address,
+        shares_to_withdraw: u128,
+        min_out: u128,
+    ) -> u128 {
         let mut min_amounts_out: Vec<i128> = Vec::new(env);
-        min_amounts_out.push_back(to_i128(needed_cash.saturating_sub(1)));
+        min_amounts_out.push_back(to_i128(min_out));
         let args: Vec<Val> = (
             to_i128(shares_to_withdraw),
             min_amounts_out.clone(),
@@ -302,7 +557,7 @@ impl ReceiptVault {
 
         let cash_before = Self::current_live_cash(env, token_address);
         let _: Val = env.invoke_contract(
-            &boosted,
+            boosted,
             &Symbol::new(env, "withdraw"),
             (
                 to_i128(shares_to_withdraw),
@@ -315,7 +570,118 @@ impl ReceiptVault {
         let received = cash_after.saturating_sub(cash_before);
         if received > 0 {
             Self::add_managed_cash(env, received);
+            let basis = Self::strategy_basis(env, boosted).unwrap_or(0u128);
+            Self::set_strategy_basis(env, boosted, basis.saturating_sub(received));
+        }
+        received
+    }
+
+    fn strategy_basis(env: &Env, vault: &Address) -> Option<u128> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::StrategyBasis(vault.clone()))
+    }
+
+    fn set_strategy_basis(env: &Env, vault: &Address, basis: u128) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::StrategyBasis(vault.clone()), &basis);
+    }
+
+    fn boosted_impaired(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::BoostedImpaired)
+            .unwrap_or(false)
+    }
+
+    /// Fresh underlying quote for a strategy position, or `None` when the
+    /// strategy cannot give a trustworthy answer right now.
+    fn quote_strategy(env: &Env, vault: &Address) -> Option<u128> {
+        let shares_i = token::TokenClient::new(env, vault).balance(&env.current_contract_address());
+        if shares_i <= 0 {
+            return Some(0u128);
+        }
+        match try_call_contract::<Vec<i128>, _>(
+            env,
+            vault,
+            "get_asset_amounts_per_shares",
+            (shares_i,),
+        ) {
+            Ok(amounts) => {
+                let amt_i = amounts.get(0).unwrap_or(0);
+                if amt_i <= 0 {
+                    None
+                } else {
+                    Some(amt_i as u128)
+                }
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Compare each strategy's fresh quote with its recognized basis. Gains
+    /// raise the basis; a shortfall beyond rounding tolerance is a realized
+    /// loss that reserves absorb first, with any remainder left to the
+    /// exchange rate. Any impairment pauses boosted deposits until the admin
+    /// acknowledges it. Without `apply` nothing is written. Returns the
+    /// reserves absorbing losses.
+    fn recognize_strategy_losses(
+        env: &Env,
+        strategies: &Vec<StrategyConfig>,
+        quotes: &Vec<(u128, bool)>,
+        apply: bool,
+    ) -> u128 {
+        let mut reserves: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalReserves)
+            .unwrap_or(0u128);
+        let mut absorbed_total = 0u128;
+        for (strategy, (reported, fresh)) in strategies.iter().zip(quotes.iter()) {
+            if !fresh {
+                continue;
+            }
+            let Some(basis) = Self::strategy_basis(env, &strategy.vault) else {
+                if apply {
+                    Self::set_strategy_basis(env, &strategy.vault, reported);
+                }
+                continue;
+            };
+            if reported >= basis {
+                if apply && reported > basis {
+                    Self::set_strategy_basis(env, &strategy.vault, reported);
+                }
+                continue;
+            }
+            let loss = basis - reported;
+            let tolerance = basis.saturating_mul(BOOSTED_LOSS_TOLERANCE_BPS) / BPS_SCALE + 1;
+            if loss <= tolerance {
+                continue;
+            }
+            let absorbed_by_reserves = loss.min(reserves);
+            reserves -= absorbed_by_reserves;
+            absorbed_total += absorbed_by_reserves;
+            if !apply {
+                continue;
+            }
+            if absorbed_by_reserves > 0 {
+                env.storage()
+                    .persistent()
+                    .set(&DataKey::TotalReserves, &reserves);
+            }
+            Self::set_strategy_basis(env, &strategy.vault, reported);
+            env.storage()
+                .instance()
+                .set(&DataKey::BoostedImpaired, &true);
+            StrategyImpaired {
+                vault: strategy.vault,
+                loss,
+                absorbed_by_reserves,
+            }
+            .publish(env);
         }
+        absorbed_total
     }
 
     /// Ensure live cash can satisfy an immediate payout/borrow.
@@ -358,6 +724,118 @@ impl ReceiptVault {
         Self::set_managed_cash(env, cash - amount);
     }
 
+    fn outflow_limit(env: &Env) -> Option<OutflowLimit> {
+        env.storage().persistent().get(&DataKey::OutflowLimit)
+    }
+
+    /// Net outflow tracked by the limiter, decayed to the current ledger.
+    fn outflow_window(env: &Env, limit: &OutflowLimit) -> OutflowWindow {
+        let now = env.ledger().timestamp();
+        let mut window: OutflowWindow = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OutflowWindow)
+            .unwrap_or(OutflowWindow {
+                net_outflow: 0,
+                last_update: now,
+            });
+        let elapsed = now.saturating_sub(window.last_update);
+        window.net_outflow = if elapsed >= limit.window_secs {
+            0
+        } else {
+            let decay = Self::checked_mul_div_u128(
+                limit.max_net_outflow,
+                elapsed as u128,
+                limit.window_secs as u128,
+            );
+            window.net_outflow.saturating_sub(decay)
+        };
+        window.last_update = now;
+        window
+    }
+
+    /// Underlying that may leave the market now without being delayed.
+    fn outflow_capacity(env: &Env) -> u128 {
+        match Self::outflow_limit(env) {
+            Some(limit) => limit
+                .max_net_outflow
+                .saturating_sub(Self::outflow_window(env, &limit).net_outflow),
+            None => u128::MAX,
+        }
+    }
+
+    /// Count `amount` against the limit and return the part within it.
+    fn consume_outflow(env: &Env, amount: u128) -> u128 {
+        let Some(limit) = Self::outflow_limit(env) else {
+            return amount;
+        };
+        let mut window = Self::outflow_window(env, &limit);
+        let allowed = amount.min(limit.max_net_outflow.saturating_sub(window.net_outflow));
+        window.net_outflow = window.net_outflow.saturating_add(allowed);
+        env.storage()
+            .persistent()
+            .set(&DataKey::OutflowWindow, &window);
+        allowed
+    }
+
+    /// Deposits and repays offset outflows within the window.
+    fn record_inflow(env: &Env, amount: u128) {
+        let Some(limit) = Self::outflow_limit(env) else {
+            return;
+        };
+        let mut window = Self::outflow_window(env, &limit);
+        window.net_outflow = window.net_outflow.saturating_sub(amount);
+        env.storage()
+            .persistent()
+            .set(&DataKey::OutflowWindow, &window);
+    }
+
+    /// Send `amount` of live cash to `recipient`, holding whatever exceeds the
+    /// outflow limit as a delayed claim. Either way the cash leaves the
+    /// market's accounting now.
+    fn pay_out(env: &Env, token_address: &Address, recipient: &Address, amount: u128) {
+        let allowed = Self::consume_outflow(env, amount);
+        let cash_before = Self::current_live_cash(env, token_address);
+        if allowed > 0 {
+            token::Client::new(env, token_address).transfer(
+                &env.current_contract_address(),
+                recipient,
+                &to_i128(allowed),
+            );
+        }
+        let delayed = amount - allowed;
+        if delayed > 0 {
+            let limit = Self::outflow_limit(env).expect("outflow limit missing");
+            let persistent = env.storage().persistent();
+            let key = DataKey::DelayedOutflow(recipient.clone());
+            let mut claim: DelayedOutflow = persistent.get(&key).unwrap_or(DelayedOutflow {
+                amount: 0,
+                claimable_at: 0,
+            });
+            claim.amount = claim.amount.saturating_add(delayed);
+            claim.claimable_at = claim.claimable_at.max(
+                env.ledger()
+                    .timestamp()
+                    .saturating_add(limit.claim_delay_secs),
+            );
+            persistent.set(&key, &claim);
+            bump_delayed_outflow_ttl(env, recipient);
+            let total: u128 = persistent
+                .get(&DataKey::TotalDelayedOutflows)
+                .unwrap_or(0u128);
+            persistent.set(&DataKey::TotalDelayedOutflows, &(total + delayed));
+            OutflowDelayed {
+                recipient: recipient.clone(),
+                amount: delayed,
+                total_delayed: claim.amount,
+                claimable_at: claim.claimable_at,
+            }
+            .publish(env);
+        }
+        let cash_after = Self::current_live_cash(env, token_address);
+        Self::sub_managed_cash(env, cash_before.saturating_sub(cash_after));
+    }
+
     fn ensure_not_in_flash_loan(env: &Env) {
         if env
             .storage()
@@ -448,6 +926,44 @@ impl ReceiptVault {
         enabled
     }
 
+    fn consume_deferred_health_check(env: &Env, user: &Address) -> bool {
+        let key = DataKey::DeferredHealthCheck(user.clone());
+        let enabled = env.storage().persistent().get(&key).unwrap_or(false);
+        if enabled {
+            env.storage().persistent().remove(&key);
+        }
+        enabled
+    }
+
+    fn require_market_manager(env: &Env, manager: &Address) {
+        let configured: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::MarketManager)
+            .expect("market manager not set");
+        if *manager != configured {
+            panic!("not market manager");
+        }
+        manager.require_auth();
+    }
+
+    /// Panics unless `account` may take the supply (`supply == true`) or borrow side.
+    fn require_access(env: &Env, account: &Address, supply: bool) {
+        if !Self::has_access(env, account, supply) {
+            panic!("account not allowlisted");
+        }
+    }
+
+    fn has_access(env: &Env, account: &Address, supply: bool) -> bool {
+        let restricted = match Self::get_access_mode(env.clone()) {
+            AccessMode::Open => false,
+            AccessMode::SupplyAllowlist => supply,
+            AccessMode::BorrowAllowlist => !supply,
+            AccessMode::FullAllowlist => true,
+        };
+        !restricted || Self::is_allowlisted(env.clone(), account.clone())
+    }
+
     fn enforce_margin_lock(
         env: &Env,
         user: &Address,
@@ -617,9 +1133,10 @@ impl ReceiptVault {
         bump_borrow_state_ttl(&env);
     }
 
-    /// Admin: set boosted vault address (DeFindex).
-    pub fn set_boosted_vault(env: Env, admin: Address, boosted_vault: Address) {
-        let _ = ensure_initialized(&env);
+    /// Admin: make pToken decimals follow the underlying token's decimals.
+    /// Only allowed before any pTokens exist so balances never change meaning.
+    pub fn sync_ptoken_decimals(env: Env, admin: Address) {
+        let token_address = ensure_initialized(&env);
         let stored: Address = env
             .storage()
             .persistent()
@@ -629,28 +1146,47 @@ impl ReceiptVault {
             panic!("not admin");
         }
         admin.require_auth();
-        let old_boosted: Option<Address> = env.storage().persistent().get(&DataKey::BoostedVault);
-        if let Some(comp_addr) = env
+        if total_ptokens_supply(&env) > 0 {
+            panic!("ptokens outstanding");
+        }
+        let decimals = token::Client::new(&env, &token_address).decimals();
+        TokenBase::set_metadata(
+            &env,
+            decimals,
+            TokenBase::name(&env),
+            TokenBase::symbol(&env),
+        );
+        PTokenDecimalsUpdated { decimals }.publish(&env);
+    }
+
+    /// Admin: set boosted vault address (DeFindex) as the market's only strategy.
+    pub fn set_boosted_vault(env: Env, admin: Address, boosted_vault: Address) {
+        let _ = ensure_initialized(&env);
+        let stored: Address = env
             .storage()
             .persistent()
-            .get::<_, Address>(&DataKey::Peridottroller)
-        {
-            // Enforce one-to-one boosted-vault ownership across markets when a
-            // shared controller is configured.
-            let _: () = call_contract_or_panic(
-                &env,
-                &comp_addr,
-                "bind_boosted_vault",
-                (
-                    env.current_contract_address(),
-                    old_boosted.clone(),
-                    Some(boosted_vault.clone()),
-                ),
-            );
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored != admin {
+            panic!("not admin");
         }
-        env.storage()
-            .persistent()
-            .set(&DataKey::BoostedVault, &boosted_vault);
+        admin.require_auth();
+        let strategies = Self::strategies(&env);
+        if strategies.len() > 1 {
+            panic!("multiple strategies configured");
+        }
+        let old_boosted: Option<Address> = strategies.first().map(|strategy| strategy.vault);
+        Self::bind_strategy(&env, old_boosted.clone(), Some(boosted_vault.clone()));
+        if let Some(old) = old_boosted.clone() {
+            Self::clear_strategy_cache(&env, &old);
+        }
+        let mut registry = Vec::new(&env);
+        registry.push_back(StrategyConfig {
+            vault: boosted_vault.clone(),
+            target_bps: BPS_SCALE as u32,
+            cap: 0,
+        });
+        Self::write_strategies(&env, &registry);
         env.storage()
             .persistent()
             .remove(&DataKey::BoostedUnderlyingCached);
@@ -664,14 +1200,19 @@ impl ReceiptVault {
         .publish(&env);
     }
 
-    /// View: get boosted vault (if set)
+    /// View: get the primary boosted vault (first strategy), if any.
     pub fn get_boosted_vault(env: Env) -> Option<Address> {
         let _ = ensure_initialized(&env);
         env.storage().persistent().get(&DataKey::BoostedVault)
     }
 
-    /// Admin: set target idle cash buffer in basis points (0..=10_000).
-    pub fn set_idle_cash_buffer_bps(env: Env, admin: Address, idle_cash_buffer_bps: u32) {
+    /// Admin: register a boosted strategy at the lowest withdrawal priority.
+    ///
+    /// `target_bps` weights the strategy against the others when idle cash is
+    /// deployed; `cap` bounds the underlying it may hold (0 = uncapped). A vault
+    /// set through `set_boosted_vault` carries the full 10_000 weight, so lower it
+    /// with `set_strategy` before adding another strategy.
+    pub fn add_strategy(env: Env, admin: Address, vault: Address, target_bps: u32, cap: u128) {
         let _ = ensure_initialized(&env);
         let stored: Address = env
             .storage()
@@ -682,15 +1223,205 @@ impl ReceiptVault {
             panic!("not admin");
         }
         admin.require_auth();
-        if idle_cash_buffer_bps > BPS_SCALE as u32 {
-            panic!("invalid idle cash buffer");
+        let mut strategies = Self::strategies(&env);
+        if strategies.iter().any(|strategy| strategy.vault == vault) {
+            panic!("strategy exists");
+        }
+        strategies.push_back(StrategyConfig {
+            vault: vault.clone(),
+            target_bps,
+            cap,
+        });
+        Self::validate_strategy_weights(&strategies);
+        Self::bind_strategy(&env, None, Some(vault.clone()));
+        Self::write_strategies(&env, &strategies);
+        StrategySet {
+            vault,
+            target_bps,
+            cap,
         }
-        if idle_cash_buffer_bps == 0 {
-            env.storage()
-                .persistent()
-                .remove(&DataKey::IdleCashBufferBps);
-        } else {
-            env.storage()
+        .publish(&env);
+    }
+
+    /// Admin: update a registered strategy's target weight and cap.
+    pub fn set_strategy(env: Env, admin: Address, vault: Address, target_bps: u32, cap: u128) {
+        let _ = ensure_initialized(&env);
+        let stored: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        let mut strategies = Self::strategies(&env);
+        let index = strategies
+            .iter()
+            .position(|strategy| strategy.vault == vault)
+            .expect("strategy not found") as u32;
+        strategies.set(
+            index,
+            StrategyConfig {
+                vault: vault.clone(),
+                target_bps,
+                cap,
+            },
+        );
+        Self::validate_strategy_weights(&strategies);
+        Self::write_strategies(&env, &strategies);
+        StrategySet {
+            vault,
+            target_bps,
+            cap,
+        }
+        .publish(&env);
+    }
+
+    /// Admin: redeem everything held by a strategy back to live cash and
+    /// unregister it.
+    pub fn remove_strategy(env: Env, admin: Address, vault: Address) {
+        let token_address = ensure_initialized(&env);
+        let stored: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        let mut strategies = Self::strategies(&env);
+        let index = strategies
+            .iter()
+            .position(|strategy| strategy.vault == vault)
+            .expect("strategy not found") as u32;
+
+        let mut recovered = Self::redeem_from_strategy(&env, &token_address, &vault, u128::MAX);
+        let shares_client = token::TokenClient::new(&env, &vault);
+        let shares_left = shares_client.balance(&env.current_contract_address());
+        if shares_left > 0 {
+            // Share rounding can strand dust; redeem whatever is left at its quote.
+            let quoted = Self::quote_strategy(&env, &vault).unwrap_or(0u128);
+            recovered = recovered.saturating_add(Self::withdraw_strategy_shares(
+                &env,
+                &token_address,
+                &vault,
+                shares_left as u128,
+                quoted.saturating_sub(1),
+            ));
+        }
+        if shares_client.balance(&env.current_contract_address()) > 0 {
+            panic!("strategy not empty");
+        }
+        strategies.remove(index);
+        Self::bind_strategy(&env, Some(vault.clone()), None);
+        Self::clear_strategy_cache(&env, &vault);
+        Self::write_strategies(&env, &strategies);
+        if strategies.is_empty() {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::BoostedUnderlyingCached);
+            env.storage()
+                .persistent()
+                .remove(&DataKey::BoostedUnderlyingUpdatedAt);
+        } else {
+            let _ = Self::strategy_balances(&env, &strategies);
+        }
+        StrategyRemoved { vault, recovered }.publish(&env);
+    }
+
+    /// View: registered boosted strategies in withdrawal priority order.
+    pub fn get_strategies(env: Env) -> Vec<StrategyConfig> {
+        let _ = ensure_initialized(&env);
+        Self::strategies(&env)
+    }
+
+    fn validate_strategy_weights(strategies: &Vec<StrategyConfig>) {
+        let mut total = 0u128;
+        for strategy in strategies.iter() {
+            total = total.saturating_add(strategy.target_bps as u128);
+        }
+        if total > BPS_SCALE {
+            panic!("invalid strategy weights");
+        }
+    }
+
+    /// Enforce one-to-one strategy ownership across markets when a shared
+    /// controller is configured.
+    fn bind_strategy(env: &Env, old_vault: Option<Address>, new_vault: Option<Address>) {
+        if let Some(comp_addr) = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::Peridottroller)
+        {
+            let _: () = call_contract_or_panic(
+                env,
+                &comp_addr,
+                "bind_boosted_vault",
+                (env.current_contract_address(), old_vault, new_vault),
+            );
+        }
+    }
+
+    fn clear_strategy_cache(env: &Env, vault: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::StrategyCached(vault.clone()));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::StrategyCachedAt(vault.clone()));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::StrategyBasis(vault.clone()));
+    }
+
+    /// Admin: acknowledge a recognized boosted loss and resume deploying idle
+    /// cash into strategies.
+    pub fn acknowledge_boosted_loss(env: Env, admin: Address) {
+        let _ = ensure_initialized(&env);
+        let stored: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        if !Self::boosted_impaired(&env) {
+            panic!("no boosted impairment");
+        }
+        env.storage().instance().remove(&DataKey::BoostedImpaired);
+        BoostedDepositsResumed { admin }.publish(&env);
+    }
+
+    /// View: whether boosted deposits are paused pending loss acknowledgement.
+    pub fn is_boosted_impaired(env: Env) -> bool {
+        Self::boosted_impaired(&env)
+    }
+
+    /// Admin: set target idle cash buffer in basis points (0..=10_000).
+    pub fn set_idle_cash_buffer_bps(env: Env, admin: Address, idle_cash_buffer_bps: u32) {
+        let _ = ensure_initialized(&env);
+        let stored: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        if idle_cash_buffer_bps > BPS_SCALE as u32 {
+            panic!("invalid idle cash buffer");
+        }
+        if idle_cash_buffer_bps == 0 {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::IdleCashBufferBps);
+        } else {
+            env.storage()
                 .persistent()
                 .set(&DataKey::IdleCashBufferBps, &idle_cash_buffer_bps);
             bump_idle_cash_buffer_ttl(&env);
@@ -707,7 +1438,8 @@ impl ReceiptVault {
         Self::idle_cash_buffer_bps(&env)
     }
 
-    /// Admin: move excess live cash into boosted vault to match target buffer.
+    /// Admin: pull from strategies above their target or cap, then move excess
+    /// live cash into strategies to match the target buffer.
     pub fn rebalance_idle_cash(env: Env, admin: Address) {
         let token_address = ensure_initialized(&env);
         let stored: Address = env
@@ -720,44 +1452,110 @@ impl ReceiptVault {
         }
         admin.require_auth();
 
-        let live_cash = Self::current_live_cash(&env, &token_address);
-        if live_cash == 0 {
-            return;
-        }
         let bps = Self::idle_cash_buffer_bps(&env) as u128;
         let total_underlying = Self::get_total_underlying(env.clone());
         let desired_idle = total_underlying.saturating_mul(bps) / BPS_SCALE;
+        let live_cash = Self::current_live_cash(&env, &token_address);
+        Self::withdraw_strategy_excess(
+            &env,
+            &token_address,
+            live_cash.saturating_sub(desired_idle),
+        );
+        let live_cash = Self::current_live_cash(&env, &token_address);
         if live_cash > desired_idle {
             let excess = live_cash - desired_idle;
             let _ = Self::deposit_into_boosted(&env, &token_address, excess);
         }
     }
 
+    /// Redeem what each strategy holds beyond its cap or its weighted share of
+    /// everything deployable (strategy holdings plus `idle_excess`).
+    fn withdraw_strategy_excess(env: &Env, token_address: &Address, idle_excess: u128) {
+        let strategies = Self::strategies(env);
+        if strategies.is_empty() {
+            return;
+        }
+        let balances = Self::strategy_balances(env, &strategies);
+        let mut deployable = idle_excess;
+        let mut total_weight = 0u128;
+        for i in 0..strategies.len() {
+            deployable = deployable.saturating_add(balances.get(i).unwrap_or(0));
+            total_weight =
+                total_weight.saturating_add(strategies.get(i).unwrap().target_bps as u128);
+        }
+        for i in 0..strategies.len() {
+            let strategy = strategies.get(i).unwrap();
+            let mut target = if total_weight == 0 {
+                0u128
+            } else {
+                deployable.saturating_mul(strategy.target_bps as u128) / total_weight
+            };
+            if strategy.cap > 0 {
+                target = target.min(strategy.cap);
+            }
+            let excess = balances.get(i).unwrap_or(0).saturating_sub(target);
+            if excess >= MIN_BOOSTED_DEPLOY_AMOUNT {
+                Self::redeem_from_strategy(env, token_address, &strategy.vault, excess);
+            }
+        }
+    }
+
     /// Deposit tokens into the vault and receive pTokens
     pub fn deposit(env: Env, user: Address, amount: u128) {
+        Self::deposit_internal(env, user.clone(), user, amount, None);
+    }
+
+    /// Deposit `amount` from `payer`, minting the pTokens to `beneficiary`.
+    pub fn deposit_for(env: Env, payer: Address, beneficiary: Address, amount: u128) {
+        Self::deposit_internal(env, payer, beneficiary, amount, None);
+    }
+
+    /// Mint exactly `ptoken_amount` pTokens, pulling the underlying they cost
+    /// at the current exchange rate (rounded up).
+    pub fn mint_shares(env: Env, user: Address, ptoken_amount: u128) {
+        let _ = ensure_initialized(&env);
+        if ptoken_amount == 0 {
+            panic!("amount below minimum");
+        }
+        Self::update_interest(env.clone());
+        let amount = Self::preview_mint(env.clone(), ptoken_amount);
+        Self::deposit_internal(env, user.clone(), user, amount, Some(ptoken_amount));
+    }
+
+    fn deposit_internal(
+        env: Env,
+        payer: Address,
+        beneficiary: Address,
+        amount: u128,
+        exact_ptokens: Option<u128>,
+    ) {
         let token_address = ensure_initialized(&env);
         Self::ensure_not_in_flash_loan(&env);
+        if Self::is_shutdown(env.clone()) {
+            panic!("market shut down");
+        }
+        Self::require_access(&env, &beneficiary, true);
         // Always update interest first
         Self::update_interest(env.clone());
-        // Require authorization from the user
-        Self::ensure_user_borrow_flag(&env, &user);
-        ensure_user_auth(&env, &user);
-        // Rewards: accrue user in this market and fail closed on error.
+        // Require authorization from the payer; the beneficiary receives the pTokens
+        Self::ensure_user_borrow_flag(&env, &beneficiary);
+        ensure_user_auth(&env, &payer);
+        // Rewards: accrue beneficiary in this market and fail closed on error.
         let total_ptokens_before = total_ptokens_supply(&env);
         let total_borrowed_before: u128 = env
             .storage()
             .persistent()
             .get(&DataKey::TotalBorrowed)
             .expect("total borrowed missing");
-        let user_ptokens_before = ptoken_balance(&env, &user);
-        let user_borrow_before = Self::get_user_borrow_balance(env.clone(), user.clone());
+        let user_ptokens_before = ptoken_balance(&env, &beneficiary);
+        let user_borrow_before = Self::get_user_borrow_balance(env.clone(), beneficiary.clone());
         let hint = ControllerAccrualHint {
             total_ptokens: Some(total_ptokens_before),
             total_borrowed: Some(total_borrowed_before),
             user_ptokens: Some(user_ptokens_before),
             user_borrowed: Some(user_borrow_before),
         };
-        Self::accrue_user_rewards(&env, &user, hint, "deposit");
+        Self::accrue_user_rewards(&env, &beneficiary, hint, "deposit");
 
         // Get the underlying token
         // Pause: consult peridottroller if set
@@ -794,9 +1592,9 @@ impl ReceiptVault {
         };
 
         // Calculate pTokens to mint based on current exchange rate BEFORE moving cash
-        let current_rate = Self::get_exchange_rate(env.clone());
+        let current_rate = Self::get_exchange_rate_1e18(env.clone());
         let amount_i128 = to_i128(amount);
-        token_client.transfer(&user, &env.current_contract_address(), &amount_i128);
+        token_client.transfer(&payer, &env.current_contract_address(), &amount_i128);
         let cash_after = Self::current_live_cash(&env, &token_address);
         let received_cash = cash_after.saturating_sub(cash_before);
         if received_cash == 0 {
@@ -811,16 +1609,21 @@ impl ReceiptVault {
             }
         }
 
-        let scaled_amount = received_cash
-            .checked_mul(SCALE_1E6)
-            .expect("ptoken calculation overflow");
-        let ptokens_to_mint = scaled_amount / current_rate;
+        let mut ptokens_to_mint = underlying_to_ptokens(&env, received_cash, current_rate);
+        if let Some(requested) = exact_ptokens {
+            if ptokens_to_mint < requested {
+                panic!("insufficient underlying received");
+            }
+            ptokens_to_mint = requested;
+        }
         if ptokens_to_mint == 0 {
             panic!("amount below minimum");
         }
         Self::add_managed_cash(&env, received_cash);
+        Self::record_inflow(&env, received_cash);
 
-        let deploy_amount = if received_cash == 0 {
+        // Queued withdraws claim new cash before it is deployed.
+        let deploy_amount = if received_cash == 0 || Self::withdraw_queue_len(&env) > 0 {
             0u128
         } else {
             let idle_bps = Self::idle_cash_buffer_bps(&env) as u128;
@@ -836,7 +1639,7 @@ impl ReceiptVault {
         let _ = Self::deposit_into_boosted(&env, &token_address, deploy_amount);
 
         // Mint pTokens and update totals
-        TokenBase::mint(&env, &user, to_i128(ptokens_to_mint));
+        TokenBase::mint(&env, &beneficiary, to_i128(ptokens_to_mint));
         let total_deposited: u128 = env
             .storage()
             .persistent()
@@ -848,21 +1651,120 @@ impl ReceiptVault {
 
         // Emit Compound-style Mint event
         Mint {
-            minter: user.clone(),
+            minter: beneficiary.clone(),
             mint_amount: received_cash,
             mint_tokens: ptokens_to_mint,
         }
         .publish(&env);
+        Self::fill_withdraw_queue(&env, &token_address);
+    }
+
+    /// Most underlying `user`, holding `balance` pTokens, may take out now.
+    /// During shutdown each holder is limited to their pro-rata share, by pTokens
+    /// held at entry, of all cash the market has had since: what is available now
+    /// plus what was already paid out. Redemptions still pay the full exchange
+    /// rate, so pTokens left behind keep their claim on borrows repaid later.
+    fn redeem_cap(env: &Env, user: &Address, balance: u128, available: u128) -> u128 {
+        if !Self::is_shutdown(env.clone()) {
+            return available;
+        }
+        let Some(settlement) = env
+            .storage()
+            .instance()
+            .get::<_, ShutdownSettlement>(&DataKey::ShutdownSettlement)
+        else {
+            return available;
+        };
+        if settlement.ptokens == 0 {
+            return available;
+        }
+        let redeemed = Self::shutdown_redemption(env, user, settlement.epoch);
+        let share = Self::checked_mul_div_u128(
+            balance.saturating_add(redeemed.ptokens),
+            available.saturating_add(settlement.paid),
+            settlement.ptokens,
+        );
+        share.saturating_sub(redeemed.underlying).min(available)
+    }
+
+    fn shutdown_redemption(env: &Env, user: &Address, epoch: u32) -> ShutdownRedemption {
+        env.storage()
+            .persistent()
+            .get::<_, ShutdownRedemption>(&DataKey::ShutdownRedeemed(user.clone()))
+            .filter(|redeemed| redeemed.epoch == epoch)
+            .unwrap_or(ShutdownRedemption {
+                epoch,
+                ptokens: 0,
+                underlying: 0,
+            })
+    }
+
+    /// Count a shutdown redemption against the holder's settlement share.
+    fn record_shutdown_redemption(env: &Env, user: &Address, ptokens: u128, underlying: u128) {
+        let Some(mut settlement) = env
+            .storage()
+            .instance()
+            .get::<_, ShutdownSettlement>(&DataKey::ShutdownSettlement)
+        else {
+            return;
+        };
+        let mut redeemed = Self::shutdown_redemption(env, user, settlement.epoch);
+        redeemed.ptokens = redeemed.ptokens.saturating_add(ptokens);
+        redeemed.underlying = redeemed.underlying.saturating_add(underlying);
+        env.storage()
+            .persistent()
+            .set(&DataKey::ShutdownRedeemed(user.clone()), &redeemed);
+        bump_shutdown_redeemed_ttl(env, user);
+        settlement.paid = settlement.paid.saturating_add(underlying);
+        env.storage()
+            .instance()
+            .set(&DataKey::ShutdownSettlement, &settlement);
+    }
+
+    /// Withdraw exactly `amount` underlying, burning the fewest pTokens (rounded up).
+    /// `u128::MAX` withdraws as much as the account can safely redeem.
+    pub fn redeem_underlying(env: Env, user: Address, amount: u128) {
+        let _ = ensure_initialized(&env);
+        if amount == u128::MAX {
+            Self::withdraw(env, user, u128::MAX);
+            return;
+        }
+        Self::update_interest(env.clone());
+        let ptoken_amount = Self::ptokens_for_underlying(&env, amount);
+        Self::withdraw_internal(env, user, ptoken_amount, Some(amount));
     }
 
-    /// Withdraw tokens using pTokens
+    /// Withdraw tokens using pTokens. `u128::MAX` redeems as much as the account
+    /// can safely withdraw (capped like `preview_redeem_max`).
     pub fn withdraw(env: Env, user: Address, ptoken_amount: u128) {
+        Self::withdraw_internal(env, user, ptoken_amount, None);
+    }
+
+    /// Burn `ptoken_amount` and pay their value, or exactly `exact_underlying` when
+    /// the pTokens were rounded up from a requested amount.
+    fn withdraw_internal(
+        env: Env,
+        user: Address,
+        ptoken_amount: u128,
+        exact_underlying: Option<u128>,
+    ) {
         let token_address = ensure_initialized(&env);
         Self::ensure_not_in_flash_loan(&env);
         user.require_auth();
         Self::ensure_user_borrow_flag(&env, &user);
         // Always update interest first
         Self::update_interest(env.clone());
+        // Queued withdraws are paid before new ones.
+        Self::fill_withdraw_queue(&env, &token_address);
+        let ptoken_amount = if ptoken_amount == u128::MAX {
+            let (max_ptokens, _) = Self::max_redeem(&env, &user);
+            if max_ptokens == 0 {
+                panic!("nothing to withdraw");
+            }
+            max_ptokens
+        } else {
+            ptoken_amount
+        };
         let current_ptokens = ptoken_balance(&env, &user);
         // Rewards accrue and fail closed on error.
         let total_ptokens_before = total_ptokens_supply(&env);
@@ -887,21 +1789,28 @@ impl ReceiptVault {
         if !Self::consume_margin_withdraw_bypass(&env, &user) {
             Self::enforce_margin_lock(&env, &user, current_ptokens, ptoken_amount);
         }
+        let defer_health_check = Self::consume_deferred_health_check(&env, &user);
 
         // Calculate underlying tokens to return based on current exchange rate
-        let current_rate = Self::get_exchange_rate(env.clone());
-        // underlying = ptoken_amount * rate / 1e6
-        // SECURITY: Use checked_mul to prevent silent overflow in release builds
-        let underlying_to_return = ptoken_amount
-            .checked_mul(current_rate)
-            .expect("withdraw calculation overflow")
-            / SCALE_1E6;
+        let current_rate = Self::get_exchange_rate_1e18(env.clone());
+        let available_underlying = Self::get_available_liquidity(env.clone());
+        // underlying = ptoken_amount * rate / 1e18 (checked, 256-bit intermediate)
+        let ptoken_value = ptokens_to_underlying(&env, ptoken_amount, current_rate);
+        // Rounding up the burn covers the requested amount; the surplus stays with suppliers.
+        let underlying_to_return = match exact_underlying {
+            Some(amount) if amount > ptoken_value => panic!("redeem rounding"),
+            Some(amount) => amount,
+            None => ptoken_value,
+        };
 
         // Check we have enough liquid underlying (cash)
-        let available_underlying = Self::get_available_liquidity(env.clone());
         if available_underlying < underlying_to_return {
             panic!("Not enough liquidity");
         }
+        let settlement_cap = Self::redeem_cap(&env, &user, current_ptokens, available_underlying);
+        if underlying_to_return > settlement_cap {
+            panic!("exceeds settlement share");
+        }
 
         // USD-based redeem gating via peridottroller, if set; otherwise local-only check
         if let Some(comp_addr) = env
@@ -920,13 +1829,19 @@ impl ReceiptVault {
                 panic!("redeem paused");
             }
             let local_debt = Self::get_user_borrow_balance(env.clone(), user.clone());
-            let other_borrows_usd: u128 = call_contract_or_panic(
-                &env,
-                &comp_addr,
-                "get_borrows_excl",
-                (user.clone(), env.current_contract_address()),
-            );
-            if local_debt > 0 || other_borrows_usd > 0 {
+            // A deferred check means the margin controller verifies account liquidity
+            // once its composite operation has finished.
+            let other_borrows_usd: u128 = if defer_health_check {
+                0
+            } else {
+                call_contract_or_panic(
+                    &env,
+                    &comp_addr,
+                    "get_borrows_excl",
+                    (user.clone(), env.current_contract_address()),
+                )
+            };
+            if !defer_health_check && (local_debt > 0 || other_borrows_usd > 0) {
                 // Other markets collateral in USD
                 let other_collateral_usd: u128 = call_contract_or_panic(
                     &env,
@@ -955,7 +1870,7 @@ impl ReceiptVault {
                 // Local remaining collateral after this redeem
                 let remaining_ptokens = current_ptokens - ptoken_amount;
                 let remaining_underlying =
-                    (remaining_ptokens.saturating_mul(current_rate)) / SCALE_1E6;
+                    ptokens_to_underlying(&env, remaining_ptokens, current_rate);
                 let remaining_discounted = (remaining_underlying.saturating_mul(cf)) / SCALE_1E6;
                 let local_collateral_usd = (remaining_discounted.saturating_mul(price)) / scale;
 
@@ -978,7 +1893,7 @@ impl ReceiptVault {
                 // Compute remaining collateral after this withdrawal
                 let remaining_ptokens = current_ptokens - ptoken_amount;
                 let remaining_underlying =
-                    (remaining_ptokens.saturating_mul(current_rate)) / SCALE_1E6;
+                    ptokens_to_underlying(&env, remaining_ptokens, current_rate);
                 let local_cf: u128 = env
                     .storage()
                     .persistent()
@@ -1015,13 +1930,13 @@ impl ReceiptVault {
             }
         }
 
-        // Create token client
-        let token_client = token::Client::new(&env, &token_address);
-
         let burn_i128 = to_i128(ptoken_amount);
         // Burn pTokens without implicit auth (already required above)
         TokenBase::update(&env, Some(&user), None, burn_i128);
         emit_burn(&env, &user, burn_i128);
+        if Self::is_shutdown(env.clone()) {
+            Self::record_shutdown_redemption(&env, &user, ptoken_amount, underlying_to_return);
+        }
         // Update totals
         let total_deposited: u128 = env
             .storage()
@@ -1043,15 +1958,8 @@ impl ReceiptVault {
             panic!("withdraw liquidity shortfall");
         }
 
-        // Transfer tokens back to user
-        let underlying_i128 = to_i128(underlying_to_return);
-        let cash_before_withdraw = Self::current_live_cash(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &user, &underlying_i128);
-        let cash_after_withdraw = Self::current_live_cash(&env, &token_address);
-        Self::sub_managed_cash(
-            &env,
-            cash_before_withdraw.saturating_sub(cash_after_withdraw),
-        );
+        // Transfer tokens back to user; any excess over the outflow limit is delayed.
+        Self::pay_out(&env, &token_address, &user, underlying_to_return);
 
         // Emit Compound-style Redeem event
         Redeem {
@@ -1062,62 +1970,437 @@ impl ReceiptVault {
         .publish(&env);
     }
 
-    /// Get user's balance in the vault in underlying terms (pTokens × exchange rate)
-    pub fn get_user_balance(env: Env, user: Address) -> u128 {
-        let pbal = ptoken_balance(&env, &user);
-        if pbal == 0 {
-            return 0u128;
+    /// Escrow `ptoken_amount` pTokens in the withdraw queue. Incoming deposits
+    /// and repays pay queued requests before anything else, split across all
+    /// live requests pro rata to their escrowed pTokens. Escrowed pTokens stop
+    /// counting as the supplier's collateral. One live request per supplier.
+    /// Returns the request id.
+    pub fn queue_withdraw(env: Env, user: Address, ptoken_amount: u128) -> u64 {
+        let _ = ensure_initialized(&env);
+        if ptoken_amount == 0 {
+            panic!("amount below minimum");
         }
-        let rate = Self::get_exchange_rate(env.clone());
-        (pbal.saturating_mul(rate)) / SCALE_1E6
-    }
-
-    /// Get user's pToken balance
-    pub fn get_ptoken_balance(env: Env, user: Address) -> u128 {
-        ptoken_balance(&env, &user)
-    }
-
-    // ERC20-like pToken API
-    pub fn approve(
-        env: Env,
-        owner: Address,
-        spender: Address,
-        amount: i128,
-        live_until_ledger: u32,
-    ) {
-        if amount < 0 {
-            panic!("bad amount");
+        if Self::is_shutdown(env.clone()) {
+            panic!("market shut down");
         }
-        TokenBase::approve(&env, &owner, &spender, amount, live_until_ledger);
-    }
-
-    pub fn allowance(env: Env, owner: Address, spender: Address) -> i128 {
-        TokenBase::allowance(&env, &owner, &spender)
-    }
-
-    pub fn transfer(env: Env, from: Address, to: MuxedAddress, amount: i128) {
-        if amount < 0 {
-            panic!("bad amount");
+        let persistent = env.storage().persistent();
+        let user_key = DataKey::UserWithdrawRequest(user.clone());
+        if persistent.has(&user_key) {
+            panic!("withdraw request exists");
         }
-        Self::transfer_internal(env, from, to.address(), amount as u128, None);
-    }
+        let len: u32 = persistent.get(&DataKey::WithdrawQueueLen).unwrap_or(0);
+        if len >= MAX_WITHDRAW_QUEUE_LEN {
+            panic!("withdraw queue full");
+        }
+        // Requires the user's auth and runs the usual collateral and margin-lock
+        // checks against the balance left behind.
+        Self::transfer_internal(
+            env.clone(),
+            user.clone(),
+            env.current_contract_address(),
+            ptoken_amount,
+            None,
+        );
 
-    pub fn transfer_from(env: Env, spender: Address, owner: Address, to: Address, amount: i128) {
-        if amount < 0 {
-            panic!("bad amount");
+        let id: u64 = persistent.get(&DataKey::NextWithdrawRequestId).unwrap_or(0);
+        let prev = if len == 0 {
+            persistent.set(&DataKey::WithdrawQueueHead, &id);
+            None
+        } else {
+            let tail: u64 = persistent
+                .get(&DataKey::WithdrawQueueTail)
+                .expect("withdraw queue tail missing");
+            let mut last: WithdrawRequest = persistent
+                .get(&DataKey::WithdrawRequest(tail))
+                .expect("withdraw request missing");
+            last.next = Some(id);
+            persistent.set(&DataKey::WithdrawRequest(tail), &last);
+            Some(tail)
+        };
+        persistent.set(&DataKey::WithdrawQueueTail, &id);
+        persistent.set(&DataKey::NextWithdrawRequestId, &(id + 1));
+        persistent.set(&DataKey::WithdrawQueueLen, &(len + 1));
+        persistent.set(
+            &DataKey::WithdrawQueuePtokens,
+            &Self::withdraw_queue_ptokens(&env).saturating_add(ptoken_amount),
+        );
+        persistent.set(
+            &DataKey::WithdrawRequest(id),
+            &WithdrawRequest {
+                owner: user.clone(),
+                ptokens: ptoken_amount,
+                prev,
+                next: None,
+            },
+        );
+        persistent.set(&user_key, &id);
+        bump_withdraw_queue_ttl(&env);
+        bump_withdraw_request_ttl(&env, id, &user);
+        WithdrawQueued {
+            owner: user,
+            id,
+            ptokens: ptoken_amount,
         }
-        Self::transfer_internal(env, owner, to, amount as u128, Some(spender));
-    }
+        .publish(&env);
 
-    pub fn balance(env: Env, account: Address) -> i128 {
-        let _ = ensure_initialized(&env);
-        TokenBase::balance(&env, &account)
+        let token_address = ensure_initialized(&env);
+        Self::fill_withdraw_queue(&env, &token_address);
+        id
     }
 
-    pub fn total_supply(env: Env) -> i128 {
+    /// Take back the unfilled pTokens of the caller's queued withdraw.
+    pub fn cancel_withdraw_request(env: Env, user: Address) {
         let _ = ensure_initialized(&env);
-        TokenBase::total_supply(&env)
-    }
+        Self::ensure_not_in_flash_loan(&env);
+        user.require_auth();
+        Self::update_interest(env.clone());
+        let persistent = env.storage().persistent();
+        let user_key = DataKey::UserWithdrawRequest(user.clone());
+        let Some(id) = persistent.get::<_, u64>(&user_key) else {
+            panic!("no withdraw request");
+        };
+        let request: WithdrawRequest = persistent
+            .get(&DataKey::WithdrawRequest(id))
+            .expect("withdraw request missing");
+        Self::unlink_withdraw_request(&env, id, &request);
+
+        let vault = env.current_contract_address();
+        let amount_i128 = to_i128(request.ptokens);
+        TokenBase::update(&env, Some(&vault), Some(&user), amount_i128);
+        stellar_tokens::fungible::emit_transfer(&env, &vault, &user, amount_i128);
+        let hint = ControllerAccrualHint {
+            total_ptokens: Some(total_ptokens_supply(&env)),
+            total_borrowed: Some(Self::get_total_borrowed(env.clone())),
+            user_ptokens: Some(ptoken_balance(&env, &user)),
+            user_borrowed: Some(Self::get_user_borrow_balance(env.clone(), user.clone())),
+        };
+        Self::accrue_user_rewards(&env, &user, hint, "cancel_withdraw");
+        WithdrawRequestCancelled {
+            owner: user,
+            id,
+            ptokens: request.ptokens,
+        }
+        .publish(&env);
+    }
+
+    /// Accrue, then pay queued withdraws from available cash. Permissionless.
+    pub fn process_withdraw_queue(env: Env) {
+        let token_address = ensure_initialized(&env);
+        Self::ensure_not_in_flash_loan(&env);
+        Self::update_interest(env.clone());
+        Self::fill_withdraw_queue(&env, &token_address);
+    }
+
+    /// View: the user's queued withdraw with its queue position and its
+    /// pro-rata share of the vault's current cash.
+    pub fn get_withdraw_request(env: Env, user: Address) -> Option<WithdrawRequestInfo> {
+        let persistent = env.storage().persistent();
+        let id: u64 = persistent.get(&DataKey::UserWithdrawRequest(user))?;
+        let request: WithdrawRequest = persistent.get(&DataKey::WithdrawRequest(id))?;
+        let (_, _, rate) = Self::preview_state(&env);
+        let mut position = 0u32;
+        let mut ptokens_ahead = 0u128;
+        let mut prev = request.prev;
+        while let Some(ahead_id) = prev {
+            if position >= MAX_WITHDRAW_QUEUE_LEN {
+                break;
+            }
+            let Some(ahead) =
+                persistent.get::<_, WithdrawRequest>(&DataKey::WithdrawRequest(ahead_id))
+            else {
+                break;
+            };
+            position += 1;
+            ptokens_ahead = ptokens_ahead.saturating_add(ahead.ptokens);
+            prev = ahead.prev;
+        }
+        let underlying = ptokens_to_underlying(&env, request.ptokens, rate);
+        let underlying_ahead = ptokens_to_underlying(&env, ptokens_ahead, rate);
+        let expected_fill = if Self::is_shutdown(env.clone()) {
+            0
+        } else {
+            let (_, pay) = Self::withdraw_request_fill(
+                &env,
+                request.ptokens,
+                Self::withdraw_queue_ptokens(&env),
+                Self::withdraw_queue_budget(&env),
+                rate,
+            );
+            pay
+        };
+        Some(WithdrawRequestInfo {
+            id,
+            ptokens: request.ptokens,
+            underlying,
+            position,
+            underlying_ahead,
+            expected_fill,
+        })
+    }
+
+    /// View: number of live queued withdraws.
+    pub fn get_withdraw_queue_len(env: Env) -> u32 {
+        Self::withdraw_queue_len(&env)
+    }
+
+    fn withdraw_queue_len(env: &Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::WithdrawQueueLen)
+            .unwrap_or(0)
+    }
+
+    fn withdraw_queue_ptokens(env: &Env) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::WithdrawQueuePtokens)
+            .unwrap_or(0)
+    }
+
+    /// Underlying the next fill pass would pay out of the cash a new
+    /// withdrawal could otherwise draw on.
+    fn pending_withdraw_queue_fill(env: &Env, rate: u128) -> u128 {
+        let queued = Self::withdraw_queue_ptokens(env);
+        if queued == 0 || Self::is_shutdown(env.clone()) {
+            return 0;
+        }
+        ptokens_to_underlying(env, queued, rate).min(Self::withdraw_queue_budget(env))
+    }
+
+    /// Drop a request from the queue, relinking its neighbours, and clear the
+    /// owner's index.
+    fn unlink_withdraw_request(env: &Env, id: u64, request: &WithdrawRequest) {
+        let persistent = env.storage().persistent();
+        match request.prev {
+            Some(prev_id) => {
+                let key = DataKey::WithdrawRequest(prev_id);
+                let mut prev: WithdrawRequest =
+                    persistent.get(&key).expect("withdraw request missing");
+                prev.next = request.next;
+                persistent.set(&key, &prev);
+            }
+            None => {
+                if let Some(next_id) = request.next {
+                    persistent.set(&DataKey::WithdrawQueueHead, &next_id);
+                } else {
+                    persistent.remove(&DataKey::WithdrawQueueHead);
+                }
+            }
+        }
+        match request.next {
+            Some(next_id) => {
+                let key = DataKey::WithdrawRequest(next_id);
+                let mut next: WithdrawRequest =
+                    persistent.get(&key).expect("withdraw request missing");
+                next.prev = request.prev;
+                persistent.set(&key, &next);
+            }
+            None => {
+                if let Some(prev_id) = request.prev {
+                    persistent.set(&DataKey::WithdrawQueueTail, &prev_id);
+                } else {
+                    persistent.remove(&DataKey::WithdrawQueueTail);
+                }
+            }
+        }
+        persistent.remove(&DataKey::WithdrawRequest(id));
+        persistent.remove(&DataKey::UserWithdrawRequest(request.owner.clone()));
+        let len = Self::withdraw_queue_len(env).saturating_sub(1);
+        persistent.set(&DataKey::WithdrawQueueLen, &len);
+        persistent.set(
+            &DataKey::WithdrawQueuePtokens,
+            &Self::withdraw_queue_ptokens(env).saturating_sub(request.ptokens),
+        );
+        bump_withdraw_queue_ttl(env);
+    }
+
+    /// Cash queued withdraws may draw on: idle cash, never reserves, within
+    /// the outflow limit.
+    fn withdraw_queue_budget(env: &Env) -> u128 {
+        let token_address = ensure_initialized(env);
+        Self::get_available_liquidity(env.clone())
+            .min(Self::current_live_cash(env, &token_address))
+            .min(Self::outflow_capacity(env))
+    }
+
+    /// pTokens to burn for a request holding `ptokens` of the `queued` total
+    /// and the underlying they pay: in full when `budget` covers the whole
+    /// queue, otherwise the request's pro-rata share of `budget`.
+    fn withdraw_request_fill(
+        env: &Env,
+        ptokens: u128,
+        queued: u128,
+        budget: u128,
+        rate: u128,
+    ) -> (u128, u128) {
+        if queued == 0 || budget == 0 {
+            return (0, 0);
+        }
+        let burn = if ptokens_to_underlying(env, queued, rate) <= budget {
+            ptokens
+        } else {
+            let budget_ptokens = underlying_to_ptokens(env, budget, rate);
+            Self::checked_mul_div_u128(budget_ptokens, ptokens, queued).min(ptokens)
+        };
+        (burn, ptokens_to_underlying(env, burn, rate))
+    }
+
+    /// Pay queued withdraws from available cash, split across every live
+    /// request pro rata to its escrowed pTokens. Interest must already be
+    /// accrued.
+    fn fill_withdraw_queue(env: &Env, token_address: &Address) {
+        if Self::withdraw_queue_len(env) == 0 || Self::is_shutdown(env.clone()) {
+            return;
+        }
+        if let Some(comp_addr) = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::Peridottroller)
+        {
+            let paused: bool = call_contract_or_panic(
+                env,
+                &comp_addr,
+                "is_redeem_paused",
+                (env.current_contract_address(),),
+            );
+            if paused {
+                return;
+            }
+        }
+        let budget = Self::withdraw_queue_budget(env);
+        if budget == 0 {
+            return;
+        }
+        let persistent = env.storage().persistent();
+        let vault = env.current_contract_address();
+        let token_client = token::Client::new(env, token_address);
+        let rate = Self::get_exchange_rate_1e18(env.clone());
+        // Shares are fixed against the queue as it stands when the pass starts.
+        let queued = Self::withdraw_queue_ptokens(env);
+        let mut paid_total = 0u128;
+        let mut burned_total = 0u128;
+        let mut next = persistent.get::<_, u64>(&DataKey::WithdrawQueueHead);
+        let mut steps = 0u32;
+        while let Some(id) = next {
+            if steps >= MAX_WITHDRAW_QUEUE_LEN {
+                break;
+            }
+            steps += 1;
+            let key = DataKey::WithdrawRequest(id);
+            let mut request: WithdrawRequest =
+                persistent.get(&key).expect("withdraw request missing");
+            next = request.next;
+            let (burn, pay) =
+                Self::withdraw_request_fill(env, request.ptokens, queued, budget, rate);
+            if burn == 0 || pay == 0 {
+                continue;
+            }
+
+            let burn_i128 = to_i128(burn);
+            TokenBase::update(env, Some(&vault), None, burn_i128);
+            emit_burn(env, &vault, burn_i128);
+            let cash_before = Self::current_live_cash(env, token_address);
+            token_client.transfer(&vault, &request.owner, &to_i128(pay));
+            let cash_after = Self::current_live_cash(env, token_address);
+            Self::sub_managed_cash(env, cash_before.saturating_sub(cash_after));
+            paid_total = paid_total.saturating_add(pay);
+            burned_total = burned_total.saturating_add(burn);
+
+            request.ptokens -= burn;
+            Redeem {
+                redeemer: request.owner.clone(),
+                redeem_amount: pay,
+                redeem_tokens: burn,
+            }
+            .publish(env);
+            WithdrawRequestFilled {
+                owner: request.owner.clone(),
+                id,
+                underlying: pay,
+                ptokens: burn,
+                remaining_ptokens: request.ptokens,
+            }
+            .publish(env);
+            if request.ptokens > 0 {
+                persistent.set(&key, &request);
+                bump_withdraw_request_ttl(env, id, &request.owner);
+            } else {
+                Self::unlink_withdraw_request(env, id, &request);
+            }
+        }
+
+        if burned_total > 0 {
+            persistent.set(
+                &DataKey::WithdrawQueuePtokens,
+                &Self::withdraw_queue_ptokens(env).saturating_sub(burned_total),
+            );
+        }
+        if paid_total > 0 {
+            Self::consume_outflow(env, paid_total);
+            let total_deposited: u128 = persistent.get(&DataKey::TotalDeposited).unwrap_or(0u128);
+            persistent.set(
+                &DataKey::TotalDeposited,
+                &total_deposited.saturating_sub(paid_total),
+            );
+        }
+    }
+
+    /// Get user's balance in the vault in underlying terms (pTokens × exchange rate)
+    pub fn get_user_balance(env: Env, user: Address) -> u128 {
+        let pbal = ptoken_balance(&env, &user);
+        if pbal == 0 {
+            return 0u128;
+        }
+        let rate = Self::get_exchange_rate_1e18(env.clone());
+        ptokens_to_underlying(&env, pbal, rate)
+    }
+
+    /// Get user's pToken balance
+    pub fn get_ptoken_balance(env: Env, user: Address) -> u128 {
+        ptoken_balance(&env, &user)
+    }
+
+    // ERC20-like pToken API
+    pub fn approve(
+        env: Env,
+        owner: Address,
+        spender: Address,
+        amount: i128,
+        live_until_ledger: u32,
+    ) {
+        if amount < 0 {
+            panic!("bad amount");
+        }
+        TokenBase::approve(&env, &owner, &spender, amount, live_until_ledger);
+    }
+
+    pub fn allowance(env: Env, owner: Address, spender: Address) -> i128 {
+        TokenBase::allowance(&env, &owner, &spender)
+    }
+
+    pub fn transfer(env: Env, from: Address, to: MuxedAddress, amount: i128) {
+        if amount < 0 {
+            panic!("bad amount");
+        }
+        Self::transfer_internal(env, from, to.address(), amount as u128, None);
+    }
+
+    pub fn transfer_from(env: Env, spender: Address, owner: Address, to: Address, amount: i128) {
+        if amount < 0 {
+            panic!("bad amount");
+        }
+        Self::transfer_internal(env, owner, to, amount as u128, Some(spender));
+    }
+
+    pub fn balance(env: Env, account: Address) -> i128 {
+        let _ = ensure_initialized(&env);
+        TokenBase::balance(&env, &account)
+    }
+
+    pub fn total_supply(env: Env) -> i128 {
+        let _ = ensure_initialized(&env);
+        TokenBase::total_supply(&env)
+    }
 
     pub fn decimals(env: Env) -> u32 {
         let _ = ensure_initialized(&env);
@@ -1146,6 +2429,11 @@ impl ReceiptVault {
         if amount == 0 {
             return;
         }
+        // Restricted receipts may only move to approved holders; the vault
+        // itself escrows queued withdraws.
+        if to != env.current_contract_address() {
+            Self::require_access(&env, &to, true);
+        }
         // Ensure collateral checks use the latest debt/index state.
         Self::update_interest(env.clone());
         Self::ensure_user_borrow_flag(&env, &from);
@@ -1202,10 +2490,10 @@ impl ReceiptVault {
                     (env.current_contract_address(),),
                 );
 
-                let current_rate = Self::get_exchange_rate(env.clone());
+                let current_rate = Self::get_exchange_rate_1e18(env.clone());
                 let remaining_ptokens = pbal - amount;
                 let remaining_underlying =
-                    (remaining_ptokens.saturating_mul(current_rate)) / SCALE_1E6;
+                    ptokens_to_underlying(&env, remaining_ptokens, current_rate);
                 let remaining_discounted = (remaining_underlying.saturating_mul(cf)) / SCALE_1E6;
                 let local_collateral_usd = (remaining_discounted.saturating_mul(price)) / scale;
                 let local_debt_usd = (local_debt.saturating_mul(price)) / scale;
@@ -1221,14 +2509,14 @@ impl ReceiptVault {
             // Prevents users with debt from transferring away collateral pTokens.
             let local_debt = Self::get_user_borrow_balance(env.clone(), from.clone());
             if local_debt > 0 {
-                let current_rate = Self::get_exchange_rate(env.clone());
+                let current_rate = Self::get_exchange_rate_1e18(env.clone());
                 let current_ptokens = ptoken_balance(&env, &from);
                 if current_ptokens < amount {
                     panic!("Insufficient pTokens");
                 }
                 let remaining_ptokens = current_ptokens - amount;
                 let remaining_underlying =
-                    (remaining_ptokens.saturating_mul(current_rate)) / SCALE_1E6;
+                    ptokens_to_underlying(&env, remaining_ptokens, current_rate);
                 let local_cf: u128 = env
                     .storage()
                     .persistent()
@@ -1412,87 +2700,605 @@ impl ReceiptVault {
         NewAdmin { admin: new_admin }.publish(&env);
     }
 
-    /// Get the exchange rate (pToken to underlying ratio) scaled by 1e6
+    /// Get the exchange rate (pToken to underlying ratio) scaled by 1e6.
+    /// Kept for integrations; all internal accounting uses `get_exchange_rate_1e18`.
     pub fn get_exchange_rate(env: Env) -> u128 {
+        Self::get_exchange_rate_1e18(env) / (INDEX_SCALE_1E18 / SCALE_1E6)
+    }
+
+    /// Get the exchange rate (pToken to underlying ratio) scaled by 1e18
+    pub fn get_exchange_rate_1e18(env: Env) -> u128 {
         let _ = ensure_initialized(&env);
-        let total_ptokens = total_ptokens_supply(&env);
+        let total_underlying = Self::get_total_underlying(env.clone());
+        Self::exchange_rate_for(&env, total_underlying)
+    }
+
+    fn exchange_rate_for(env: &Env, total_underlying: u128) -> u128 {
+        let total_ptokens = total_ptokens_supply(env);
         if total_ptokens == 0 {
-            let total_underlying = Self::get_total_underlying(env.clone());
             if total_underlying > 0 {
                 panic!("non-empty vault at zero supply");
             }
-            return env
+            let initial: u128 = env
                 .storage()
                 .persistent()
                 .get(&DataKey::InitialExchangeRate)
                 .unwrap_or(SCALE_1E6);
+            return initial
+                .checked_mul(INDEX_SCALE_1E18 / SCALE_1E6)
+                .expect("exchange rate overflow");
         }
-        let total_underlying = Self::get_total_underlying(env.clone());
         if total_underlying == 0 {
             panic!("invalid underlying state");
         }
-        // rate = total_underlying / total_ptokens, scaled 1e6
-        let scaled_underlying = total_underlying
-            .checked_mul(SCALE_1E6)
-            .expect("exchange rate overflow");
-        scaled_underlying / total_ptokens
+        // rate = total_underlying / total_ptokens, scaled 1e18
+        U256::from_u128(env, total_underlying)
+            .mul(&U256::from_u128(env, INDEX_SCALE_1E18))
+            .div(&U256::from_u128(env, total_ptokens))
+            .to_u128()
+            .expect("exchange rate overflow")
     }
 
-    /// Get the underlying token address
-    pub fn get_underlying_token(env: Env) -> Address {
-        let _ = ensure_initialized(&env);
-        env.storage()
+    /// Accrued totals, total underlying and 1e18 exchange rate at the current
+    /// ledger, computed the way `update_interest` would without persisting them.
+    fn preview_state(env: &Env) -> (PendingAccrual, u128, u128) {
+        let token_address = ensure_initialized(env);
+        let last_time: u64 = env
+            .storage()
             .persistent()
-            .get(&DataKey::UnderlyingToken)
-            .expect("Vault not initialized")
+            .get(&DataKey::LastUpdateTime)
+            .expect("last update missing");
+        let now = env.ledger().timestamp();
+        let elapsed = if now <= last_time || Self::is_shutdown(env.clone()) {
+            0u128
+        } else {
+            (now - last_time) as u128
+        };
+        let mut accrual = Self::pending_accrual(env, &token_address, elapsed, false);
+        // `update_interest` recognizes strategy losses before accruing.
+        let boosted_underlying = if elapsed == 0 {
+            Self::get_boosted_underlying(env)
+        } else {
+            let strategies = Self::strategies(env);
+            let quotes = Self::strategy_quotes(env, &strategies);
+            let absorbed = Self::recognize_strategy_losses(env, &strategies, &quotes, false);
+            accrual.total_reserves = accrual.total_reserves.saturating_sub(absorbed);
+            let mut total = 0u128;
+            for (balance, _) in quotes.iter() {
+                total = total.saturating_add(balance);
+            }
+            total
+        };
+        let total_underlying = Self::total_underlying_with(
+            env,
+            boosted_underlying,
+            accrual.total_borrowed,
+            accrual.total_reserves,
+            accrual.total_admin_fees,
+        );
+        let rate = Self::exchange_rate_for(env, total_underlying);
+        (accrual, total_underlying, rate)
     }
 
-    /// Get collateral factor (scaled 1e6)
-    pub fn get_collateral_factor(env: Env) -> u128 {
+    /// ERC-4626 view: pTokens worth `assets` underlying at the accrued exchange rate (rounds down).
+    pub fn convert_to_shares(env: Env, assets: u128) -> u128 {
+        let (_, _, rate) = Self::preview_state(&env);
+        underlying_to_ptokens(&env, assets, rate)
+    }
+
+    /// ERC-4626 view: underlying worth `shares` pTokens at the accrued exchange rate (rounds down).
+    pub fn convert_to_assets(env: Env, shares: u128) -> u128 {
+        let (_, _, rate) = Self::preview_state(&env);
+        ptokens_to_underlying(&env, shares, rate)
+    }
+
+    /// ERC-4626 view: pTokens `deposit(assets)` would mint now.
+    pub fn preview_deposit(env: Env, assets: u128) -> u128 {
+        let (_, _, rate) = Self::preview_state(&env);
+        underlying_to_ptokens(&env, assets, rate)
+    }
+
+    /// ERC-4626 view: underlying `withdraw(shares)` would pay now, capped at
+    /// the cash left once queued withdraws are paid.
+    pub fn preview_redeem(env: Env, shares: u128) -> u128 {
+        let (accrual, total_underlying, rate) = Self::preview_state(&env);
+        let available = total_underlying
+            .saturating_sub(accrual.total_borrowed)
+            .saturating_sub(Self::pending_withdraw_queue_fill(&env, rate));
+        ptokens_to_underlying(&env, shares, rate).min(available)
+    }
+
+    /// ERC-4626 view: fewest pTokens whose redemption pays at least `assets` (rounds up).
+    pub fn preview_withdraw(env: Env, assets: u128) -> u128 {
+        Self::ptokens_for_underlying(&env, assets)
+    }
+
+    /// ERC-4626 view: underlying `mint_shares(shares)` would pull now (rounds up).
+    pub fn preview_mint(env: Env, shares: u128) -> u128 {
+        let (_, _, rate) = Self::preview_state(&env);
+        ptokens_to_underlying_ceil(&env, shares, rate)
+    }
+
+    fn ptokens_for_underlying(env: &Env, assets: u128) -> u128 {
+        let (_, _, rate) = Self::preview_state(env);
+        underlying_to_ptokens_ceil(env, assets, rate)
+    }
+
+    /// ERC-4626 view: most underlying `user` can deposit now (0 when paused,
+    /// shut down or not allowlisted; `u128::MAX` when uncapped).
+    pub fn max_deposit(env: Env, user: Address) -> u128 {
         let _ = ensure_initialized(&env);
-        env.storage()
+        if Self::is_shutdown(env.clone()) || !Self::has_access(&env, &user, true) {
+            return 0u128;
+        }
+        if let Some(comp_addr) = env
+            .storage()
             .persistent()
-            .get(&DataKey::CollateralFactorScaled)
-            .unwrap_or(500_000u128)
+            .get::<_, Address>(&DataKey::Peridottroller)
+        {
+            let paused: bool = call_contract_or_panic(
+                &env,
+                &comp_addr,
+                "is_deposit_paused",
+                (env.current_contract_address(),),
+            );
+            if paused {
+                return 0u128;
+            }
+        }
+        let cap: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::SupplyCap)
+            .unwrap_or(0u128);
+        if cap == 0 {
+            return u128::MAX;
+        }
+        let (_, total_underlying, _) = Self::preview_state(&env);
+        cap.saturating_sub(total_underlying)
     }
 
-    /// Admin: set peridottroller address
-    pub fn set_peridottroller(env: Env, peridottroller: Address) {
-        let _ = ensure_initialized(&env);
-        let admin: Address = env
+    /// ERC-4626 view: most underlying `user` can withdraw now. Applies the same
+    /// liquidity, margin-lock and account-health checks as `withdraw`; the
+    /// peridottroller's `preview_redeem_max` bound is evaluated here because the
+    /// controller cannot call back into this market.
+    pub fn max_withdraw(env: Env, user: Address) -> u128 {
+        Self::max_redeem(&env, &user).1
+    }
+
+    /// Largest pToken redemption `user` can make now and the underlying it pays.
+    fn max_redeem(env: &Env, user: &Address) -> (u128, u128) {
+        let (accrual, total_underlying, rate) = Self::preview_state(env);
+        let balance = ptoken_balance(env, user);
+        if balance == 0 {
+            return (0u128, 0u128);
+        }
+        let mut max_ptokens = balance;
+        if let Some(margin_controller) = env
             .storage()
             .persistent()
-            .get(&DataKey::Admin)
-            .expect("admin not set");
-        admin.require_auth();
-        let token_address: Address = env
+            .get::<_, Address>(&DataKey::MarginController)
+        {
+            let locked_ptokens: u128 = call_contract_or_panic(
+                env,
+                &margin_controller,
+                "locked_ptokens_in_market",
+                (user.clone(), env.current_contract_address()),
+            );
+            max_ptokens = balance.saturating_sub(locked_ptokens);
+        }
+        // `withdraw` pays the queue before the caller.
+        let available = total_underlying
+            .saturating_sub(accrual.total_borrowed)
+            .saturating_sub(Self::pending_withdraw_queue_fill(env, rate));
+        let available = Self::redeem_cap(env, user, balance, available);
+        let local_debt = Self::user_borrow_balance_at(env, user, Some(accrual.borrow_index));
+
+        // Health inputs: (other collateral USD, required USD, price, scale, cf).
+        let mut usd_health: Option<(u128, u128, u128, u128, u128)> = None;
+        let mut local_cf: Option<u128> = None;
+        if let Some(comp_addr) = env
             .storage()
             .persistent()
-            .get(&DataKey::UnderlyingToken)
-            .expect("Vault not initialized");
-        let _: bool = call_contract_or_panic::<bool, _>(
-            &env,
-            &peridottroller,
-            "is_deposit_paused",
-            (env.current_contract_address(),),
-        );
-        let _: bool = call_contract_or_panic::<bool, _>(
-            &env,
-            &peridottroller,
-            "is_redeem_paused",
-            (env.current_contract_address(),),
-        );
-        let _: bool = call_contract_or_panic::<bool, _>(
-            &env,
-            &peridottroller,
-            "is_borrow_paused",
-            (env.current_contract_address(),),
-        );
-        let _: () = call_contract_or_panic::<(), _>(
-            &env,
-            &peridottroller,
-            "accrue_user_market",
-            (
+            .get::<_, Address>(&DataKey::Peridottroller)
+        {
+            let paused: bool = call_contract_or_panic(
+                env,
+                &comp_addr,
+                "is_redeem_paused",
+                (env.current_contract_address(),),
+            );
+            if paused {
+                return (0u128, 0u128);
+            }
+            let other_borrows_usd: u128 = call_contract_or_panic(
+                env,
+                &comp_addr,
+                "get_borrows_excl",
+                (user.clone(), env.current_contract_address()),
+            );
+            if local_debt > 0 || other_borrows_usd > 0 {
+                let other_collateral_usd: u128 = call_contract_or_panic(
+                    env,
+                    &comp_addr,
+                    "get_collateral_excl_usd",
+                    (user.clone(), env.current_contract_address()),
+                );
+                let price_opt: Option<(u128, u128)> = call_contract_or_panic(
+                    env,
+                    &comp_addr,
+                    "get_price_usd",
+                    (Self::get_underlying_token(env.clone()),),
+                );
+                let Some((price, scale)) = price_opt else {
+                    return (0u128, 0u128);
+                };
+                let cf: u128 = call_contract_or_panic(
+                    env,
+                    &comp_addr,
+                    "get_market_cf",
+                    (env.current_contract_address(),),
+                );
+                let required_usd =
+                    other_borrows_usd.saturating_add((local_debt.saturating_mul(price)) / scale);
+                usd_health = Some((other_collateral_usd, required_usd, price, scale, cf));
+            }
+        } else if local_debt > 0 {
+            local_cf = Some(
+                env.storage()
+                    .persistent()
+                    .get(&DataKey::CollateralFactorScaled)
+                    .unwrap_or(500_000u128),
+            );
+        }
+
+        let can_redeem = |ptokens: u128| -> bool {
+            let paid = ptokens_to_underlying(env, ptokens, rate);
+            if paid > available {
+                return false;
+            }
+            let remaining_underlying = ptokens_to_underlying(env, balance - ptokens, rate);
+            if let Some((other_collateral_usd, required_usd, price, scale, cf)) = usd_health {
+                let remaining_discounted = (remaining_underlying.saturating_mul(cf)) / SCALE_1E6;
+                let local_collateral_usd = (remaining_discounted.saturating_mul(price)) / scale;
+                return other_collateral_usd.saturating_add(local_collateral_usd) >= required_usd;
+            }
+            if let Some(cf) = local_cf {
+                return (remaining_underlying.saturating_mul(cf)) / SCALE_1E6 >= local_debt;
+            }
+            true
+        };
+        if !can_redeem(0) {
+            return (0u128, 0u128);
+        }
+        // Every check is monotone in the redeemed amount, so bisect for the largest.
+        let mut low = 0u128;
+        let mut high = max_ptokens;
+        while low < high {
+            let mid = high - (high - low) / 2;
+            if can_redeem(mid) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        (low, ptokens_to_underlying(env, low, rate))
+    }
+
+    /// Most underlying `user` can borrow now under the same liquidity, cap,
+    /// account-health and minimum-borrow checks as `borrow`; 0 when the headroom
+    /// is below the market's `min_borrow`.
+    pub fn max_borrow(env: Env, user: Address) -> u128 {
+        let token_address = ensure_initialized(&env);
+        let storage = env.storage().persistent();
+        let rates_ready = storage
+            .get::<_, bool>(&DataKey::RatesReady)
+            .unwrap_or_else(|| storage.get::<_, Address>(&DataKey::InterestModel).is_some());
+        if !rates_ready || !Self::has_access(&env, &user, false) {
+            return 0u128;
+        }
+        let (accrual, total_underlying, rate) = Self::preview_state(&env);
+        let mut max_amount = total_underlying.saturating_sub(accrual.total_borrowed);
+        let bcap: u128 = storage.get(&DataKey::BorrowCap).unwrap_or(0u128);
+        if bcap > 0 {
+            let principal_total: u128 = storage
+                .get(&DataKey::TotalBorrowPrincipal)
+                .unwrap_or(accrual.total_borrowed);
+            max_amount = max_amount.min(bcap.saturating_sub(principal_total));
+        }
+        let balance = ptoken_balance(&env, &user);
+        let debt = Self::user_borrow_balance_at(&env, &user, Some(accrual.borrow_index));
+        let by_health = if let Some(comp_addr) = storage.get::<_, Address>(&DataKey::Peridottroller)
+        {
+            let paused: bool = call_contract_or_panic(
+                &env,
+                &comp_addr,
+                "is_borrow_paused",
+                (env.current_contract_address(),),
+            );
+            if paused {
+                return 0u128;
+            }
+            let liq_hint = MarketLiquidityHint {
+                ptoken_balance: balance,
+                user_borrowed: debt,
+                exchange_rate: rate,
+            };
+            let (liquidity, shortfall): (u128, u128) = call_contract_or_panic(
+                &env,
+                &comp_addr,
+                "hypothetical_liquidity_with_hint",
+                (
+                    user.clone(),
+                    env.current_contract_address(),
+                    0u128,
+                    token_address.clone(),
+                    liq_hint,
+                ),
+            );
+            if shortfall > 0 {
+                return 0u128;
+            }
+            let price_opt: Option<(u128, u128)> =
+                call_contract_or_panic(&env, &comp_addr, "get_price_usd", (token_address,));
+            match price_opt {
+                // Largest amount whose USD value (rounded down) fits the liquidity.
+                Some((price, scale)) if price > 0 => {
+                    liquidity
+                        .saturating_add(1)
+                        .saturating_mul(scale)
+                        .saturating_sub(1)
+                        / price
+                }
+                _ => 0u128,
+            }
+        } else {
+            let local_cf: u128 = storage
+                .get(&DataKey::CollateralFactorScaled)
+                .unwrap_or(500_000u128);
+            let collateral_value = ptokens_to_underlying(&env, balance, rate);
+            let local_max_borrow = (collateral_value.saturating_mul(local_cf)) / 1_000_000u128;
+            local_max_borrow.saturating_sub(debt)
+        };
+        let max_amount = max_amount.min(by_health);
+        if max_amount < Self::dust_limits(&env).min_borrow {
+            return 0u128;
+        }
+        max_amount
+    }
+
+    /// Utilization, borrow rate and supply rate (all scaled 1e6) on totals
+    /// accrued to the current ledger, using the same model inputs and static
+    /// fallback as `update_interest`. Rates are 0 while accrual is frozen.
+    fn current_rates(env: &Env) -> (u128, u128, u128) {
+        let token_address = ensure_initialized(env);
+        let (accrual, _, _) = Self::preview_state(env);
+        let borrows = accrual.total_borrowed;
+        let pooled_reserves = accrual
+            .total_reserves
+            .saturating_add(accrual.total_admin_fees);
+        let cash = Self::model_cash(env, &token_address, borrows);
+        let utilization = Self::utilization_for(cash, borrows, pooled_reserves);
+        if Self::is_shutdown(env.clone()) {
+            return (utilization, 0u128, 0u128);
+        }
+        let borrow_rate = Self::borrow_rate_for(env, cash, borrows, pooled_reserves, false);
+        let storage = env.storage().persistent();
+        let rf: u128 = storage.get(&DataKey::ReserveFactorScaled).unwrap_or(0u128);
+        let af: u128 = storage.get(&DataKey::AdminFeeScaled).unwrap_or(0u128);
+        let to_suppliers = SCALE_1E6.saturating_sub(rf.saturating_add(af));
+        // Suppliers earn the debt-weighted rate across variable and fixed borrows.
+        let fixed = accrual.total_fixed_borrowed.min(borrows);
+        let paid_rate = if fixed == 0 {
+            borrow_rate
+        } else {
+            borrow_rate
+                .saturating_mul(borrows - fixed)
+                .saturating_add(accrual.fixed_rate.saturating_mul(fixed))
+                / borrows
+        };
+        let supply_rate =
+            paid_rate.saturating_mul(utilization) / SCALE_1E6 * to_suppliers / SCALE_1E6;
+        (utilization, borrow_rate, supply_rate)
+    }
+
+    /// `borrows / (cash + borrows - reserves)` scaled 1e6, as the rate model computes it.
+    fn utilization_for(cash: u128, borrows: u128, pooled_reserves: u128) -> u128 {
+        if borrows == 0 {
+            return 0u128;
+        }
+        let denom = cash.saturating_add(borrows).saturating_sub(pooled_reserves);
+        borrows
+            .saturating_mul(SCALE_1E6)
+            .checked_div(denom)
+            .map_or(SCALE_1E6, |util| util.min(SCALE_1E6))
+    }
+
+    /// Yearly rate (scaled 1e6) compounded per second the way accrual does.
+    fn rate_to_apy(env: &Env, yearly_rate_scaled: u128) -> u128 {
+        if yearly_rate_scaled == 0 {
+            return 0u128;
+        }
+        checked_compound_interest(
+            env,
+            SCALE_1E6,
+            yearly_rate_scaled.min(MAX_YEARLY_RATE_SCALED),
+            SECONDS_PER_YEAR,
+        )
+    }
+
+    /// Borrowed share of the pool (scaled 1e6), as the rate model computes it.
+    pub fn get_utilization(env: Env) -> u128 {
+        Self::current_rates(&env).0
+    }
+
+    /// Current borrow APR (scaled 1e6).
+    pub fn get_borrow_rate_per_year(env: Env) -> u128 {
+        Self::current_rates(&env).1
+    }
+
+    /// Current supply APR (scaled 1e6): borrow APR times utilization, net of
+    /// the reserve factor and admin fee.
+    pub fn get_supply_rate_per_year(env: Env) -> u128 {
+        Self::current_rates(&env).2
+    }
+
+    /// Borrow APY (scaled 1e6) with per-second compounding.
+    pub fn get_borrow_apy(env: Env) -> u128 {
+        let (_, borrow_rate, _) = Self::current_rates(&env);
+        Self::rate_to_apy(&env, borrow_rate)
+    }
+
+    /// Supply APY (scaled 1e6) with per-second compounding.
+    pub fn get_supply_apy(env: Env) -> u128 {
+        let (_, _, supply_rate) = Self::current_rates(&env);
+        Self::rate_to_apy(&env, supply_rate)
+    }
+
+    /// Recorded checkpoints, oldest first.
+    pub fn get_rate_checkpoints(env: Env) -> Vec<RateCheckpoint> {
+        let _ = ensure_initialized(&env);
+        Self::rate_checkpoints(&env)
+    }
+
+    /// Time-weighted averages over the trailing `window_secs`. Each checkpoint's
+    /// values hold until the next one (the latest until now); the window is
+    /// clipped to the oldest checkpoint. `supply_rate` annualizes exchange-rate
+    /// growth between the first and last checkpoints used.
+    pub fn get_rate_averages(env: Env, window_secs: u64) -> Option<RateAverages> {
+        let _ = ensure_initialized(&env);
+        if window_secs == 0 {
+            panic!("invalid window");
+        }
+        let checkpoints = Self::rate_checkpoints(&env);
+        let len = checkpoints.len();
+        if len == 0 {
+            return None;
+        }
+        let now = env.ledger().timestamp();
+        let from = now.saturating_sub(window_secs);
+        // Latest checkpoint at or before the window start carries into it.
+        let mut first = 0u32;
+        for i in 0..len {
+            if checkpoints.get_unchecked(i).timestamp <= from {
+                first = i;
+            } else {
+                break;
+            }
+        }
+        let first_cp = checkpoints.get_unchecked(first);
+        let last_cp = checkpoints.get_unchecked(len - 1);
+        let start = from.max(first_cp.timestamp);
+        let end = now.max(last_cp.timestamp);
+        let mut exchange_rate_sum = 0u128;
+        let mut utilization_sum = 0u128;
+        let mut borrow_rate_sum = 0u128;
+        for i in first..len {
+            let cp = checkpoints.get_unchecked(i);
+            let seg_start = cp.timestamp.max(start);
+            let seg_end = if i + 1 < len {
+                checkpoints.get_unchecked(i + 1).timestamp
+            } else {
+                end
+            };
+            let dt = seg_end.saturating_sub(seg_start) as u128;
+            exchange_rate_sum =
+                exchange_rate_sum.saturating_add(cp.exchange_rate.saturating_mul(dt));
+            utilization_sum = utilization_sum.saturating_add(cp.utilization.saturating_mul(dt));
+            borrow_rate_sum = borrow_rate_sum.saturating_add(cp.borrow_rate.saturating_mul(dt));
+        }
+        let span = end.saturating_sub(start) as u128;
+        let (exchange_rate, utilization, borrow_rate) = if span == 0 {
+            (
+                last_cp.exchange_rate,
+                last_cp.utilization,
+                last_cp.borrow_rate,
+            )
+        } else {
+            (
+                exchange_rate_sum / span,
+                utilization_sum / span,
+                borrow_rate_sum / span,
+            )
+        };
+        let growth_secs = last_cp.timestamp.saturating_sub(first_cp.timestamp) as u128;
+        let supply_rate = if growth_secs == 0 || last_cp.exchange_rate <= first_cp.exchange_rate {
+            0u128
+        } else {
+            U256::from_u128(&env, last_cp.exchange_rate - first_cp.exchange_rate)
+                .mul(&U256::from_u128(&env, SCALE_1E6 * SECONDS_PER_YEAR))
+                .div(
+                    &U256::from_u128(&env, first_cp.exchange_rate)
+                        .mul(&U256::from_u128(&env, growth_secs)),
+                )
+                .to_u128()
+                .unwrap_or(u128::MAX)
+        };
+        Some(RateAverages {
+            start,
+            end,
+            exchange_rate,
+            utilization,
+            borrow_rate,
+            supply_rate,
+        })
+    }
+
+    /// Get the underlying token address
+    pub fn get_underlying_token(env: Env) -> Address {
+        let _ = ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&DataKey::UnderlyingToken)
+            .expect("Vault not initialized")
+    }
+
+    /// Get collateral factor (scaled 1e6)
+    pub fn get_collateral_factor(env: Env) -> u128 {
+        let _ = ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&DataKey::CollateralFactorScaled)
+            .unwrap_or(500_000u128)
+    }
+
+    /// Admin: set peridottroller address
+    pub fn set_peridottroller(env: Env, peridottroller: Address) {
+        let _ = ensure_initialized(&env);
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        admin.require_auth();
+        let token_address: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UnderlyingToken)
+            .expect("Vault not initialized");
+        let _: bool = call_contract_or_panic::<bool, _>(
+            &env,
+            &peridottroller,
+            "is_deposit_paused",
+            (env.current_contract_address(),),
+        );
+        let _: bool = call_contract_or_panic::<bool, _>(
+            &env,
+            &peridottroller,
+            "is_redeem_paused",
+            (env.current_contract_address(),),
+        );
+        let _: bool = call_contract_or_panic::<bool, _>(
+            &env,
+            &peridottroller,
+            "is_borrow_paused",
+            (env.current_contract_address(),),
+        );
+        let _: () = call_contract_or_panic::<(), _>(
+            &env,
+            &peridottroller,
+            "accrue_user_market",
+            (
                 env.current_contract_address(),
                 env.current_contract_address(),
                 Option::<ControllerAccrualHint>::None,
@@ -1524,9 +3330,7 @@ impl ReceiptVault {
         );
         let _price_check: Option<(u128, u128)> =
             call_contract_or_panic(&env, &peridottroller, "get_price_usd", (token_address,));
-        let existing_boosted: Option<Address> =
-            env.storage().persistent().get(&DataKey::BoostedVault);
-        if existing_boosted.is_some() {
+        for strategy in Self::strategies(&env).iter() {
             let _: () = call_contract_or_panic(
                 &env,
                 &peridottroller,
@@ -1534,7 +3338,7 @@ impl ReceiptVault {
                 (
                     env.current_contract_address(),
                     Option::<Address>::None,
-                    existing_boosted.clone(),
+                    Some(strategy.vault),
                 ),
             );
         }
@@ -1544,6 +3348,54 @@ impl ReceiptVault {
         NewPeridottroller { peridottroller }.publish(&env);
     }
 
+    pub fn get_peridottroller(env: Env) -> Option<Address> {
+        let _ = ensure_initialized(&env);
+        env.storage().persistent().get(&DataKey::Peridottroller)
+    }
+
+    /// Peridottroller: enter or leave shutdown settlement for this market.
+    pub fn set_shutdown(env: Env, active: bool) {
+        let _ = ensure_initialized(&env);
+        let peridottroller: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Peridottroller)
+            .expect("peridottroller not set");
+        peridottroller.require_auth();
+        // Accrue up to now under the current mode before switching.
+        Self::update_interest(env.clone());
+        if active {
+            if !Self::is_shutdown(env.clone()) {
+                // Shares are fixed by the supply at entry; a new epoch voids
+                // redemptions counted in an earlier shutdown.
+                let epoch = env
+                    .storage()
+                    .instance()
+                    .get::<_, ShutdownSettlement>(&DataKey::ShutdownSettlement)
+                    .map_or(0, |settlement| settlement.epoch.wrapping_add(1));
+                env.storage().instance().set(
+                    &DataKey::ShutdownSettlement,
+                    &ShutdownSettlement {
+                        epoch,
+                        ptokens: total_ptokens_supply(&env),
+                        paid: 0,
+                    },
+                );
+            }
+            env.storage().instance().set(&DataKey::Shutdown, &true);
+        } else {
+            env.storage().instance().remove(&DataKey::Shutdown);
+        }
+        ShutdownUpdated { active }.publish(&env);
+    }
+
+    pub fn is_shutdown(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::Shutdown)
+            .unwrap_or(false)
+    }
+
     /// Admin: set or clear margin controller address used for collateral lock checks.
     pub fn set_margin_controller(env: Env, admin: Address, margin_controller: Option<Address>) {
         let _ = ensure_initialized(&env);
@@ -1598,7 +3450,117 @@ impl ReceiptVault {
             .set(&DataKey::MarginWithdrawBypass(user), &true);
     }
 
-    /// Admin: set interest rate model address
+    /// Skip redeem health gating on the user's next withdraw; the margin controller
+    /// must check account liquidity before its call returns.
+    pub fn defer_withdraw_health_check(env: Env, margin_controller: Address, user: Address) {
+        let _ = ensure_initialized(&env);
+        let configured: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::MarginController)
+            .expect("margin controller not set");
+        if margin_controller != configured {
+            panic!("not margin controller");
+        }
+        margin_controller.require_auth();
+        env.storage()
+            .persistent()
+            .set(&DataKey::DeferredHealthCheck(user), &true);
+    }
+
+    /// Admin: set or clear the market manager that runs access allowlists.
+    pub fn set_market_manager(env: Env, admin: Address, manager: Option<Address>) {
+        let _ = ensure_initialized(&env);
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if admin != stored_admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        match manager {
+            Some(manager) => env
+                .storage()
+                .persistent()
+                .set(&DataKey::MarketManager, &manager),
+            None => env.storage().persistent().remove(&DataKey::MarketManager),
+        }
+    }
+
+    pub fn get_market_manager(env: Env) -> Option<Address> {
+        let _ = ensure_initialized(&env);
+        env.storage().persistent().get(&DataKey::MarketManager)
+    }
+
+    /// Market manager: choose which side of the market requires allowlisting.
+    pub fn set_access_mode(env: Env, manager: Address, mode: AccessMode) {
+        let _ = ensure_initialized(&env);
+        Self::require_market_manager(&env, &manager);
+        env.storage().instance().set(&DataKey::AccessMode, &mode);
+        AccessModeUpdated { mode }.publish(&env);
+    }
+
+    pub fn get_access_mode(env: Env) -> AccessMode {
+        env.storage()
+            .instance()
+            .get(&DataKey::AccessMode)
+            .unwrap_or(AccessMode::Open)
+    }
+
+    /// Market manager: add or remove an account from the local allowlist.
+    pub fn set_allowlisted(env: Env, manager: Address, account: Address, allowed: bool) {
+        let _ = ensure_initialized(&env);
+        Self::require_market_manager(&env, &manager);
+        let key = DataKey::Allowlisted(account.clone());
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        AllowlistUpdated { account, allowed }.publish(&env);
+    }
+
+    /// Market manager: set or clear an external membership contract exposing
+    /// `is_allowed(account) -> bool`, consulted after the local allowlist.
+    pub fn set_allowlist_source(env: Env, manager: Address, source: Option<Address>) {
+        let _ = ensure_initialized(&env);
+        Self::require_market_manager(&env, &manager);
+        if let Some(source) = source {
+            let _: bool = call_contract_or_panic(
+                &env,
+                &source,
+                "is_allowed",
+                (env.current_contract_address(),),
+            );
+            env.storage()
+                .persistent()
+                .set(&DataKey::AllowlistSource, &source);
+            return;
+        }
+        env.storage().persistent().remove(&DataKey::AllowlistSource);
+    }
+
+    pub fn is_allowlisted(env: Env, account: Address) -> bool {
+        let key = DataKey::Allowlisted(account.clone());
+        if env.storage().persistent().get(&key).unwrap_or(false) {
+            bump_allowlist_ttl(&env, &account);
+            return true;
+        }
+        match env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::AllowlistSource)
+        {
+            Some(source) => call_contract_or_panic(&env, &source, "is_allowed", (account,)),
+            None => false,
+        }
+    }
+
+    /// Admin: set interest rate model address. Staged once the market has
+    /// suppliers, and the borrow rate it quotes now may differ from the current
+    /// one by at most the borrow-rate step.
     pub fn set_interest_model(env: Env, model: Address) {
         let _ = ensure_initialized(&env);
         let admin: Address = env
@@ -1611,7 +3573,7 @@ impl ReceiptVault {
         let _ = call_contract_or_panic::<u128, _>(
             &env,
             &model,
-            "get_borrow_rate",
+            "peek_borrow_rate",
             (0u128, 0u128, 0u128),
         );
         let _ = call_contract_or_panic::<u128, _>(
@@ -1620,12 +3582,335 @@ impl ReceiptVault {
             "get_supply_rate",
             (0u128, 0u128, 0u128, 0u128),
         );
+        if !Self::interest_model_change_ready(&env, &model, false) {
+            return;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::InterestModel, &model.clone());
+        env.storage()
+            .persistent()
+            .remove(&DataKey::InterestModelKeyed);
+        env.storage().persistent().set(&DataKey::RatesReady, &true);
+        bump_rates_ready_ttl(&env);
+        NewInterestModel { model }.publish(&env);
+    }
+
+    /// Admin: use a shared rate model registry that prices this market by its
+    /// own address. The registry must already hold a curve for this market.
+    /// Staged and step-limited like `set_interest_model`.
+    pub fn set_interest_registry(env: Env, registry: Address) {
+        let _ = ensure_initialized(&env);
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        admin.require_auth();
+        let market = env.current_contract_address();
+        let _ = call_contract_or_panic::<u128, _>(
+            &env,
+            &registry,
+            "peek_borrow_rate",
+            (market.clone(), 0u128, 0u128, 0u128),
+        );
+        let _ = call_contract_or_panic::<u128, _>(
+            &env,
+            &registry,
+            "get_supply_rate",
+            (market, 0u128, 0u128, 0u128, 0u128),
+        );
+        if !Self::interest_model_change_ready(&env, &registry, true) {
+            return;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::InterestModel, &registry);
+        env.storage()
+            .persistent()
+            .set(&DataKey::InterestModelKeyed, &true);
+        env.storage().persistent().set(&DataKey::RatesReady, &true);
+        bump_rates_ready_ttl(&env);
+        NewInterestModel { model: registry }.publish(&env);
+    }
+
+    /// Admin: minimum seconds between rate checkpoints.
+    pub fn set_checkpoint_interval(env: Env, admin: Address, interval_secs: u64) {
+        let _ = ensure_initialized(&env);
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored_admin != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        if interval_secs == 0 {
+            panic!("invalid checkpoint interval");
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::CheckpointInterval, &interval_secs);
+        CheckpointIntervalUpdated { interval_secs }.publish(&env);
+    }
+
+    pub fn get_checkpoint_interval(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::CheckpointInterval)
+            .unwrap_or(RATE_CHECKPOINT_INTERVAL_SECS)
+    }
+
+    /// Admin: set the smallest new borrow and the smallest debt a partial
+    /// repay or liquidation may leave (0 disables either). `min_debt` may not
+    /// exceed `min_borrow`, so any allowed borrow leaves a valid debt.
+    pub fn set_dust_limits(env: Env, admin: Address, min_borrow: u128, min_debt: u128) {
+        let _ = ensure_initialized(&env);
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored_admin != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        if min_debt > min_borrow {
+            panic!("invalid dust limits");
+        }
+        env.storage().persistent().set(
+            &DataKey::DustLimits,
+            &DustLimits {
+                min_borrow,
+                min_debt,
+            },
+        );
+        DustLimitsUpdated {
+            min_borrow,
+            min_debt,
+        }
+        .publish(&env);
+    }
+
+    pub fn get_min_borrow(env: Env) -> u128 {
+        Self::dust_limits(&env).min_borrow
+    }
+
+    pub fn get_min_debt(env: Env) -> u128 {
+        Self::dust_limits(&env).min_debt
+    }
+
+    fn dust_limits(env: &Env) -> DustLimits {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DustLimits)
+            .unwrap_or(DustLimits {
+                min_borrow: 0,
+                min_debt: 0,
+            })
+    }
+
+    /// Admin: set or clear the rolling-window limit on net outflows. Payouts
+    /// beyond it are held as delayed claims instead of reverting.
+    pub fn set_outflow_limit(env: Env, admin: Address, limit: Option<OutflowLimit>) {
+        let _ = ensure_initialized(&env);
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored_admin != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        let persistent = env.storage().persistent();
+        match &limit {
+            Some(config) => {
+                if config.max_net_outflow == 0 || config.window_secs == 0 {
+                    panic!("invalid outflow limit");
+                }
+                persistent.set(&DataKey::OutflowLimit, config);
+            }
+            None => persistent.remove(&DataKey::OutflowLimit),
+        }
+        // Start the new limit from a clean window.
+        persistent.remove(&DataKey::OutflowWindow);
+        OutflowLimitUpdated { limit }.publish(&env);
+    }
+
+    pub fn get_outflow_limit(env: Env) -> Option<OutflowLimit> {
+        Self::outflow_limit(&env)
+    }
+
+    /// View: underlying that may leave the market now before payouts are
+    /// delayed (`u128::MAX` when unlimited).
+    pub fn get_outflow_capacity(env: Env) -> u128 {
+        Self::outflow_capacity(&env)
+    }
+
+    pub fn get_delayed_outflow(env: Env, recipient: Address) -> Option<DelayedOutflow> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DelayedOutflow(recipient))
+    }
+
+    pub fn get_total_delayed_outflows(env: Env) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TotalDelayedOutflows)
+            .unwrap_or(0u128)
+    }
+
+    /// Pay out `recipient`'s delayed claim once its delay has passed. Permissionless.
+    pub fn claim_delayed_outflow(env: Env, recipient: Address) -> u128 {
+        let token_address = ensure_initialized(&env);
+        Self::ensure_not_in_flash_loan(&env);
+        let persistent = env.storage().persistent();
+        let key = DataKey::DelayedOutflow(recipient.clone());
+        let Some(claim) = persistent.get::<_, DelayedOutflow>(&key) else {
+            panic!("no delayed outflow");
+        };
+        if env.ledger().timestamp() < claim.claimable_at {
+            panic!("outflow still delayed");
+        }
+        persistent.remove(&key);
+        let total: u128 = persistent
+            .get(&DataKey::TotalDelayedOutflows)
+            .unwrap_or(0u128);
+        persistent.set(
+            &DataKey::TotalDelayedOutflows,
+            &total.saturating_sub(claim.amount),
+        );
+        token::Client::new(&env, &token_address).transfer(
+            &env.current_contract_address(),
+            &recipient,
+            &to_i128(claim.amount),
+        );
+        DelayedOutflowClaimed {
+            recipient,
+            amount: claim.amount,
+        }
+        .publish(&env);
+        claim.amount
+    }
+
+    /// Peridottroller: make `recipient`'s delayed claim payable now (guardian action).
+    pub fn release_delayed_outflow(env: Env, recipient: Address) {
+        Self::set_delayed_outflow_time(&env, &recipient, |_| env.ledger().timestamp());
+    }
+
+    /// Peridottroller: push `recipient`'s delayed claim back by `extra_secs`
+    /// (guardian action).
+    pub fn extend_delayed_outflow(env: Env, recipient: Address, extra_secs: u64) {
+        Self::set_delayed_outflow_time(&env, &recipient, |claimable_at| {
+            claimable_at.saturating_add(extra_secs)
+        });
+    }
+
+    fn set_delayed_outflow_time(env: &Env, recipient: &Address, next: impl Fn(u64) -> u64) {
+        let _ = ensure_initialized(env);
+        let peridottroller: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Peridottroller)
+            .expect("peridottroller not set");
+        peridottroller.require_auth();
+        let persistent = env.storage().persistent();
+        let key = DataKey::DelayedOutflow(recipient.clone());
+        let Some(mut claim) = persistent.get::<_, DelayedOutflow>(&key) else {
+            panic!("no delayed outflow");
+        };
+        claim.claimable_at = next(claim.claimable_at);
+        persistent.set(&key, &claim);
+        bump_delayed_outflow_ttl(env, recipient);
+        DelayedOutflowUpdated {
+            recipient: recipient.clone(),
+            claimable_at: claim.claimable_at,
+        }
+        .publish(env);
+    }
+
+    /// Admin: configure fixed-rate borrowing. Disabling stops new fixed borrows;
+    /// existing positions keep their rates and can still be rebalanced.
+    pub fn set_fixed_rate_config(env: Env, admin: Address, config: FixedRateConfig) {
+        let _ = ensure_initialized(&env);
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored_admin != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        if config.premium > MAX_YEARLY_RATE_SCALED
+            || config.rebalance_spread > MAX_YEARLY_RATE_SCALED
+        {
+            panic!("invalid fixed rate config");
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::FixedRateConfig, &config);
+        FixedRateConfigUpdated {
+            enabled: config.enabled,
+            premium: config.premium,
+            rebalance_spread: config.rebalance_spread,
+        }
+        .publish(&env);
+    }
+
+    /// Admin: configure term borrowing. The curve needs strictly increasing terms.
+    /// Disabling stops new term loans; open loans run to maturity unchanged.
+    pub fn set_term_config(env: Env, admin: Address, config: TermConfig) {
+        let _ = ensure_initialized(&env);
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if stored_admin != admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        let points = config.curve.len();
+        if points == 0
+            || points > MAX_TERM_CURVE_POINTS
+            || config.penalty_spread > MAX_YEARLY_RATE_SCALED
+        {
+            panic!("invalid term config");
+        }
+        let mut prev_term = 0u64;
+        for point in config.curve.iter() {
+            if point.term_secs <= prev_term || point.premium > MAX_YEARLY_RATE_SCALED {
+                panic!("invalid term config");
+            }
+            prev_term = point.term_secs;
+        }
         env.storage()
             .persistent()
-            .set(&DataKey::InterestModel, &model.clone());
-        env.storage().persistent().set(&DataKey::RatesReady, &true);
-        bump_rates_ready_ttl(&env);
-        NewInterestModel { model }.publish(&env);
+            .set(&DataKey::TermConfig, &config);
+        TermConfigUpdated {
+            enabled: config.enabled,
+            penalty_spread: config.penalty_spread,
+        }
+        .publish(&env);
+    }
+
+    pub fn get_term_config(env: Env) -> Option<TermConfig> {
+        Self::term_config(&env)
+    }
+
+    fn term_config(env: &Env) -> Option<TermConfig> {
+        env.storage().persistent().get(&DataKey::TermConfig)
+    }
+
+    pub fn get_fixed_rate_config(env: Env) -> Option<FixedRateConfig> {
+        Self::fixed_rate_config(&env)
+    }
+
+    fn fixed_rate_config(env: &Env) -> Option<FixedRateConfig> {
+        env.storage().persistent().get(&DataKey::FixedRateConfig)
     }
 
     /// Admin: explicitly enable static-rate mode when no external model is used.
@@ -1657,7 +3942,224 @@ impl ReceiptVault {
         bump_rates_ready_ttl(&env);
     }
 
-    /// Admin: set reserve factor (0..=1e6)
+    fn admin_param_change_delay_secs() -> u64 {
+        if cfg!(test) {
+            0
+        } else {
+            ADMIN_PARAM_CHANGE_DELAY_SECS
+        }
+    }
+
+    /// Bounds the step from `current` and stages the change behind the admin
+    /// delay; repeating the same value after the delay applies it. Returns
+    /// true once `value` may be written.
+    fn admin_param_change_ready(
+        env: &Env,
+        param: VaultParam,
+        current: Option<u128>,
+        value: u128,
+    ) -> bool {
+        if current == Some(value) {
+            return true;
+        }
+        let persistent = env.storage().persistent();
+        let pending_key = DataKey::PendingParam(param);
+        // Nothing is at stake before the first deposit, so setup applies directly.
+        if TokenBase::total_supply(env) == 0 {
+            persistent.remove(&pending_key);
+            return true;
+        }
+        Self::check_param_step(param, current.unwrap_or(0), value);
+        let delay = Self::admin_param_change_delay_secs();
+        if delay == 0 {
+            persistent.remove(&pending_key);
+            return true;
+        }
+        bump_pending_param_ttl(env, param);
+        let now = env.ledger().timestamp();
+        if let Some(pending) = persistent.get::<_, PendingParamChange>(&pending_key) {
+            if pending.value == value {
+                if now < pending.execute_after {
+                    panic!("param change timelocked");
+                }
+                persistent.remove(&pending_key);
+                return true;
+            }
+        }
+        let execute_after = now.saturating_add(delay);
+        persistent.set(
+            &pending_key,
+            &PendingParamChange {
+                value,
+                execute_after,
+            },
+        );
+        bump_pending_param_ttl(env, param);
+        PendingParamChangeUpdated {
+            param,
+            value,
+            execute_after,
+        }
+        .publish(env);
+        false
+    }
+
+    fn check_param_step(param: VaultParam, current: u128, value: u128) {
+        let within = match param {
+            VaultParam::ReserveFactor => current.abs_diff(value) <= MAX_RESERVE_FACTOR_STEP,
+            VaultParam::FlashLoanFee => current.abs_diff(value) <= MAX_FLASH_LOAN_FEE_STEP,
+            VaultParam::SupplyRate | VaultParam::BorrowRate => {
+                current.abs_diff(value) <= MAX_RATE_STEP
+            }
+            // Caps may always tighten; 0 (uncapped) has no ratio to bound.
+            VaultParam::SupplyCap | VaultParam::BorrowCap => {
+                current == 0
+                    || value == 0
+                    || value <= current.saturating_mul(MAX_CAP_STEP_MULTIPLIER)
+            }
+        };
+        if !within {
+            panic!("param step too large");
+        }
+    }
+
+    /// Bounds the jump in borrow rate a model swap causes at the current
+    /// utilization and stages the swap behind the admin delay, like
+    /// `admin_param_change_ready`. Accrues under the old model once it may apply.
+    fn interest_model_change_ready(env: &Env, model: &Address, keyed: bool) -> bool {
+        let persistent = env.storage().persistent();
+        let key = DataKey::PendingInterestModel;
+        if TokenBase::total_supply(env) == 0 {
+            persistent.remove(&key);
+            return true;
+        }
+        let token_address = ensure_initialized(env);
+        let (accrual, _, _) = Self::preview_state(env);
+        let borrows = accrual.total_borrowed;
+        let pooled_reserves = accrual
+            .total_reserves
+            .saturating_add(accrual.total_admin_fees);
+        let cash = Self::model_cash(env, &token_address, borrows);
+        let current = Self::borrow_rate_for(env, cash, borrows, pooled_reserves, false);
+        let quoted: u128 = if keyed {
+            call_contract_or_panic(
+                env,
+                model,
+                "peek_borrow_rate",
+                (
+                    env.current_contract_address(),
+                    cash,
+                    borrows,
+                    pooled_reserves,
+                ),
+            )
+        } else {
+            call_contract_or_panic(
+                env,
+                model,
+                "peek_borrow_rate",
+                (cash, borrows, pooled_reserves),
+            )
+        };
+        // With nothing borrowed no one is repriced by the swap.
+        if borrows > 0 {
+            Self::check_param_step(VaultParam::BorrowRate, current, quoted);
+        }
+        let delay = Self::admin_param_change_delay_secs();
+        let now = env.ledger().timestamp();
+        let ready = match persistent.get::<_, PendingInterestModel>(&key) {
+            _ if delay == 0 => true,
+            Some(pending) if pending.model == *model && pending.keyed == keyed => {
+                if now < pending.execute_after {
+                    panic!("param change timelocked");
+                }
+                true
+            }
+            _ => false,
+        };
+        if ready {
+            persistent.remove(&key);
+            Self::update_interest(env.clone());
+            return true;
+        }
+        let execute_after = now.saturating_add(delay);
+        persistent.set(
+            &key,
+            &PendingInterestModel {
+                model: model.clone(),
+                keyed,
+                execute_after,
+            },
+        );
+        bump_pending_interest_model_ttl(env);
+        PendingInterestModelUpdated {
+            model: model.clone(),
+            keyed,
+            execute_after,
+        }
+        .publish(env);
+        false
+    }
+
+    /// View: staged rate model swap, if any.
+    pub fn get_pending_interest_model(env: Env) -> Option<PendingInterestModel> {
+        bump_pending_interest_model_ttl(&env);
+        env.storage()
+            .persistent()
+            .get(&DataKey::PendingInterestModel)
+    }
+
+    /// Admin: drop a staged rate model swap.
+    pub fn cancel_interest_model_change(env: Env) {
+        let _ = ensure_initialized(&env);
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        admin.require_auth();
+        let Some(pending) = env
+            .storage()
+            .persistent()
+            .get::<_, PendingInterestModel>(&DataKey::PendingInterestModel)
+        else {
+            panic!("no pending param change");
+        };
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingInterestModel);
+        PendingInterestModelCancelled {
+            model: pending.model,
+        }
+        .publish(&env);
+    }
+
+    /// View: staged change for `param`, if any.
+    pub fn get_pending_param_change(env: Env, param: VaultParam) -> Option<PendingParamChange> {
+        bump_pending_param_ttl(&env, param);
+        env.storage()
+            .persistent()
+            .get(&DataKey::PendingParam(param))
+    }
+
+    /// Admin: drop a staged parameter change.
+    pub fn cancel_param_change(env: Env, param: VaultParam) {
+        let _ = ensure_initialized(&env);
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        admin.require_auth();
+        let key = DataKey::PendingParam(param);
+        if !env.storage().persistent().has(&key) {
+            panic!("no pending param change");
+        }
+        env.storage().persistent().remove(&key);
+        PendingParamChangeCancelled { param }.publish(&env);
+    }
+
+    /// Admin: set reserve factor (0..=1e6). Staged once the market has suppliers.
     pub fn set_reserve_factor(env: Env, reserve_factor_scaled: u128) {
         let _ = ensure_initialized(&env);
         let admin: Address = env
@@ -1669,6 +4171,18 @@ impl ReceiptVault {
         if reserve_factor_scaled > 1_000_000u128 {
             panic!("Invalid reserve factor");
         }
+        let current = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ReserveFactorScaled);
+        if !Self::admin_param_change_ready(
+            &env,
+            VaultParam::ReserveFactor,
+            current,
+            reserve_factor_scaled,
+        ) {
+            return;
+        }
         env.storage()
             .persistent()
             .set(&DataKey::ReserveFactorScaled, &reserve_factor_scaled);
@@ -1699,7 +4213,8 @@ impl ReceiptVault {
         .publish(&env);
     }
 
-    /// Admin: set flash loan fee (0..=1e6, applied to principal)
+    /// Admin: set flash loan fee (0..=1e6, applied to principal). Staged once
+    /// the market has suppliers.
     pub fn set_flash_loan_fee(env: Env, fee_scaled: u128) {
         let _ = ensure_initialized(&env);
         let admin: Address = env
@@ -1711,6 +4226,10 @@ impl ReceiptVault {
         if fee_scaled > 1_000_000u128 {
             panic!("Invalid flash fee");
         }
+        let current = env.storage().persistent().get(&DataKey::FlashLoanFeeScaled);
+        if !Self::admin_param_change_ready(&env, VaultParam::FlashLoanFee, current, fee_scaled) {
+            return;
+        }
         env.storage()
             .persistent()
             .set(&DataKey::FlashLoanFeeScaled, &fee_scaled);
@@ -1720,7 +4239,41 @@ impl ReceiptVault {
         .publish(&env);
     }
 
-    /// Admin: set supply cap (0 disables)
+    /// Admin: discount the flash fee for `account` when it initiates a loan
+    /// (scaled 1e6; 1_000_000 exempts it, 0 clears the discount).
+    pub fn set_flash_fee_discount(env: Env, account: Address, discount_scaled: u128) {
+        let _ = ensure_initialized(&env);
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        admin.require_auth();
+        if discount_scaled > SCALE_1E6 {
+            panic!("Invalid flash fee discount");
+        }
+        let key = DataKey::FlashLoanFeeDiscount(account.clone());
+        if discount_scaled == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &discount_scaled);
+        }
+        FlashFeeDiscountUpdated {
+            account,
+            discount_mantissa: discount_scaled,
+        }
+        .publish(&env);
+    }
+
+    /// View: flash fee discount for `account` (scaled 1e6).
+    pub fn get_flash_fee_discount(env: Env, account: Address) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::FlashLoanFeeDiscount(account))
+            .unwrap_or(0u128)
+    }
+
+    /// Admin: set supply cap (0 disables). Staged once the market has suppliers.
     pub fn set_supply_cap(env: Env, cap: u128) {
         let _ = ensure_initialized(&env);
         let admin: Address = env
@@ -1729,11 +4282,15 @@ impl ReceiptVault {
             .get(&DataKey::Admin)
             .expect("admin not set");
         admin.require_auth();
+        let current = env.storage().persistent().get(&DataKey::SupplyCap);
+        if !Self::admin_param_change_ready(&env, VaultParam::SupplyCap, current, cap) {
+            return;
+        }
         env.storage().persistent().set(&DataKey::SupplyCap, &cap);
         NewSupplyCap { supply_cap: cap }.publish(&env);
     }
 
-    /// Admin: set borrow cap (0 disables)
+    /// Admin: set borrow cap (0 disables). Staged once the market has suppliers.
     pub fn set_borrow_cap(env: Env, cap: u128) {
         let _ = ensure_initialized(&env);
         let admin: Address = env
@@ -1743,6 +4300,10 @@ impl ReceiptVault {
             .expect("admin not set");
         admin.require_auth();
         let storage = env.storage().persistent();
+        let current = storage.get(&DataKey::BorrowCap);
+        if !Self::admin_param_change_ready(&env, VaultParam::BorrowCap, current, cap) {
+            return;
+        }
         if cap == 0 {
             // Disable principal tracking when cap is disabled to avoid stale state.
             storage.remove(&DataKey::TotalBorrowPrincipal);
@@ -1831,60 +4392,379 @@ impl ReceiptVault {
         if amount > fees {
             panic!("Insufficient admin fees");
         }
-        let updated_fees = fees.saturating_sub(amount);
+        let updated_fees = fees.saturating_sub(amount);
+        env.storage()
+            .persistent()
+            .set(&DataKey::TotalAdminFees, &updated_fees);
+        // Transfer underlying to admin
+        let token_client = token::Client::new(&env, &token_address);
+        let amount_i128 = to_i128(amount);
+        let cash_before = Self::current_live_cash(&env, &token_address);
+        token_client.transfer(&env.current_contract_address(), &admin, &amount_i128);
+        let cash_after = Self::current_live_cash(&env, &token_address);
+        Self::sub_managed_cash(&env, cash_before.saturating_sub(cash_after));
+        AdminFeesReduced {
+            reduce_amount: amount,
+            total_admin_fees: updated_fees,
+        }
+        .publish(&env);
+    }
+
+    /// Admin: set or clear the treasury allowed to call `sweep_to_treasury`.
+    pub fn set_treasury(env: Env, admin: Address, treasury: Option<Address>) {
+        let _ = ensure_initialized(&env);
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set");
+        if admin != stored_admin {
+            panic!("not admin");
+        }
+        admin.require_auth();
+        if let Some(addr) = treasury.clone() {
+            env.storage().persistent().set(&DataKey::Treasury, &addr);
+        } else {
+            env.storage().persistent().remove(&DataKey::Treasury);
+        }
+        TreasuryUpdated { treasury }.publish(&env);
+    }
+
+    pub fn get_treasury(env: Env) -> Option<Address> {
+        let _ = ensure_initialized(&env);
+        env.storage().persistent().get(&DataKey::Treasury)
+    }
+
+    /// Treasury: accrue, then pay out all reserves and admin fees that cash
+    /// can cover (reserves first). Returns `(reserves, admin_fees)` swept.
+    pub fn sweep_to_treasury(env: Env) -> (u128, u128) {
+        let token_address = ensure_initialized(&env);
+        let treasury: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Treasury)
+            .expect("treasury not set");
+        treasury.require_auth();
+        Self::ensure_not_in_flash_loan(&env);
+        if Self::is_shutdown(env.clone()) {
+            panic!("market shut down");
+        }
+        Self::update_interest(env.clone());
+        let reserves: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalReserves)
+            .unwrap_or(0u128);
+        let fees: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalAdminFees)
+            .unwrap_or(0u128);
+        let owed = reserves.saturating_add(fees);
+        if owed == 0 {
+            return (0u128, 0u128);
+        }
+        Self::ensure_liquid_cash(&env, &token_address, owed);
+        let live_cash = Self::current_live_cash(&env, &token_address);
+        let reserves_out = reserves.min(live_cash);
+        let fees_out = fees.min(live_cash - reserves_out);
+        let total_out = reserves_out + fees_out;
+        if total_out == 0 {
+            return (0u128, 0u128);
+        }
+        let token_client = token::Client::new(&env, &token_address);
+        let cash_before = Self::current_live_cash(&env, &token_address);
+        token_client.transfer(
+            &env.current_contract_address(),
+            &treasury,
+            &to_i128(total_out),
+        );
+        let cash_after = Self::current_live_cash(&env, &token_address);
+        Self::sub_managed_cash(&env, cash_before.saturating_sub(cash_after));
+        if reserves_out > 0 {
+            let updated_reserves = reserves - reserves_out;
+            env.storage()
+                .persistent()
+                .set(&DataKey::TotalReserves, &updated_reserves);
+            ReservesReduced {
+                reduce_amount: reserves_out,
+                total_reserves: updated_reserves,
+            }
+            .publish(&env);
+        }
+        if fees_out > 0 {
+            let updated_fees = fees - fees_out;
+            env.storage()
+                .persistent()
+                .set(&DataKey::TotalAdminFees, &updated_fees);
+            AdminFeesReduced {
+                reduce_amount: fees_out,
+                total_admin_fees: updated_fees,
+            }
+            .publish(&env);
+        }
+        (reserves_out, fees_out)
+    }
+
+    //
+
+    /// Update interest based on elapsed time and current per-second rate
+    pub fn update_interest(env: Env) {
+        if env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::UnderlyingToken)
+            .is_none()
+        {
+            return;
+        }
+        bump_core_ttl(&env);
+        bump_borrow_state_ttl(&env);
+        let last_time: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::LastUpdateTime)
+            .expect("last update missing");
+        let now = env.ledger().timestamp();
+        if now <= last_time {
+            return;
+        }
+        if Self::is_shutdown(env.clone()) {
+            // Accrual is frozen during shutdown; skip the period rather than defer it.
+            env.storage()
+                .persistent()
+                .set(&DataKey::LastUpdateTime, &now);
+            return;
+        }
+        let elapsed = (now - last_time) as u128;
+        let strategies = Self::strategies(&env);
+        let quotes = Self::strategy_quotes(&env, &strategies);
+        Self::recognize_strategy_losses(&env, &strategies, &quotes, true);
+        let token_address: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UnderlyingToken)
+            .expect("underlying not set");
+        let tb_prior: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalBorrowed)
+            .expect("total borrowed missing");
+        let accrual = Self::pending_accrual(&env, &token_address, elapsed, true);
+        env.storage()
+            .instance()
+            .set(&DataKey::LastBorrowRate, &accrual.borrow_rate);
+        let accruing = accrual.borrow_rate > 0 || accrual.fixed_rate > 0;
+        if tb_prior > 0 && accruing {
+            let storage = env.storage().persistent();
+            storage.set(&DataKey::TotalReserves, &accrual.total_reserves);
+            storage.set(&DataKey::TotalAdminFees, &accrual.total_admin_fees);
+            storage.set(&DataKey::TotalBorrowed, &accrual.total_borrowed);
+            storage.set(&DataKey::BorrowIndex, &accrual.borrow_index);
+        }
+
+        AccrueInterest {
+            interest_accumulated: accrual.interest,
+            borrow_index: accrual.borrow_index,
+            total_borrows: accrual.total_borrowed,
+        }
+        .publish(&env);
+
+        // Move time forward only when accrual inputs cannot produce future interest
+        // (no debt or zero rate) or this update accrued a non-zero amount.
+        if tb_prior == 0 || !accruing || accrual.interest > 0 {
+            env.storage()
+                .persistent()
+                .set(&DataKey::LastUpdateTime, &now);
+        }
+        Self::record_rate_checkpoint(&env, &accrual, now);
+    }
+
+    /// Write a checkpoint once the interval since the last one has passed,
+    /// overwriting the oldest slot when the buffer is full.
+    fn record_rate_checkpoint(env: &Env, accrual: &PendingAccrual, now: u64) {
+        let instance = env.storage().instance();
+        let interval: u64 = instance
+            .get(&DataKey::CheckpointInterval)
+            .unwrap_or(RATE_CHECKPOINT_INTERVAL_SECS);
+        if let Some(last) = instance.get::<_, u64>(&DataKey::CheckpointAt) {
+            if now < last.saturating_add(interval) {
+                return;
+            }
+        }
+        if total_ptokens_supply(env) == 0 {
+            return;
+        }
+        let total_underlying = Self::total_underlying_with(
+            env,
+            Self::get_boosted_underlying(env),
+            accrual.total_borrowed,
+            accrual.total_reserves,
+            accrual.total_admin_fees,
+        );
+        let checkpoint = RateCheckpoint {
+            timestamp: now,
+            exchange_rate: Self::exchange_rate_for(env, total_underlying),
+            borrow_index: accrual.borrow_index,
+            utilization: accrual.utilization,
+            borrow_rate: accrual.borrow_rate,
+        };
+        let head: u32 = instance.get(&DataKey::RateCheckpointHead).unwrap_or(0u32);
+        let len: u32 = instance.get(&DataKey::RateCheckpointLen).unwrap_or(0u32);
+        let slot = (head + len) % MAX_RATE_CHECKPOINTS;
+        if len < MAX_RATE_CHECKPOINTS {
+            instance.set(&DataKey::RateCheckpointLen, &(len + 1));
+        } else {
+            // Full: `slot` is the oldest checkpoint, which the new one replaces.
+            instance.set(
+                &DataKey::RateCheckpointHead,
+                &((head + 1) % MAX_RATE_CHECKPOINTS),
+            );
+        }
         env.storage()
             .persistent()
-            .set(&DataKey::TotalAdminFees, &updated_fees);
-        // Transfer underlying to admin
-        let token_client = token::Client::new(&env, &token_address);
-        let amount_i128 = to_i128(amount);
-        let cash_before = Self::current_live_cash(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &admin, &amount_i128);
-        let cash_after = Self::current_live_cash(&env, &token_address);
-        Self::sub_managed_cash(&env, cash_before.saturating_sub(cash_after));
-        AdminFeesReduced {
-            reduce_amount: amount,
-            total_admin_fees: updated_fees,
-        }
-        .publish(&env);
+            .set(&DataKey::RateCheckpoint(slot), &checkpoint);
+        bump_rate_checkpoint_ttl(env, slot);
+        instance.set(&DataKey::CheckpointAt, &now);
     }
 
-    //
+    fn rate_checkpoints(env: &Env) -> Vec<RateCheckpoint> {
+        let instance = env.storage().instance();
+        let head: u32 = instance.get(&DataKey::RateCheckpointHead).unwrap_or(0u32);
+        let len: u32 = instance.get(&DataKey::RateCheckpointLen).unwrap_or(0u32);
+        let mut checkpoints = Vec::new(env);
+        for i in 0..len {
+            let key = DataKey::RateCheckpoint((head + i) % MAX_RATE_CHECKPOINTS);
+            if let Some(checkpoint) = env.storage().persistent().get(&key) {
+                checkpoints.push_back(checkpoint);
+            }
+        }
+        checkpoints
+    }
 
-    /// Update interest based on elapsed time and current per-second rate
-    pub fn update_interest(env: Env) {
-        if env
+    /// Borrow interest for `elapsed` seconds split into reserves, admin fees and the
+    /// borrow index, without writing any of it. `update_interest` persists the result
+    /// (`accruing`); preview views read it directly.
+    fn pending_accrual(
+        env: &Env,
+        token_address: &Address,
+        elapsed: u128,
+        accruing: bool,
+    ) -> PendingAccrual {
+        // Borrow interest accrual via global index (split to reserves, admin fees, and suppliers)
+        let tb_prior: u128 = env
             .storage()
             .persistent()
-            .get::<_, Address>(&DataKey::UnderlyingToken)
-            .is_none()
-        {
-            return;
-        }
-        bump_core_ttl(&env);
-        bump_borrow_state_ttl(&env);
+            .get(&DataKey::TotalBorrowed)
+            .expect("total borrowed missing");
+        let current_reserves: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalReserves)
+            .unwrap_or(0u128);
+        let current_admin_fees: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TotalAdminFees)
+            .unwrap_or(0u128);
+        let fixed_pool = Self::fixed_debt_pool(env);
         let last_time: u64 = env
             .storage()
             .persistent()
             .get(&DataKey::LastUpdateTime)
             .expect("last update missing");
-        let now = env.ledger().timestamp();
-        if now <= last_time {
-            return;
+        let fixed_prior_raw = Self::fixed_pool_debt(&fixed_pool, last_time);
+        let fixed_prior = fixed_prior_raw.min(tb_prior);
+        let mut accrual = PendingAccrual {
+            borrow_rate: 0,
+            fixed_rate: Self::fixed_pool_rate(&fixed_pool),
+            utilization: 0,
+            interest: 0,
+            total_borrowed: tb_prior,
+            total_fixed_borrowed: fixed_prior,
+            total_reserves: current_reserves,
+            total_admin_fees: current_admin_fees,
+            borrow_index: env
+                .storage()
+                .persistent()
+                .get(&DataKey::BorrowIndex)
+                .expect("borrow index missing"),
+        };
+        if elapsed == 0 {
+            return accrual;
         }
-        let elapsed = (now - last_time) as u128;
-        let token_address: Address = env
+
+        let model_cash = Self::model_cash(env, token_address, tb_prior);
+        let pooled_reserves = current_reserves.saturating_add(current_admin_fees);
+        let borrow_yearly_rate_scaled =
+            Self::borrow_rate_for(env, model_cash, tb_prior, pooled_reserves, accruing);
+        if borrow_yearly_rate_scaled > MAX_YEARLY_RATE_SCALED {
+            panic!("interest rate out of bounds");
+        }
+        accrual.borrow_rate = borrow_yearly_rate_scaled;
+        accrual.utilization = Self::utilization_for(model_cash, tb_prior, pooled_reserves);
+        if tb_prior == 0 || (borrow_yearly_rate_scaled == 0 && accrual.fixed_rate == 0) {
+            return accrual;
+        }
+
+        // Variable debt compounds at the market rate and moves the borrow index;
+        // fixed debt grows by exactly what its positions accrued.
+        let variable_prior = tb_prior - fixed_prior;
+        let variable_interest = if variable_prior == 0 || borrow_yearly_rate_scaled == 0 {
+            0u128
+        } else {
+            checked_compound_interest(env, variable_prior, borrow_yearly_rate_scaled, elapsed)
+        };
+        let fixed_interest = if fixed_prior == 0 || accrual.fixed_rate == 0 {
+            0u128
+        } else {
+            let at = last_time.saturating_add(elapsed as u64);
+            Self::fixed_pool_debt(&fixed_pool, at).saturating_sub(fixed_prior_raw)
+        };
+        let borrow_interest_total = variable_interest.saturating_add(fixed_interest);
+        accrual.interest = borrow_interest_total;
+
+        // Split between reserves, admin fees and suppliers based on factors
+        let rf: u128 = env
             .storage()
             .persistent()
-            .get(&DataKey::UnderlyingToken)
-            .expect("underlying not set");
-        // Borrow interest accrual via global index (split to reserves, admin fees, and suppliers)
-        let tb_prior: u128 = env
+            .get(&DataKey::ReserveFactorScaled)
+            .unwrap_or(0u128);
+        let af: u128 = env
             .storage()
             .persistent()
-            .get(&DataKey::TotalBorrowed)
-            .expect("total borrowed missing");
+            .get(&DataKey::AdminFeeScaled)
+            .unwrap_or(0u128);
+        let to_reserves = (borrow_interest_total.saturating_mul(rf)) / SCALE_1E6;
+        let to_admin = (borrow_interest_total.saturating_mul(af)) / SCALE_1E6;
+        accrual.total_reserves = current_reserves.saturating_add(to_reserves);
+        accrual.total_admin_fees = current_admin_fees.saturating_add(to_admin);
+
+        // Increase total borrowed by total interest; supplier yield is
+        // reflected through exchange-rate math via the borrow growth path.
+        accrual.total_borrowed = tb_prior.saturating_add(borrow_interest_total);
+        accrual.total_fixed_borrowed = fixed_prior.saturating_add(fixed_interest);
+        if variable_interest == 0 {
+            return accrual;
+        }
+
+        // Update borrow index with checked math (no saturating overflow).
+        // delta = old_index * variable_interest / variable_prior, widened to U256
+        // since compounded interest rarely shares factors with the variable debt.
+        let old_index = accrual.borrow_index;
+        let delta_index = U256::from_u128(env, old_index)
+            .mul(&U256::from_u128(env, variable_interest))
+            .div(&U256::from_u128(env, variable_prior))
+            .to_u128()
+            .expect("borrow index overflow");
+        accrual.borrow_index = old_index
+            .checked_add(delta_index)
+            .expect("borrow index overflow");
+        accrual
+    }
 
+    /// Gross cash handed to the rate model: live cash plus boosted underlying.
+    fn model_cash(env: &Env, token_address: &Address, tb_prior: u128) -> u128 {
         // Snapshot gross cash once so rate queries use raw liquidity inputs and
         // reserves are subtracted only inside the rate model.
         //
@@ -1893,9 +4773,9 @@ impl ReceiptVault {
         // trusting extreme external quotes while preserving legitimate yield growth.
         // If baseline is unavailable while borrows are outstanding, fail-safe by
         // ignoring boosted cash for this accrual tick.
-        let cached_before = Self::cached_boosted_underlying(&env);
-        let boosted_reported = Self::get_boosted_underlying(&env);
-        let boosted_accounting = Self::estimate_boosted_underlying_from_accounting(&env);
+        let cached_before = Self::cached_boosted_underlying(env);
+        let boosted_reported = Self::get_boosted_underlying(env);
+        let boosted_accounting = Self::estimate_boosted_underlying_from_accounting(env);
         let boosted_baseline = cached_before.max(boosted_accounting);
         let boosted_cap = if boosted_baseline == 0 {
             if tb_prior > 0 {
@@ -1909,45 +4789,61 @@ impl ReceiptVault {
             )
         };
         let boosted_for_model = boosted_reported.min(boosted_cap);
-        let model_cash =
-            Self::current_live_cash(&env, &token_address).saturating_add(boosted_for_model);
-
-        let current_reserves: u128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::TotalReserves)
-            .unwrap_or(0u128);
-        let current_admin_fees: u128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::TotalAdminFees)
-            .unwrap_or(0u128);
-        let pooled_reserves = current_reserves.saturating_add(current_admin_fees);
+        Self::current_live_cash(env, token_address).saturating_add(boosted_for_model)
+    }
 
-        let mut interest_accumulated_event: u128 = 0u128;
-        let mut event_borrow_index: u128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::BorrowIndex)
-            .expect("borrow index missing");
-        let mut event_total_borrows: u128 = tb_prior;
-        let mut advance_last_update = tb_prior == 0;
-        // Determine borrow yearly rate from model if set, else static
-        let borrow_yearly_rate_scaled: u128 = if let Some(model) = env
+    /// Borrow yearly rate from the interest model if set, else the static rate.
+    /// A failing model call falls back to the static rate. Only accrual
+    /// (`accruing`) calls `get_borrow_rate`, which lets a stateful model advance
+    /// its curve, and reports failures; views and staging checks peek.
+    fn borrow_rate_for(
+        env: &Env,
+        model_cash: u128,
+        borrows: u128,
+        pooled_reserves: u128,
+        accruing: bool,
+    ) -> u128 {
+        if let Some(model) = env
             .storage()
             .persistent()
             .get::<_, Address>(&DataKey::InterestModel)
         {
-            let borrows: u128 = tb_prior;
-            match try_call_contract(
-                &env,
-                &model,
-                "get_borrow_rate",
-                (model_cash, borrows, pooled_reserves),
-            ) {
+            let keyed = env
+                .storage()
+                .persistent()
+                .get(&DataKey::InterestModelKeyed)
+                .unwrap_or(false);
+            let function = if accruing {
+                "get_borrow_rate"
+            } else {
+                "peek_borrow_rate"
+            };
+            let quoted = if keyed {
+                try_call_contract(
+                    env,
+                    &model,
+                    function,
+                    (
+                        env.current_contract_address(),
+                        model_cash,
+                        borrows,
+                        pooled_reserves,
+                    ),
+                )
+            } else {
+                try_call_contract(
+                    env,
+                    &model,
+                    function,
+                    (model_cash, borrows, pooled_reserves),
+                )
+            };
+            match quoted {
                 Ok(rate) => rate,
                 Err(err) => {
-                    emit_external_call_failure(&env, &model, &err, true);
+                    if accruing {
+                        emit_external_call_failure(env, &model, &err, true);
+                    }
                     env.storage()
                         .persistent()
                         .get(&DataKey::BorrowYearlyRateScaled)
@@ -1959,97 +4855,6 @@ impl ReceiptVault {
                 .persistent()
                 .get(&DataKey::BorrowYearlyRateScaled)
                 .expect("borrow yearly rate missing")
-        };
-        if borrow_yearly_rate_scaled > MAX_YEARLY_RATE_SCALED {
-            panic!("interest rate out of bounds");
-        }
-        if tb_prior > 0 && borrow_yearly_rate_scaled > 0 {
-            let borrow_interest_total =
-                checked_interest_product(&env, tb_prior, borrow_yearly_rate_scaled, elapsed);
-            interest_accumulated_event = borrow_interest_total;
-            if borrow_interest_total > 0 {
-                advance_last_update = true;
-            }
-
-            // Split between reserves, admin fees and suppliers based on factors
-            let rf: u128 = env
-                .storage()
-                .persistent()
-                .get(&DataKey::ReserveFactorScaled)
-                .unwrap_or(0u128);
-            let af: u128 = env
-                .storage()
-                .persistent()
-                .get(&DataKey::AdminFeeScaled)
-                .unwrap_or(0u128);
-            let to_reserves = (borrow_interest_total.saturating_mul(rf)) / SCALE_1E6;
-            let to_admin = (borrow_interest_total.saturating_mul(af)) / SCALE_1E6;
-            let _to_suppliers = borrow_interest_total
-                .saturating_sub(to_reserves)
-                .saturating_sub(to_admin);
-
-            // Update total reserves and admin fees
-            let current_reserves: u128 = env
-                .storage()
-                .persistent()
-                .get(&DataKey::TotalReserves)
-                .unwrap_or(0u128);
-            env.storage().persistent().set(
-                &DataKey::TotalReserves,
-                &current_reserves.saturating_add(to_reserves),
-            );
-            let current_fees: u128 = env
-                .storage()
-                .persistent()
-                .get(&DataKey::TotalAdminFees)
-                .unwrap_or(0u128);
-            env.storage().persistent().set(
-                &DataKey::TotalAdminFees,
-                &current_fees.saturating_add(to_admin),
-            );
-
-            // Increase total borrowed by total interest; supplier yield is
-            // reflected through exchange-rate math via the borrow growth path.
-            let tb_after = tb_prior.saturating_add(borrow_interest_total);
-            env.storage()
-                .persistent()
-                .set(&DataKey::TotalBorrowed, &tb_after);
-            event_total_borrows = tb_after;
-
-            // Update borrow index with checked math (no saturating overflow).
-            // delta = old_index * borrow_interest / tb_prior
-            let old_index: u128 = env
-                .storage()
-                .persistent()
-                .get(&DataKey::BorrowIndex)
-                .expect("borrow index missing");
-            let delta_index =
-                Self::checked_mul_div_u128(old_index, borrow_interest_total, tb_prior);
-            let new_index = old_index
-                .checked_add(delta_index)
-                .expect("borrow index overflow");
-            env.storage()
-                .persistent()
-                .set(&DataKey::BorrowIndex, &new_index);
-            event_borrow_index = new_index;
-        }
-        if tb_prior > 0 && borrow_yearly_rate_scaled == 0 {
-            advance_last_update = true;
-        }
-
-        AccrueInterest {
-            interest_accumulated: interest_accumulated_event,
-            borrow_index: event_borrow_index,
-            total_borrows: event_total_borrows,
-        }
-        .publish(&env);
-
-        // Move time forward only when accrual inputs cannot produce future interest
-        // (no debt or zero rate) or this update accrued a non-zero amount.
-        if advance_last_update {
-            env.storage()
-                .persistent()
-                .set(&DataKey::LastUpdateTime, &now);
         }
     }
 
@@ -2141,8 +4946,6 @@ impl ReceiptVault {
 
     /// Get total underlying
     pub fn get_total_underlying(env: Env) -> u128 {
-        // managed_cash + boosted_underlying + borrows - reserves - admin_fees
-        let cash = Self::get_managed_cash(&env);
         let borrows: u128 = env
             .storage()
             .persistent()
@@ -2159,6 +4962,18 @@ impl ReceiptVault {
             .get(&DataKey::TotalAdminFees)
             .unwrap_or(0u128);
         let boosted_underlying = Self::get_boosted_underlying(&env);
+        Self::total_underlying_with(&env, boosted_underlying, borrows, reserves, admin_fees)
+    }
+
+    fn total_underlying_with(
+        env: &Env,
+        boosted_underlying: u128,
+        borrows: u128,
+        reserves: u128,
+        admin_fees: u128,
+    ) -> u128 {
+        // managed_cash + boosted_underlying + borrows - reserves - admin_fees
+        let cash = Self::get_managed_cash(env);
         cash.saturating_add(boosted_underlying)
             .saturating_add(borrows)
             .saturating_sub(reserves)
@@ -2166,6 +4981,7 @@ impl ReceiptVault {
     }
 
     /// Admin: update yearly interest rate (scaled 1e6). Applies after accruing with old rate.
+    /// Staged once the market has suppliers.
     pub fn set_interest_rate(env: Env, yearly_rate_scaled: u128) {
         let _ = ensure_initialized(&env);
         // Admin guard
@@ -2186,6 +5002,15 @@ impl ReceiptVault {
         if yearly_rate_scaled > borrow_rate_scaled {
             panic!("invalid rate relationship");
         }
+        let current = env.storage().persistent().get(&DataKey::YearlyRateScaled);
+        if !Self::admin_param_change_ready(
+            &env,
+            VaultParam::SupplyRate,
+            current,
+            yearly_rate_scaled,
+        ) {
+            return;
+        }
         // Accrue with old rate first
         Self::update_interest(env.clone());
         env.storage()
@@ -2197,7 +5022,7 @@ impl ReceiptVault {
         .publish(&env);
     }
 
-    /// Admin: update borrow yearly rate (scaled 1e6)
+    /// Admin: update borrow yearly rate (scaled 1e6). Staged once the market has suppliers.
     pub fn set_borrow_rate(env: Env, yearly_rate_scaled: u128) {
         let _ = ensure_initialized(&env);
         // Admin guard
@@ -2218,6 +5043,18 @@ impl ReceiptVault {
         if supply_rate_scaled > yearly_rate_scaled {
             panic!("invalid rate relationship");
         }
+        let current = env
+            .storage()
+            .persistent()
+            .get(&DataKey::BorrowYearlyRateScaled);
+        if !Self::admin_param_change_ready(
+            &env,
+            VaultParam::BorrowRate,
+            current,
+            yearly_rate_scaled,
+        ) {
+            return;
+        }
         Self::update_interest(env.clone());
         env.storage()
             .persistent()
@@ -2259,14 +5096,40 @@ impl ReceiptVault {
             .expect("admin not set")
     }
 
-    /// Get user's current borrow balance (principal adjusted by index)
+    /// Get user's current borrow balance: variable debt adjusted by index plus
+    /// fixed-rate debt.
     pub fn get_user_borrow_balance(env: Env, user: Address) -> u128 {
         let _ = ensure_initialized(&env);
+        Self::user_borrow_balance_at(&env, &user, None)
+    }
+
+    /// Borrow balance at `index`, or at the stored borrow index when `None`.
+    /// Fixed-rate debt is valued at the current ledger alongside an explicit
+    /// index, else at the last accrual.
+    fn user_borrow_balance_at(env: &Env, user: &Address, index: Option<u128>) -> u128 {
+        let live = index.is_some();
+        Self::variable_borrow_balance_at(env, user, index)
+            .saturating_add(Self::fixed_borrow_balance(env, user, live))
+            .saturating_add(Self::term_borrow_balance(env, user, live).0)
+    }
+
+    /// Current ledger time when `live`, else the last interest accrual.
+    fn borrow_valuation_time(env: &Env, live: bool) -> u64 {
+        if live {
+            return env.ledger().timestamp();
+        }
+        env.storage()
+            .persistent()
+            .get(&DataKey::LastUpdateTime)
+            .expect("last update missing")
+    }
+
+    fn variable_borrow_balance_at(env: &Env, user: &Address, index: Option<u128>) -> u128 {
         let has_borrowed: Option<bool> = env
             .storage()
             .persistent()
             .get(&DataKey::HasBorrowed(user.clone()));
-        bump_user_borrow_state_ttl(&env, &user);
+        bump_user_borrow_state_ttl(env, user);
         let snap: Option<BorrowSnapshot> = env
             .storage()
             .persistent()
@@ -2276,7 +5139,7 @@ impl ReceiptVault {
                 panic!("borrow snapshot missing");
             }
             // Fail closed for collateralized accounts with missing borrow state.
-            if has_borrowed.is_none() && ptoken_balance(&env, &user) > 0 {
+            if has_borrowed.is_none() && ptoken_balance(env, user) > 0 {
                 panic!("borrow state missing");
             }
             return 0u128;
@@ -2284,15 +5147,219 @@ impl ReceiptVault {
         if snapshot.principal == 0 {
             return 0u128;
         }
-        let current_index: u128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::BorrowIndex)
-            .expect("borrow index missing");
+        let current_index: u128 = index.unwrap_or_else(|| {
+            env.storage()
+                .persistent()
+                .get(&DataKey::BorrowIndex)
+                .expect("borrow index missing")
+        });
         // principal * current_index / user_index
         (snapshot.principal.saturating_mul(current_index)) / snapshot.interest_index
     }
 
+    fn fixed_borrow_snapshot(env: &Env, user: &Address) -> Option<FixedBorrowSnapshot> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::FixedBorrowSnapshots(user.clone()))
+    }
+
+    /// Fixed-rate debt accrued at the locked rate up to the current ledger
+    /// when `live`, else up to the last accrual.
+    fn fixed_borrow_balance(env: &Env, user: &Address, live: bool) -> u128 {
+        let Some(snapshot) = Self::fixed_borrow_snapshot(env, user) else {
+            return 0u128;
+        };
+        let at = Self::borrow_valuation_time(env, live);
+        Self::fixed_debt_at(
+            env,
+            snapshot.principal,
+            snapshot.rate,
+            snapshot.updated_at,
+            at,
+        )
+    }
+
+    /// `principal` plus simple interest at `rate` from `from` to `at`.
+    fn fixed_debt_at(env: &Env, principal: u128, rate: u128, from: u64, at: u64) -> u128 {
+        let elapsed = at.saturating_sub(from) as u128;
+        if principal == 0 || rate == 0 || elapsed == 0 {
+            return principal;
+        }
+        principal.saturating_add(checked_interest_product(env, principal, rate, elapsed))
+    }
+
+    fn fixed_debt_pool(env: &Env) -> FixedDebtPool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::FixedDebtPool)
+            .unwrap_or(FixedDebtPool {
+                principal: 0,
+                rate_weight: 0,
+                time_weight: 0,
+            })
+    }
+
+    /// Sum of all fixed-rate and term debt at `at`.
+    fn fixed_pool_debt(pool: &FixedDebtPool, at: u64) -> u128 {
+        let accrued_weight = pool
+            .rate_weight
+            .checked_mul(at as u128)
+            .expect("interest overflow")
+            .saturating_sub(pool.time_weight);
+        pool.principal
+            .saturating_add(accrued_weight / SECONDS_PER_YEAR.saturating_mul(SCALE_1E6))
+    }
+
+    /// Principal-weighted locked rate across the pool (scaled 1e6).
+    fn fixed_pool_rate(pool: &FixedDebtPool) -> u128 {
+        if pool.principal == 0 {
+            return 0u128;
+        }
+        (pool.rate_weight / pool.principal).min(MAX_YEARLY_RATE_SCALED)
+    }
+
+    fn term_loans(env: &Env, user: &Address) -> Vec<TermLoan> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TermLoans(user.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn write_term_loans(env: &Env, user: &Address, loans: &Vec<TermLoan>) {
+        let key = DataKey::TermLoans(user.clone());
+        if loans.is_empty() {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+        env.storage().persistent().set(&key, loans);
+        bump_term_loans_ttl(env, user);
+    }
+
+    /// Variable rate of the latest accrual that overdue loans compound at, before
+    /// the penalty spread.
+    fn term_penalty_base_rate(env: &Env) -> u128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::LastBorrowRate)
+            .unwrap_or_else(|| {
+                env.storage()
+                    .persistent()
+                    .get(&DataKey::BorrowYearlyRateScaled)
+                    .unwrap_or(0u128)
+            })
+    }
+
+    /// Loan debt at `at`: simple interest at the quoted rate until maturity, then at
+    /// the penalty rate. A loan settled past maturity keeps its pegged penalty rate.
+    fn term_loan_balance(env: &Env, loan: &TermLoan, at: u64, penalty_base_rate: u128) -> u128 {
+        if loan.updated_at >= loan.maturity {
+            return Self::fixed_debt_at(
+                env,
+                loan.principal,
+                loan.penalty_rate,
+                loan.updated_at,
+                at,
+            );
+        }
+        let to = at.min(loan.maturity).max(loan.updated_at);
+        let debt = Self::fixed_debt_at(env, loan.principal, loan.rate, loan.updated_at, to);
+        let penalty_rate = Self::term_penalty_rate(env, loan, penalty_base_rate);
+        Self::fixed_debt_at(env, debt, penalty_rate, to, at.max(to))
+    }
+
+    /// Overdue rate for `loan`: the variable rate (floored at the quoted rate) plus the spread.
+    fn term_penalty_rate(env: &Env, loan: &TermLoan, penalty_base_rate: u128) -> u128 {
+        let spread = Self::term_config(env).map_or(0u128, |config| config.penalty_spread);
+        penalty_base_rate
+            .max(loan.rate)
+            .saturating_add(spread)
+            .min(MAX_YEARLY_RATE_SCALED)
+    }
+
+    /// Rate the fixed pool carries `loan` at.
+    fn term_pool_rate(loan: &TermLoan) -> u128 {
+        if loan.updated_at >= loan.maturity {
+            loan.penalty_rate
+        } else {
+            loan.rate
+        }
+    }
+
+    /// Total and overdue term debt.
+    fn term_borrow_balance(env: &Env, user: &Address, live: bool) -> (u128, u128) {
+        let loans = Self::term_loans(env, user);
+        if loans.is_empty() {
+            return (0u128, 0u128);
+        }
+        let at = Self::borrow_valuation_time(env, live);
+        let penalty_base_rate = Self::term_penalty_base_rate(env);
+        let now = env.ledger().timestamp();
+        let mut total = 0u128;
+        let mut overdue = 0u128;
+        for loan in loans.iter() {
+            let debt = Self::term_loan_balance(env, &loan, at, penalty_base_rate);
+            total = total.saturating_add(debt);
+            if now >= loan.maturity {
+                overdue = overdue.saturating_add(debt);
+            }
+        }
+        (total, overdue)
+    }
+
+    fn open_term_loan(env: &Env, user: &Address, amount: u128, rate: u128, maturity: u64) -> u64 {
+        let mut loans = Self::term_loans(env, user);
+        if loans.len() >= MAX_TERM_LOANS_PER_USER {
+            panic!("too many term loans");
+        }
+        let id: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::NextTermLoanId)
+            .unwrap_or(0u64);
+        env.storage()
+            .persistent()
+            .set(&DataKey::NextTermLoanId, &(id + 1));
+        let loan = TermLoan {
+            id,
+            principal: amount,
+            rate,
+            maturity,
+            updated_at: Self::borrow_valuation_time(env, false),
+            penalty_rate: 0,
+        };
+        let mut pos = loans.len();
+        for i in 0..loans.len() {
+            if loans.get_unchecked(i).maturity > maturity {
+                pos = i;
+                break;
+            }
+        }
+        Self::add_fixed_debt(env, loan.principal, loan.rate, loan.updated_at);
+        loans.insert(pos, loan);
+        Self::write_term_loans(env, user, &loans);
+        id
+    }
+
+    /// Get the user's fixed-rate debt and its locked yearly rate (scaled 1e6).
+    pub fn get_fixed_borrow(env: Env, user: Address) -> (u128, u128) {
+        let _ = ensure_initialized(&env);
+        let rate = Self::fixed_borrow_snapshot(&env, &user).map_or(0u128, |snap| snap.rate);
+        (Self::fixed_borrow_balance(&env, &user, false), rate)
+    }
+
+    /// Fixed-rate and term share of `get_total_borrowed`: the sum of their
+    /// debts as of the last accrual.
+    pub fn get_total_fixed_borrowed(env: Env) -> u128 {
+        let _ = ensure_initialized(&env);
+        let at = Self::borrow_valuation_time(&env, false);
+        Self::fixed_pool_debt(&Self::fixed_debt_pool(&env), at)
+    }
+
+    /// Principal-weighted yearly rate (scaled 1e6) across fixed-rate and term borrows.
+    pub fn get_avg_fixed_rate(env: Env) -> u128 {
+        Self::fixed_pool_rate(&Self::fixed_debt_pool(&env))
+    }
+
     /// Get current borrow balance for a margin position namespace.
     pub fn get_margin_borrow_balance(env: Env, position_id: u64) -> u128 {
         let _ = ensure_initialized(&env);
@@ -2430,6 +5497,188 @@ impl ReceiptVault {
         }
     }
 
+    /// Replace `user`'s fixed-rate snapshot with `principal` at `rate` as of the
+    /// last accrual, moving the old and new positions through the fixed pool.
+    fn write_fixed_borrow_snapshot(env: &Env, user: &Address, principal: u128, rate: u128) {
+        let key = DataKey::FixedBorrowSnapshots(user.clone());
+        if let Some(old) = Self::fixed_borrow_snapshot(env, user) {
+            Self::remove_fixed_debt(env, old.principal, old.rate, old.updated_at);
+        }
+        if principal == 0 {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+        let snap = FixedBorrowSnapshot {
+            principal,
+            rate,
+            updated_at: Self::borrow_valuation_time(env, false),
+        };
+        Self::add_fixed_debt(env, snap.principal, snap.rate, snap.updated_at);
+        env.storage().persistent().set(&key, &snap);
+        bump_fixed_borrow_snapshot_ttl(env, user);
+    }
+
+    /// Add a fixed-rate position or term loan to the pool.
+    fn add_fixed_debt(env: &Env, principal: u128, rate: u128, updated_at: u64) {
+        let mut pool = Self::fixed_debt_pool(env);
+        let weight = principal.checked_mul(rate).expect("interest overflow");
+        pool.principal = pool.principal.saturating_add(principal);
+        pool.rate_weight = pool
+            .rate_weight
+            .checked_add(weight)
+            .expect("interest overflow");
+        pool.time_weight = weight
+            .checked_mul(updated_at as u128)
+            .and_then(|w| pool.time_weight.checked_add(w))
+            .expect("interest overflow");
+        env.storage()
+            .persistent()
+            .set(&DataKey::FixedDebtPool, &pool);
+    }
+
+    /// Take a position out of the pool with the same terms it was added with.
+    fn remove_fixed_debt(env: &Env, principal: u128, rate: u128, updated_at: u64) {
+        let mut pool = Self::fixed_debt_pool(env);
+        let weight = principal.saturating_mul(rate);
+        pool.principal = pool.principal.saturating_sub(principal);
+        pool.rate_weight = pool.rate_weight.saturating_sub(weight);
+        pool.time_weight = pool
+            .time_weight
+            .saturating_sub(weight.saturating_mul(updated_at as u128));
+        if pool.principal == 0 {
+            env.storage().persistent().remove(&DataKey::FixedDebtPool);
+            return;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::FixedDebtPool, &pool);
+    }
+
+    /// Apply `repay_amount` to `user`'s overdue term loans first, then variable,
+    /// fixed and open term debt. Returns the principal repaid and the debt left.
+    fn repay_user_debt(env: &Env, user: &Address, repay_amount: u128) -> (u128, u128) {
+        let (mut principal_repay, mut remaining) =
+            Self::repay_term_loans(env, user, repay_amount, true);
+
+        let variable_debt = Self::variable_borrow_balance_at(env, user, None);
+        let variable_repay = remaining.min(variable_debt);
+        principal_repay = principal_repay.saturating_add(Self::principal_component_of_repay(
+            env,
+            user,
+            variable_debt,
+            variable_repay,
+        ));
+        Self::write_borrow_snapshot(env, user.clone(), variable_debt - variable_repay);
+        remaining -= variable_repay;
+
+        let fixed_debt = Self::fixed_borrow_balance(env, user, false);
+        let fixed_repay = remaining.min(fixed_debt);
+        if fixed_repay > 0 {
+            let snapshot = Self::fixed_borrow_snapshot(env, user).expect("fixed borrow missing");
+            let accrued_interest = fixed_debt.saturating_sub(snapshot.principal);
+            principal_repay = principal_repay.saturating_add(
+                fixed_repay
+                    .saturating_sub(accrued_interest)
+                    .min(snapshot.principal),
+            );
+            Self::write_fixed_borrow_snapshot(env, user, fixed_debt - fixed_repay, snapshot.rate);
+            remaining -= fixed_repay;
+        }
+
+        let (term_principal, _) = Self::repay_term_loans(env, user, remaining, false);
+        principal_repay = principal_repay.saturating_add(term_principal);
+        (
+            principal_repay,
+            Self::user_borrow_balance_at(env, user, None),
+        )
+    }
+
+    /// Repay term loans in maturity order, only overdue ones when `overdue_only`.
+    /// Overdue loans are settled on the way: penalty interest the pool did not
+    /// accrue is booked and the loan is re-pegged to the current penalty rate.
+    /// Returns the principal repaid and the unused amount.
+    fn repay_term_loans(
+        env: &Env,
+        user: &Address,
+        amount: u128,
+        overdue_only: bool,
+    ) -> (u128, u128) {
+        let loans = Self::term_loans(env, user);
+        if loans.is_empty() {
+            return (0u128, amount);
+        }
+        let at = Self::borrow_valuation_time(env, false);
+        let penalty_base_rate = Self::term_penalty_base_rate(env);
+        let now = env.ledger().timestamp();
+        let mut remaining = amount;
+        let mut principal_repay = 0u128;
+        let mut kept = Vec::new(env);
+        let mut changed = false;
+        for loan in loans.iter() {
+            let debt = Self::term_loan_balance(env, &loan, at, penalty_base_rate);
+            let eligible = !overdue_only || now >= loan.maturity;
+            let pay = if eligible { remaining.min(debt) } else { 0u128 };
+            let settle = at >= loan.maturity && loan.updated_at < at;
+            if pay == 0 && !settle {
+                kept.push_back(loan);
+                continue;
+            }
+            changed = true;
+            let accrued_interest = debt.saturating_sub(loan.principal);
+            principal_repay = principal_repay
+                .saturating_add(pay.saturating_sub(accrued_interest).min(loan.principal));
+            remaining -= pay;
+            let pool_rate = Self::term_pool_rate(&loan);
+            Self::remove_fixed_debt(env, loan.principal, pool_rate, loan.updated_at);
+            // Past maturity the loan owes penalty interest the pool never accrued.
+            let pooled = Self::fixed_debt_at(env, loan.principal, pool_rate, loan.updated_at, at);
+            Self::book_penalty_interest(env, debt.saturating_sub(pooled));
+            if debt > pay {
+                let loan = TermLoan {
+                    principal: debt - pay,
+                    updated_at: at,
+                    penalty_rate: if at >= loan.maturity {
+                        Self::term_penalty_rate(env, &loan, penalty_base_rate)
+                    } else {
+                        loan.penalty_rate
+                    },
+                    ..loan
+                };
+                Self::add_fixed_debt(env, loan.principal, Self::term_pool_rate(&loan), at);
+                kept.push_back(loan);
+            }
+        }
+        if changed {
+            Self::write_term_loans(env, user, &kept);
+        }
+        (principal_repay, remaining)
+    }
+
+    /// Book term penalty interest accrued outside the fixed pool into `TotalBorrowed`,
+    /// splitting the reserve and admin shares as `update_interest` does.
+    fn book_penalty_interest(env: &Env, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+        let storage = env.storage().persistent();
+        let tb: u128 = storage
+            .get(&DataKey::TotalBorrowed)
+            .expect("total borrowed missing");
+        storage.set(&DataKey::TotalBorrowed, &tb.saturating_add(amount));
+        let rf: u128 = storage.get(&DataKey::ReserveFactorScaled).unwrap_or(0u128);
+        let af: u128 = storage.get(&DataKey::AdminFeeScaled).unwrap_or(0u128);
+        let reserves: u128 = storage.get(&DataKey::TotalReserves).unwrap_or(0u128);
+        let admin_fees: u128 = storage.get(&DataKey::TotalAdminFees).unwrap_or(0u128);
+        storage.set(
+            &DataKey::TotalReserves,
+            &reserves.saturating_add(amount.saturating_mul(rf) / SCALE_1E6),
+        );
+        storage.set(
+            &DataKey::TotalAdminFees,
+            &admin_fees.saturating_add(amount.saturating_mul(af) / SCALE_1E6),
+        );
+    }
+
     fn write_margin_borrow_snapshot(env: &Env, position_id: u64, principal: u128) {
         let current_index: u128 = env
             .storage()
@@ -2513,12 +5762,206 @@ impl ReceiptVault {
         if pbal == 0 {
             return 0u128;
         }
-        let rate = Self::get_exchange_rate(env.clone());
-        (pbal.saturating_mul(rate)) / SCALE_1E6
+        let rate = Self::get_exchange_rate_1e18(env.clone());
+        ptokens_to_underlying(&env, pbal, rate)
     }
 
     /// Borrow tokens against pToken collateral
     pub fn borrow(env: Env, user: Address, amount: u128) {
+        Self::borrow_internal(env, user.clone(), user, amount, BorrowMode::Variable);
+    }
+
+    /// Borrow against `user`'s collateral and send the funds to `receiver`.
+    pub fn borrow_to(env: Env, user: Address, receiver: Address, amount: u128) {
+        Self::borrow_internal(env, user, receiver, amount, BorrowMode::Variable);
+    }
+
+    /// Borrow at a rate locked now from the variable rate plus the configured
+    /// premium. Panics when the locked rate would exceed `max_rate_scaled`.
+    pub fn borrow_fixed(env: Env, user: Address, amount: u128, max_rate_scaled: u128) {
+        Self::borrow_internal(
+            env,
+            user.clone(),
+            user,
+            amount,
+            BorrowMode::Fixed {
+                max_rate: max_rate_scaled,
+            },
+        );
+    }
+
+    /// Borrow until `maturity` at a rate quoted from the term curve. Returns the
+    /// loan id. Overdue loans accrue the penalty rate and can be liquidated
+    /// regardless of account health.
+    pub fn borrow_term(
+        env: Env,
+        user: Address,
+        amount: u128,
+        maturity: u64,
+        max_rate_scaled: u128,
+    ) -> u64 {
+        Self::borrow_internal(
+            env,
+            user.clone(),
+            user,
+            amount,
+            BorrowMode::Term {
+                maturity,
+                max_rate: max_rate_scaled,
+            },
+        )
+        .expect("term loan not opened")
+    }
+
+    /// Yearly rate (scaled 1e6) a term borrow of `amount` maturing at `maturity` would lock now.
+    pub fn get_term_quote(env: Env, amount: u128, maturity: u64) -> u128 {
+        let token_address = ensure_initialized(&env);
+        Self::term_rate_quote(&env, &token_address, amount, maturity)
+    }
+
+    /// Variable rate after borrowing `amount`, plus the curve premium for the term.
+    fn term_rate_quote(env: &Env, token_address: &Address, amount: u128, maturity: u64) -> u128 {
+        let config = Self::term_config(env);
+        let Some(config) = config.filter(|config| config.enabled) else {
+            panic!("term borrowing disabled");
+        };
+        let now = env.ledger().timestamp();
+        if maturity <= now {
+            panic!("invalid maturity");
+        }
+        let term = maturity - now;
+        let first = config.curve.get_unchecked(0);
+        let last = config.curve.get_unchecked(config.curve.len() - 1);
+        if term > last.term_secs {
+            panic!("term too long");
+        }
+        let mut premium = first.premium;
+        for i in 1..config.curve.len() {
+            let lo = config.curve.get_unchecked(i - 1);
+            let hi = config.curve.get_unchecked(i);
+            if term <= lo.term_secs {
+                break;
+            }
+            if term <= hi.term_secs {
+                let span = (hi.term_secs - lo.term_secs) as u128;
+                let into = (term - lo.term_secs) as u128;
+                premium = if hi.premium >= lo.premium {
+                    lo.premium + (hi.premium - lo.premium) * into / span
+                } else {
+                    lo.premium - (lo.premium - hi.premium) * into / span
+                };
+                break;
+            }
+        }
+        let (accrual, _, _) = Self::preview_state(env);
+        let pooled_reserves = accrual
+            .total_reserves
+            .saturating_add(accrual.total_admin_fees);
+        let cash =
+            Self::model_cash(env, token_address, accrual.total_borrowed).saturating_sub(amount);
+        let borrows = accrual.total_borrowed.saturating_add(amount);
+        Self::borrow_rate_for(env, cash, borrows, pooled_reserves, false)
+            .saturating_add(premium)
+            .min(MAX_YEARLY_RATE_SCALED)
+    }
+
+    /// Open term loans for `user`, earliest maturity first, with debt accrued
+    /// to the last interest update.
+    pub fn get_term_loans(env: Env, user: Address) -> Vec<TermLoanInfo> {
+        let _ = ensure_initialized(&env);
+        let at = Self::borrow_valuation_time(&env, false);
+        let penalty_rate = Self::term_penalty_base_rate(&env);
+        let now = env.ledger().timestamp();
+        let mut out = Vec::new(&env);
+        for loan in Self::term_loans(&env, &user).iter() {
+            out.push_back(TermLoanInfo {
+                id: loan.id,
+                debt: Self::term_loan_balance(&env, &loan, at, penalty_rate),
+                rate: loan.rate,
+                maturity: loan.maturity,
+                overdue: now >= loan.maturity,
+            });
+        }
+        out
+    }
+
+    /// Debt on `user`'s term loans past maturity. The peridottroller lets
+    /// liquidators repay this without an account shortfall.
+    pub fn get_overdue_term_debt(env: Env, user: Address) -> u128 {
+        let _ = ensure_initialized(&env);
+        Self::term_borrow_balance(&env, &user, false).1
+    }
+
+    /// Book penalty interest on `user`'s overdue term loans into the pooled totals
+    /// and re-peg them to the current penalty rate. Permissionless.
+    pub fn settle_term_loans(env: Env, user: Address) {
+        let _ = ensure_initialized(&env);
+        Self::ensure_not_in_flash_loan(&env);
+        Self::update_interest(env.clone());
+        Self::repay_term_loans(&env, &user, 0, true);
+    }
+
+    /// Fixed yearly rate (scaled 1e6) a fixed-rate borrow of `amount` would lock now.
+    pub fn get_fixed_rate_quote(env: Env, amount: u128) -> u128 {
+        let token_address = ensure_initialized(&env);
+        Self::fixed_rate_quote(&env, &token_address, amount)
+    }
+
+    /// Variable rate after borrowing `amount`, plus the premium.
+    fn fixed_rate_quote(env: &Env, token_address: &Address, amount: u128) -> u128 {
+        let config = Self::fixed_rate_config(env);
+        if !config.as_ref().is_some_and(|config| config.enabled) {
+            panic!("fixed rate disabled");
+        }
+        let premium = config.map_or(0u128, |config| config.premium);
+        let (accrual, _, _) = Self::preview_state(env);
+        let pooled_reserves = accrual
+            .total_reserves
+            .saturating_add(accrual.total_admin_fees);
+        let cash =
+            Self::model_cash(env, token_address, accrual.total_borrowed).saturating_sub(amount);
+        let borrows = accrual.total_borrowed.saturating_add(amount);
+        Self::borrow_rate_for(env, cash, borrows, pooled_reserves, false)
+            .saturating_add(premium)
+            .min(MAX_YEARLY_RATE_SCALED)
+    }
+
+    /// Re-lock `user`'s fixed rate at the current variable rate plus premium once
+    /// the variable rate exceeds the locked rate by more than the rebalance spread.
+    /// Permissionless.
+    pub fn rebalance_fixed_rate(env: Env, user: Address) {
+        let _ = ensure_initialized(&env);
+        Self::ensure_not_in_flash_loan(&env);
+        Self::update_interest(env.clone());
+        let config = Self::fixed_rate_config(&env).expect("fixed rate disabled");
+        let snapshot = Self::fixed_borrow_snapshot(&env, &user).expect("no fixed borrow");
+        let debt = Self::fixed_borrow_balance(&env, &user, false);
+        if debt == 0 {
+            panic!("no fixed borrow");
+        }
+        let (_, variable_rate, _) = Self::current_rates(&env);
+        if variable_rate <= snapshot.rate.saturating_add(config.rebalance_spread) {
+            panic!("rebalance not allowed");
+        }
+        let new_rate = variable_rate
+            .saturating_add(config.premium)
+            .min(MAX_YEARLY_RATE_SCALED);
+        Self::write_fixed_borrow_snapshot(&env, &user, debt, new_rate);
+        FixedRateLocked {
+            borrower: user,
+            rate: new_rate,
+            fixed_borrows: debt,
+        }
+        .publish(&env);
+    }
+
+    fn borrow_internal(
+        env: Env,
+        user: Address,
+        receiver: Address,
+        amount: u128,
+        mode: BorrowMode,
+    ) -> Option<u64> {
         let token_address = ensure_initialized(&env);
         Self::ensure_not_in_flash_loan(&env);
         Self::ensure_user_borrow_flag(&env, &user);
@@ -2532,6 +5975,10 @@ impl ReceiptVault {
             panic!("rates not configured");
         }
         ensure_user_auth(&env, &user);
+        Self::require_access(&env, &user, false);
+        if amount < Self::dust_limits(&env).min_borrow {
+            panic!("borrow below minimum");
+        }
         let mut user_ptokens_before: u128 = 0;
         let mut user_borrow_before: u128 = 0;
         let mut exchange_rate: u128 = 0;
@@ -2548,7 +5995,7 @@ impl ReceiptVault {
                 .expect("total borrowed missing");
             user_ptokens_before = ptoken_balance(&env, &user);
             user_borrow_before = Self::get_user_borrow_balance(env.clone(), user.clone());
-            exchange_rate = Self::get_exchange_rate(env.clone());
+            exchange_rate = Self::get_exchange_rate_1e18(env.clone());
             let hint = ControllerAccrualHint {
                 total_ptokens: Some(total_ptokens_before),
                 total_borrowed: Some(total_borrowed_before),
@@ -2650,9 +6097,58 @@ impl ReceiptVault {
         }
 
         // Update totals and user snapshot
-        let new_principal =
-            Self::get_user_borrow_balance(env.clone(), user.clone()).saturating_add(amount);
-        Self::write_borrow_snapshot(&env, user.clone(), new_principal);
+        let variable_debt = Self::variable_borrow_balance_at(&env, &user, None);
+        let mut term_loan_id = None;
+        match mode {
+            BorrowMode::Variable => {
+                Self::write_borrow_snapshot(
+                    &env,
+                    user.clone(),
+                    variable_debt.saturating_add(amount),
+                );
+            }
+            BorrowMode::Fixed { max_rate } => {
+                let quote = Self::fixed_rate_quote(&env, &token_address, amount);
+                if quote > max_rate {
+                    panic!("fixed rate above max");
+                }
+                // Blend any existing fixed debt into one debt-weighted locked rate.
+                let fixed_debt = Self::fixed_borrow_balance(&env, &user, false);
+                let old_rate =
+                    Self::fixed_borrow_snapshot(&env, &user).map_or(0u128, |snap| snap.rate);
+                let new_fixed_debt = fixed_debt.saturating_add(amount);
+                let new_rate = fixed_debt
+                    .saturating_mul(old_rate)
+                    .saturating_add(amount.saturating_mul(quote))
+                    / new_fixed_debt;
+                Self::write_borrow_snapshot(&env, user.clone(), variable_debt);
+                Self::write_fixed_borrow_snapshot(&env, &user, new_fixed_debt, new_rate);
+                FixedRateLocked {
+                    borrower: user.clone(),
+                    rate: new_rate,
+                    fixed_borrows: new_fixed_debt,
+                }
+                .publish(&env);
+            }
+            BorrowMode::Term { maturity, max_rate } => {
+                let quote = Self::term_rate_quote(&env, &token_address, amount, maturity);
+                if quote > max_rate {
+                    panic!("term rate above max");
+                }
+                Self::write_borrow_snapshot(&env, user.clone(), variable_debt);
+                let loan_id = Self::open_term_loan(&env, &user, amount, quote, maturity);
+                TermLoanOpened {
+                    borrower: user.clone(),
+                    loan_id,
+                    amount,
+                    rate: quote,
+                    maturity,
+                }
+                .publish(&env);
+                term_loan_id = Some(loan_id);
+            }
+        }
+        let account_borrows = Self::user_borrow_balance_at(&env, &user, None);
 
         if bcap > 0 {
             let total_principal_before: u128 = env
@@ -2681,22 +6177,18 @@ impl ReceiptVault {
             .persistent()
             .set(&DataKey::TotalBorrowed, &total_borrows);
 
-        // Transfer tokens to user
-        let token_client = token::Client::new(&env, &token_address);
-        let amount_i128 = to_i128(amount);
-        let cash_before = Self::current_live_cash(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &user, &amount_i128);
-        let cash_after = Self::current_live_cash(&env, &token_address);
-        Self::sub_managed_cash(&env, cash_before.saturating_sub(cash_after));
+        // Transfer tokens to receiver; any excess over the outflow limit is delayed.
+        Self::pay_out(&env, &token_address, &receiver, amount);
 
         // Emit event
         BorrowEvent {
             borrower: user.clone(),
             borrow_amount: amount,
-            account_borrows: new_principal,
+            account_borrows,
             total_borrows,
         }
         .publish(&env);
+        term_loan_id
     }
 
     /// Borrow into a margin position namespace.
@@ -2749,6 +6241,7 @@ impl ReceiptVault {
         if receiver != owner {
             panic!("receiver must be position owner");
         }
+        Self::require_access(&env, &receiver, false);
         Self::ensure_margin_position_borrow_flag(&env, position_id);
         if amount == 0 {
             panic!("bad amount");
@@ -2820,30 +6313,35 @@ impl ReceiptVault {
             .persistent()
             .set(&DataKey::TotalBorrowed, &total_borrows);
 
-        let token_client = token::Client::new(&env, &token_address);
-        let amount_i128 = to_i128(amount);
-        let cash_before = Self::current_live_cash(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &receiver, &amount_i128);
-        let cash_after = Self::current_live_cash(&env, &token_address);
-        Self::sub_managed_cash(&env, cash_before.saturating_sub(cash_after));
+        Self::pay_out(&env, &token_address, &receiver, amount);
     }
 
     /// Repay borrowed tokens
     pub fn repay(env: Env, user: Address, amount: u128) {
+        Self::repay_internal(env, user.clone(), user, amount);
+    }
+
+    /// Repay `borrower`'s debt from `payer`. `u128::MAX` repays the full
+    /// balance including interest accrued in this call.
+    pub fn repay_for(env: Env, payer: Address, borrower: Address, amount: u128) {
+        Self::repay_internal(env, payer, borrower, amount);
+    }
+
+    fn repay_internal(env: Env, payer: Address, borrower: Address, amount: u128) {
         let token_address = ensure_initialized(&env);
         Self::ensure_not_in_flash_loan(&env);
-        Self::ensure_user_borrow_flag(&env, &user);
+        Self::ensure_user_borrow_flag(&env, &borrower);
         // Compute a deterministic repay cap from pre-accrual state so auth entries
         // do not depend on time-elapsed interest updates between simulation and execution.
-        let debt_before_accrual = Self::get_user_borrow_balance(env.clone(), user.clone());
+        let debt_before_accrual = Self::get_user_borrow_balance(env.clone(), borrower.clone());
         let planned_repay = if amount > debt_before_accrual {
             debt_before_accrual
         } else {
             amount
         };
         Self::update_interest(env.clone());
-        ensure_user_auth(&env, &user);
-        let current_debt = Self::get_user_borrow_balance(env.clone(), user.clone());
+        ensure_user_auth(&env, &payer);
+        let current_debt = Self::get_user_borrow_balance(env.clone(), borrower.clone());
         if let Some(_comp_addr) = env
             .storage()
             .persistent()
@@ -2855,38 +6353,41 @@ impl ReceiptVault {
                 .persistent()
                 .get(&DataKey::TotalBorrowed)
                 .expect("total borrowed missing");
-            let user_ptokens_before = ptoken_balance(&env, &user);
+            let user_ptokens_before = ptoken_balance(&env, &borrower);
             let hint = ControllerAccrualHint {
                 total_ptokens: Some(total_ptokens_before),
                 total_borrowed: Some(total_borrowed_before),
                 user_ptokens: Some(user_ptokens_before),
                 user_borrowed: Some(current_debt),
             };
-            Self::accrue_user_rewards(&env, &user, hint, "repay");
+            Self::accrue_user_rewards(&env, &borrower, hint, "repay");
         }
 
         if current_debt == 0 {
             return;
         }
-        let repay_amount = if planned_repay > current_debt {
+        let repay_amount = if amount == u128::MAX || planned_repay > current_debt {
             current_debt
         } else {
             planned_repay
         };
-        let principal_repay_user =
-            Self::principal_component_of_repay(&env, &user, current_debt, repay_amount);
-
-        // Transfer tokens from user
+        let remaining_debt = current_debt - repay_amount;
+        if remaining_debt > 0 && remaining_debt < Self::dust_limits(&env).min_debt {
+            panic!("remaining debt below minimum");
+        }
+        // Transfer tokens from payer
         let token_client = token::Client::new(&env, &token_address);
         let repay_i128 = to_i128(repay_amount);
         let cash_before = Self::current_live_cash(&env, &token_address);
-        token_client.transfer(&user, &env.current_contract_address(), &repay_i128);
+        token_client.transfer(&payer, &env.current_contract_address(), &repay_i128);
         let cash_after = Self::current_live_cash(&env, &token_address);
-        Self::add_managed_cash(&env, cash_after.saturating_sub(cash_before));
+        let received_cash = cash_after.saturating_sub(cash_before);
+        Self::add_managed_cash(&env, received_cash);
+        Self::record_inflow(&env, received_cash);
 
         // Update snapshot and totals
-        let new_principal = current_debt - repay_amount;
-        Self::write_borrow_snapshot(&env, user.clone(), new_principal);
+        let (principal_repay_user, new_principal) =
+            Self::repay_user_debt(&env, &borrower, repay_amount);
 
         let bcap: u128 = env
             .storage()
@@ -2924,13 +6425,14 @@ impl ReceiptVault {
             .set(&DataKey::TotalBorrowed, &tb_after);
 
         RepayBorrow {
-            payer: user.clone(),
-            borrower: user.clone(),
+            payer: payer.clone(),
+            borrower: borrower.clone(),
             repay_amount,
             account_borrows: new_principal,
             total_borrows: tb_after,
         }
         .publish(&env);
+        Self::fill_withdraw_queue(&env, &token_address);
     }
 
     /// Repay debt tracked in a margin position namespace.
@@ -2970,7 +6472,9 @@ impl ReceiptVault {
         let cash_before = Self::current_live_cash(&env, &token_address);
         token_client.transfer(&payer, &env.current_contract_address(), &repay_i128);
         let cash_after = Self::current_live_cash(&env, &token_address);
-        Self::add_managed_cash(&env, cash_after.saturating_sub(cash_before));
+        let received_cash = cash_after.saturating_sub(cash_before);
+        Self::add_managed_cash(&env, received_cash);
+        Self::record_inflow(&env, received_cash);
 
         let new_principal = current_debt - repay_amount;
         Self::write_margin_borrow_snapshot(&env, position_id, new_principal);
@@ -3009,16 +6513,20 @@ impl ReceiptVault {
         env.storage()
             .persistent()
             .set(&DataKey::TotalBorrowed, &tb_after);
+        Self::fill_withdraw_queue(&env, &token_address);
     }
 
-    /// Execute a flash loan to `receiver`. Receiver must return `amount + fee` within the callback.
-    pub fn flash_loan(env: Env, receiver: Address, amount: u128, data: Bytes) {
+    /// Execute a flash loan to `receiver` on behalf of `initiator`. Receiver must return
+    /// `amount + fee` within the callback and answer with the ERC-3156 success value.
+    pub fn flash_loan(env: Env, initiator: Address, receiver: Address, amount: u128, data: Bytes) {
         if amount == 0 {
             panic!("invalid flash amount");
         }
         let token_address = ensure_initialized(&env);
         Self::ensure_not_in_flash_loan(&env);
-        Self::update_interest(env.clone());
+        // The initiator is forwarded to the receiver and selects any fee discount,
+        // so it must be authenticated.
+        initiator.require_auth();
 
         if let Some(comp_addr) = env
             .storage()
@@ -3036,6 +6544,89 @@ impl ReceiptVault {
             }
         }
 
+        let (fee, balance_before) =
+            Self::flash_loan_start(&env, &token_address, &receiver, amount, Some(&initiator));
+
+        // Intentionally no `receiver.require_auth()`: contract receivers cannot satisfy
+        // account-style auth here, and self-initiated callbacks hit Soroban's re-entry guard.
+        // Consent is by returning the success value; repayment is enforced by the balance check.
+        let accepted: BytesN<32> = call_contract_or_panic(
+            &env,
+            &receiver,
+            "on_flash_loan",
+            (
+                env.current_contract_address(),
+                initiator,
+                token_address.clone(),
+                amount,
+                fee,
+                data.clone(),
+            ),
+        );
+        if accepted != flash_callback_success(&env) {
+            panic!("invalid flash callback");
+        }
+
+        Self::flash_loan_finish(&env, &token_address, receiver, amount, fee, balance_before);
+    }
+
+    /// Peridottroller: open one leg of a multi-market flash loan, sending `amount`
+    /// to `receiver`. The market stays locked until `flash_loan_close`. Returns the
+    /// fee, discounted for `initiator` (authenticated by the peridottroller).
+    pub fn flash_loan_open(env: Env, initiator: Address, receiver: Address, amount: u128) -> u128 {
+        if amount == 0 {
+            panic!("invalid flash amount");
+        }
+        let token_address = ensure_initialized(&env);
+        Self::ensure_not_in_flash_loan(&env);
+        let comp_addr: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Peridottroller)
+            .expect("no peridottroller");
+        comp_addr.require_auth();
+
+        let (fee, balance_before) =
+            Self::flash_loan_start(&env, &token_address, &receiver, amount, Some(&initiator));
+        env.storage().persistent().set(
+            &DataKey::FlashLoanPending,
+            &(receiver, amount, fee, balance_before),
+        );
+        fee
+    }
+
+    /// Peridottroller: settle the leg opened by `flash_loan_open`, requiring
+    /// `amount + fee` to be back in the market.
+    pub fn flash_loan_close(env: Env) {
+        let token_address = ensure_initialized(&env);
+        let comp_addr: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Peridottroller)
+            .expect("no peridottroller");
+        comp_addr.require_auth();
+        let (receiver, amount, fee, balance_before): (Address, u128, u128, u128) = env
+            .storage()
+            .persistent()
+            .get(&DataKey::FlashLoanPending)
+            .expect("no flash loan");
+        env.storage()
+            .persistent()
+            .remove(&DataKey::FlashLoanPending);
+        Self::flash_loan_finish(&env, &token_address, receiver, amount, fee, balance_before);
+    }
+
+    /// Lock the market and send `amount` to `receiver`. Returns the fee owed and
+    /// the pre-loan balance that repayment is checked against.
+    fn flash_loan_start(
+        env: &Env,
+        token_address: &Address,
+        receiver: &Address,
+        amount: u128,
+        initiator: Option<&Address>,
+    ) -> (u128, u128) {
+        Self::update_interest(env.clone());
+
         let available = Self::get_available_liquidity(env.clone());
         if available < amount {
             panic!("insufficient liquidity");
@@ -3043,20 +6634,15 @@ impl ReceiptVault {
 
         // Pull from boosted vault on demand so flash loans are backed by live cash.
         // Do this before taking the pre-loan balance snapshot used for repayment checks.
-        Self::ensure_liquid_cash(&env, &token_address, amount);
-        let cash_for_flash = Self::current_live_cash(&env, &token_address);
+        Self::ensure_liquid_cash(env, token_address, amount);
+        let cash_for_flash = Self::current_live_cash(env, token_address);
         if cash_for_flash < amount {
             panic!("flash loan liquidity shortfall");
         }
 
-        let fee_scaled: u128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::FlashLoanFeeScaled)
-            .unwrap_or(0u128);
-        let fee = (amount.saturating_mul(fee_scaled)) / SCALE_1E6;
+        let fee = Self::flash_fee_for(env, amount, initiator);
 
-        let token_client = token::Client::new(&env, &token_address);
+        let token_client = token::Client::new(env, token_address);
 
         let balance_before_i: i128 = token_client.balance(&env.current_contract_address());
         if balance_before_i < 0 {
@@ -3067,19 +6653,90 @@ impl ReceiptVault {
         env.storage()
             .persistent()
             .set(&DataKey::FlashLoanActive, &true);
-        Self::sub_managed_cash(&env, amount);
-        token_client.transfer(&env.current_contract_address(), &receiver, &to_i128(amount));
+        Self::sub_managed_cash(env, amount);
+        token_client.transfer(&env.current_contract_address(), receiver, &to_i128(amount));
+        (fee, balance_before)
+    }
 
-        // Intentionally no `receiver.require_auth()`: contract receivers cannot satisfy
-        // account-style auth here, and self-initiated callbacks hit Soroban's re-entry guard.
-        // Consent is by implementing this callback; repayment is enforced by the balance check.
-        call_contract_or_panic::<(), _>(
-            &env,
-            &receiver,
-            "on_flash_loan",
-            (env.current_contract_address(), amount, fee, data.clone()),
-        );
+    /// Flash fee on `amount`, less any discount granted to `initiator`.
+    fn flash_fee_for(env: &Env, amount: u128, initiator: Option<&Address>) -> u128 {
+        let fee_scaled: u128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::FlashLoanFeeScaled)
+            .unwrap_or(0u128);
+        let fee = (amount.saturating_mul(fee_scaled)) / SCALE_1E6;
+        let discount_scaled: u128 = initiator
+            .and_then(|account| {
+                env.storage()
+                    .persistent()
+                    .get(&DataKey::FlashLoanFeeDiscount(account.clone()))
+            })
+            .unwrap_or(0u128);
+        fee - (fee.saturating_mul(discount_scaled)) / SCALE_1E6
+    }
+
+    /// View (ERC-3156): largest amount `flash_loan` can lend right now, i.e. live cash
+    /// net of delayed claims plus what strategies can return, capped by available
+    /// liquidity; 0 for other tokens.
+    pub fn max_flash_loan(env: Env, token: Address) -> u128 {
+        let token_address = ensure_initialized(&env);
+        if token != token_address
+            || Self::is_shutdown(env.clone())
+            || env
+                .storage()
+                .persistent()
+                .get::<_, bool>(&DataKey::FlashLoanActive)
+                .unwrap_or(false)
+        {
+            return 0u128;
+        }
+        if let Some(comp_addr) = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::Peridottroller)
+        {
+            let paused: bool = call_contract_or_panic(
+                &env,
+                &comp_addr,
+                "is_borrow_paused",
+                (env.current_contract_address(),),
+            );
+            if paused {
+                return 0u128;
+            }
+        }
+        let (accrual, total_underlying, _) = Self::preview_state(&env);
+        let mut lendable = Self::current_live_cash(&env, &token_address);
+        for strategy in Self::strategies(&env).iter() {
+            // Strategy withdrawals may come back one unit short of the quote.
+            let redeemable = Self::quote_strategy(&env, &strategy.vault)
+                .unwrap_or(0u128)
+                .saturating_sub(1);
+            lendable = lendable.saturating_add(redeemable);
+        }
+        lendable.min(total_underlying.saturating_sub(accrual.total_borrowed))
+    }
+
+    /// View (ERC-3156): undiscounted fee charged for flash-borrowing `amount` of `token`.
+    pub fn flash_fee(env: Env, token: Address, amount: u128) -> u128 {
+        let token_address = ensure_initialized(&env);
+        if token != token_address {
+            panic!("unsupported token");
+        }
+        Self::flash_fee_for(&env, amount, None)
+    }
 
+    /// Check repayment, book the fee to reserves and unlock the market.
+    fn flash_loan_finish(
+        env: &Env,
+        token_address: &Address,
+        receiver: Address,
+        amount: u128,
+        fee: u128,
+        balance_before: u128,
+    ) {
+        let token_client = token::Client::new(env, token_address);
         let balance_after_i: i128 = token_client.balance(&env.current_contract_address());
         if balance_after_i < 0 {
             panic!("invalid repayment state");
@@ -3093,7 +6750,7 @@ impl ReceiptVault {
         let fee_paid = balance_after.saturating_sub(balance_before);
         let returned = balance_after.saturating_sub(balance_before.saturating_sub(amount));
         if returned > 0 {
-            Self::add_managed_cash(&env, returned);
+            Self::add_managed_cash(env, returned);
         }
         if fee_paid > 0 {
             let reserves: u128 = env
@@ -3108,11 +6765,11 @@ impl ReceiptVault {
         env.storage().persistent().remove(&DataKey::FlashLoanActive);
 
         FlashLoan {
-            receiver: receiver.clone(),
+            receiver,
             amount,
             fee_paid,
         }
-        .publish(&env);
+        .publish(env);
     }
 
     /// Repay on behalf during liquidation; only callable by peridottroller/peridottroller
@@ -3145,9 +6802,6 @@ impl ReceiptVault {
         } else {
             planned_repay
         };
-        let principal_repay_user =
-            Self::principal_component_of_repay(&env, &borrower, current_debt, repay_amount);
-
         // Pull repayment from liquidator allowance. This avoids requiring liquidator
         // sub-invocation auth entries that depend on dynamic repay amounts.
         let token_client = token::Client::new(&env, &token_address);
@@ -3160,11 +6814,13 @@ impl ReceiptVault {
             &repay_i128,
         );
         let cash_after = Self::current_live_cash(&env, &token_address);
-        Self::add_managed_cash(&env, cash_after.saturating_sub(cash_before));
+        let received_cash = cash_after.saturating_sub(cash_before);
+        Self::add_managed_cash(&env, received_cash);
+        Self::record_inflow(&env, received_cash);
 
         // Update borrower snapshot and totals
-        let new_principal = current_debt - repay_amount;
-        Self::write_borrow_snapshot(&env, borrower.clone(), new_principal);
+        let (principal_repay_user, new_principal) =
+            Self::repay_user_debt(&env, &borrower, repay_amount);
 
         let bcap: u128 = env
             .storage()
@@ -3245,7 +6901,7 @@ impl ReceiptVault {
                 "fee_missing_recipient",
             );
         }
-        if seize_ctx.shortfall == 0 {
+        if seize_ctx.shortfall == 0 && !seize_ctx.overdue {
             abort_seize(&env, &borrower, &liquidator, ptoken_amount, "solvent");
         }
         // Do not block liquidations based on redeem previews. A precomputed
@@ -3257,6 +6913,19 @@ impl ReceiptVault {
         if borrower_bal < ptoken_amount {
             abort_seize(&env, &borrower, &liquidator, ptoken_amount, "insufficient");
         }
+        let supply_restricted = matches!(
+            Self::get_access_mode(env.clone()),
+            AccessMode::SupplyAllowlist | AccessMode::FullAllowlist
+        );
+        if supply_restricted && !Self::is_allowlisted(env.clone(), liquidator.clone()) {
+            abort_seize(
+                &env,
+                &borrower,
+                &liquidator,
+                ptoken_amount,
+                "not_allowlisted",
+            );
+        }
         let mut remaining = ptoken_amount;
         if seize_ctx.fee_ptokens > 0 {
             if let Some(recipient) = seize_ctx.fee_recipient {
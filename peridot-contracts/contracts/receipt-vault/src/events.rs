@@ -1,3 +1,4 @@
+use crate::storage::{AccessMode, OutflowLimit, VaultParam};
 use soroban_sdk::{contractevent, Address, Symbol};
 
 /// Mirrors Compound's Mint event: emitted on deposit when pTokens are minted.
@@ -97,6 +98,15 @@ pub struct NewFlashLoanFee {
     pub fee_mantissa: u128,
 }
 
+/// Per-initiator flash fee discount update (1e6 = fully exempt).
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FlashFeeDiscountUpdated {
+    #[topic]
+    pub account: Address,
+    pub discount_mantissa: u128,
+}
+
 /// Mirrors Compound's NewCollateralFactor event.
 #[contractevent]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -155,6 +165,171 @@ pub struct AdminFeesReduced {
     pub total_admin_fees: u128,
 }
 
+/// Emits when the treasury allowed to sweep reserves and fees changes.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreasuryUpdated {
+    pub treasury: Option<Address>,
+}
+
+/// Emits when a supplier escrows pTokens in the withdraw queue.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawQueued {
+    #[topic]
+    pub owner: Address,
+    pub id: u64,
+    pub ptokens: u128,
+}
+
+/// Emits when incoming cash pays (part of) a queued withdraw.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawRequestFilled {
+    #[topic]
+    pub owner: Address,
+    pub id: u64,
+    pub underlying: u128,
+    pub ptokens: u128,
+    pub remaining_ptokens: u128,
+}
+
+/// Emits when a supplier takes back the unfilled part of a queued withdraw.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawRequestCancelled {
+    #[topic]
+    pub owner: Address,
+    pub id: u64,
+    pub ptokens: u128,
+}
+
+/// Emits when the admin sets or clears the market's outflow limit.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutflowLimitUpdated {
+    pub limit: Option<OutflowLimit>,
+}
+
+/// Emits when a payout beyond the outflow limit is held for a delayed claim.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutflowDelayed {
+    #[topic]
+    pub recipient: Address,
+    pub amount: u128,
+    pub total_delayed: u128,
+    pub claimable_at: u64,
+}
+
+/// Emits when the guardian releases or extends a delayed claim.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DelayedOutflowUpdated {
+    #[topic]
+    pub recipient: Address,
+    pub claimable_at: u64,
+}
+
+/// Emits when a delayed claim is paid out.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DelayedOutflowClaimed {
+    #[topic]
+    pub recipient: Address,
+    pub amount: u128,
+}
+
+/// Emits when the admin changes the market's minimum borrow and debt.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DustLimitsUpdated {
+    pub min_borrow: u128,
+    pub min_debt: u128,
+}
+
+/// Emits when an admin parameter change is staged behind the delay.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingParamChangeUpdated {
+    #[topic]
+    pub param: VaultParam,
+    pub value: u128,
+    pub execute_after: u64,
+}
+
+/// Emits when a staged admin parameter change is dropped.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingParamChangeCancelled {
+    #[topic]
+    pub param: VaultParam,
+}
+
+/// Emits when a rate model swap is staged behind the delay.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingInterestModelUpdated {
+    #[topic]
+    pub model: Address,
+    pub keyed: bool,
+    pub execute_after: u64,
+}
+
+/// Emits when a staged rate model swap is dropped.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingInterestModelCancelled {
+    #[topic]
+    pub model: Address,
+}
+
+/// Emits when fixed-rate borrowing is configured.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedRateConfigUpdated {
+    pub enabled: bool,
+    pub premium: u128,
+    pub rebalance_spread: u128,
+}
+
+/// Emits when a borrower's fixed rate is locked by a borrow or rebalance.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedRateLocked {
+    #[topic]
+    pub borrower: Address,
+    pub rate: u128,
+    pub fixed_borrows: u128,
+}
+
+/// Emits when term borrowing is configured.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TermConfigUpdated {
+    pub enabled: bool,
+    pub penalty_spread: u128,
+}
+
+/// Emits when a term loan is opened.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TermLoanOpened {
+    #[topic]
+    pub borrower: Address,
+    pub loan_id: u64,
+    pub amount: u128,
+    pub rate: u128,
+    pub maturity: u64,
+}
+
+/// Emits when the minimum spacing between rate checkpoints changes.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CheckpointIntervalUpdated {
+    pub interval_secs: u64,
+}
+
 /// Mirrors Compound's NewPeridottroller (custom) event.
 #[contractevent]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -163,6 +338,29 @@ pub struct NewPeridottroller {
     pub peridottroller: Address,
 }
 
+/// Emits when the peridottroller moves the market in or out of shutdown settlement.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShutdownUpdated {
+    pub active: bool,
+}
+
+/// Emits when the market manager changes a permissioned market's access mode.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccessModeUpdated {
+    pub mode: AccessMode,
+}
+
+/// Emits when an account is added to or removed from the market allowlist.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowlistUpdated {
+    #[topic]
+    pub account: Address,
+    pub allowed: bool,
+}
+
 /// Emits when the boosted vault address is updated.
 #[contractevent]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -171,6 +369,43 @@ pub struct BoostedVaultSet {
     pub new_vault: Option<Address>,
 }
 
+/// Emits when a boosted strategy is registered or its weight/cap changes.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrategySet {
+    #[topic]
+    pub vault: Address,
+    pub target_bps: u32,
+    pub cap: u128,
+}
+
+/// Emits when a boosted strategy is drained and unregistered.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrategyRemoved {
+    #[topic]
+    pub vault: Address,
+    pub recovered: u128,
+}
+
+/// Emits when a strategy reports less than its recognized underlying.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrategyImpaired {
+    #[topic]
+    pub vault: Address,
+    pub loss: u128,
+    pub absorbed_by_reserves: u128,
+}
+
+/// Emits when the admin acknowledges a boosted loss and resumes deposits.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BoostedDepositsResumed {
+    #[topic]
+    pub admin: Address,
+}
+
 /// Flash loan execution log.
 #[contractevent]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -225,3 +460,10 @@ pub struct InvalidSeizeAttempt {
     pub requested: u128,
     pub reason: Symbol,
 }
+
+/// Emits when pToken decimals are re-synced to the underlying token.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PTokenDecimalsUpdated {
+    pub decimals: u32,
+}
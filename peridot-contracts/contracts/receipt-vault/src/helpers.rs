@@ -1,6 +1,6 @@
-use soroban_sdk::{Address, Env, IntoVal, Symbol};
+use soroban_sdk::{Address, Bytes, BytesN, Env, IntoVal, Symbol, U256};
 
-use crate::constants::SCALE_1E6;
+use crate::constants::{FLASH_CALLBACK_MESSAGE, INDEX_SCALE_1E18, SCALE_1E6, SECONDS_PER_YEAR};
 use crate::events::{ExternalCallFailed, InterestOverflow, InvalidSeizeAttempt};
 
 pub fn abort_seize(
@@ -24,16 +24,62 @@ pub fn ensure_user_auth(_env: &Env, user: &Address) {
     user.require_auth();
 }
 
+/// Underlying value of `ptokens` at a 1e18-scaled exchange rate (rounds down).
+pub fn ptokens_to_underlying(env: &Env, ptokens: u128, rate: u128) -> u128 {
+    U256::from_u128(env, ptokens)
+        .mul(&U256::from_u128(env, rate))
+        .div(&U256::from_u128(env, INDEX_SCALE_1E18))
+        .to_u128()
+        .expect("exchange rate overflow")
+}
+
+/// pTokens worth `amount` underlying at a 1e18-scaled exchange rate (rounds down).
+pub fn underlying_to_ptokens(env: &Env, amount: u128, rate: u128) -> u128 {
+    if rate == 0 {
+        panic!("invalid exchange rate");
+    }
+    U256::from_u128(env, amount)
+        .mul(&U256::from_u128(env, INDEX_SCALE_1E18))
+        .div(&U256::from_u128(env, rate))
+        .to_u128()
+        .expect("exchange rate overflow")
+}
+
+/// Underlying needed to mint `ptokens` at a 1e18-scaled exchange rate (rounds up).
+pub fn ptokens_to_underlying_ceil(env: &Env, ptokens: u128, rate: u128) -> u128 {
+    let scale = U256::from_u128(env, INDEX_SCALE_1E18);
+    U256::from_u128(env, ptokens)
+        .mul(&U256::from_u128(env, rate))
+        .add(&scale.sub(&U256::from_u32(env, 1)))
+        .div(&scale)
+        .to_u128()
+        .expect("exchange rate overflow")
+}
+
+/// pTokens needed to receive at least `amount` underlying at a 1e18-scaled
+/// exchange rate (rounds up).
+pub fn underlying_to_ptokens_ceil(env: &Env, amount: u128, rate: u128) -> u128 {
+    if rate == 0 {
+        panic!("invalid exchange rate");
+    }
+    let rate_wide = U256::from_u128(env, rate);
+    U256::from_u128(env, amount)
+        .mul(&U256::from_u128(env, INDEX_SCALE_1E18))
+        .add(&rate_wide.sub(&U256::from_u32(env, 1)))
+        .div(&rate_wide)
+        .to_u128()
+        .expect("exchange rate overflow")
+}
+
 pub fn checked_interest_product(
     env: &Env,
     amount: u128,
     yearly_rate_scaled: u128,
     elapsed: u128,
 ) -> u128 {
-    // Compute interest = amount * yearly_rate_scaled * elapsed / (seconds_per_year * 1e6)
+    // Compute interest = amount * yearly_rate_scaled * elapsed / (SECONDS_PER_YEAR * 1e6)
     // Reduce factors by gcd with denominator to avoid intermediate overflow.
-    let seconds_per_year: u128 = 365 * 24 * 60 * 60;
-    let mut denom = seconds_per_year.saturating_mul(SCALE_1E6);
+    let mut denom = SECONDS_PER_YEAR.saturating_mul(SCALE_1E6);
     let mut a = amount;
     let mut b = yearly_rate_scaled;
     let mut c = elapsed;
@@ -63,6 +109,73 @@ pub fn checked_interest_product(
     numerator / denom
 }
 
+/// Interest on `amount` compounded every second over `elapsed` seconds:
+/// amount * (e^x - 1) with x = yearly_rate * elapsed / SECONDS_PER_YEAR.
+/// Per-second compounding converges to e^x (relative gap ~ x^2 / (2 * elapsed)), so the
+/// growth factor is evaluated as a Taylor series in 1e18 fixed point (range-reduced by halving x,
+/// then squared back up) using 256-bit intermediates.
+pub fn checked_compound_interest(
+    env: &Env,
+    amount: u128,
+    yearly_rate_scaled: u128,
+    elapsed: u128,
+) -> u128 {
+    let overflow = || -> ! {
+        InterestOverflow {
+            amount,
+            yearly_rate_scaled,
+            elapsed,
+        }
+        .publish(env);
+        panic!("interest overflow");
+    };
+    // x scaled 1e18
+    let mut x = yearly_rate_scaled
+        .checked_mul(elapsed)
+        .and_then(|v| v.checked_mul(INDEX_SCALE_1E18 / SCALE_1E6))
+        .unwrap_or_else(|| overflow())
+        / SECONDS_PER_YEAR;
+    let mut squarings = 0u32;
+    while x > INDEX_SCALE_1E18 {
+        x /= 2;
+        squarings += 1;
+    }
+
+    let zero = U256::from_u32(env, 0);
+    let two = U256::from_u32(env, 2);
+    let scale = U256::from_u128(env, INDEX_SCALE_1E18);
+    let x_wide = U256::from_u128(env, x);
+    // growth = e^x - 1 (scaled 1e18)
+    let mut growth = x_wide.clone();
+    let mut term = x_wide.clone();
+    let mut k = 2u32;
+    while k <= MAX_COMPOUND_TERMS {
+        term = term.mul(&x_wide).div(&scale.mul(&U256::from_u32(env, k)));
+        if term == zero {
+            break;
+        }
+        growth = growth.add(&term);
+        k += 1;
+    }
+    // (1 + g)^2 - 1 = 2g + g^2
+    let growth_cap = U256::from_u128(env, u128::MAX);
+    for _ in 0..squarings {
+        growth = growth.mul(&two).add(&growth.mul(&growth).div(&scale));
+        if growth > growth_cap {
+            overflow();
+        }
+    }
+
+    U256::from_u128(env, amount)
+        .mul(&growth)
+        .div(&scale)
+        .to_u128()
+        .unwrap_or_else(|| overflow())
+}
+
+// x <= 1 after range reduction, so 1/k! drops below 1e-18 well within this bound.
+const MAX_COMPOUND_TERMS: u32 = 24;
+
 fn gcd_u128(mut a: u128, mut b: u128) -> u128 {
     while b != 0 {
         let r = a % b;
@@ -146,3 +259,10 @@ where
         }
     }
 }
+
+/// Value a flash borrower's `on_flash_loan` must return to accept the loan.
+pub fn flash_callback_success(env: &Env) -> BytesN<32> {
+    env.crypto()
+        .keccak256(&Bytes::from_slice(env, FLASH_CALLBACK_MESSAGE))
+        .to_bytes()
+}
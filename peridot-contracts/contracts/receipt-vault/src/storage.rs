@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address, Env, IntoVal};
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Vec};
 use stellar_tokens::fungible::Base as TokenBase;
 
 // Storage key types for the contract
@@ -11,10 +11,12 @@ pub enum DataKey {
     LastUpdateTime,        // u64
     AccumulatedInterest,   // u128
     YearlyRateScaled,      // u128, scaled by 1_000_000 (6 decimals)
-    InitialExchangeRate,   // u128, scaled 1e6
+    InitialExchangeRate,   // u128, scaled 1e6 (widened to 1e18 for accounting)
     // Borrowing-related keys
     BorrowSnapshots(Address),      // BorrowSnapshot per user
     HasBorrowed(Address),          // bool flag per user
+    FixedBorrowSnapshots(Address), // FixedBorrowSnapshot per user
+    TermLoans(Address),            // Vec<TermLoan> per user, ordered by maturity
     MarginBorrowSnapshots(u64),    // BorrowSnapshot per margin position
     MarginHasBorrowed(u64),        // bool flag per margin position
     TotalBorrowed,                 // u128
@@ -25,25 +27,66 @@ pub enum DataKey {
     PendingAdmin,                  // Address pending acceptance
     Peridottroller,                // Address (optional)
     InterestModel,                 // Address (optional)
+    InterestModelKeyed,            // bool, InterestModel is a registry keyed by market
+    PendingParam(VaultParam),      // PendingParamChange staged behind the admin delay
+    PendingInterestModel,          // PendingInterestModel staged behind the admin delay
     ReserveFactorScaled,           // u128 (scaled 1e6), defaults 0
     AdminFeeScaled,                // u128 (scaled 1e6), defaults 0
     FlashLoanFeeScaled,            // u128 (scaled 1e6), defaults 0
+    FlashLoanFeeDiscount(Address), // u128 (scaled 1e6) fee discount per initiator, 1e6 = exempt
     TotalAdminFees,                // u128 accumulated admin fees
     TotalReserves,                 // u128 accumulated reserves
+    Treasury,                      // Address (optional) allowed to sweep reserves and admin fees
     SupplyCap,                     // u128, max total underlying (principal + interest)
     BorrowCap,                     // u128, max total borrowed
     Initialized,                   // bool flag to prevent re-initialization
     BoostedVault,                  // Optional DeFindex vault address for boosted markets
     BoostedUnderlyingCached,       // u128 cached underlying amount for boosted vault
     BoostedUnderlyingUpdatedAt,    // u64 timestamp of cached boosted underlying
+    Strategies,                    // Vec<StrategyConfig> in withdrawal priority order
+    StrategyCached(Address),       // u128 cached underlying for one strategy
+    StrategyCachedAt(Address),     // u64 timestamp of a strategy's cached underlying
+    StrategyBasis(Address),        // u128 recognized strategy underlying (net flows, high-water)
+    BoostedImpaired,               // bool (instance) boosted deposits paused after a loss
     TotalBorrowPrincipal,          // u128 principal-only global borrow total
-    RatesReady,                    // bool, borrow/rate-sensitive operations enabled
-    IdleCashBufferBps,             // u32, target idle cash in basis points (0..=10_000)
-    FlashLoanActive,               // bool reentrancy guard for accounting-sensitive paths
-    MarginController,              // Address (optional), enforces margin collateral locks
+    FixedDebtPool,   // FixedDebtPool sums behind the fixed-rate share of TotalBorrowed
+    FixedRateConfig, // FixedRateConfig, fixed-rate borrowing disabled when unset
+    TermConfig,      // TermConfig, term borrowing disabled when unset
+    NextTermLoanId,  // u64 term loan id counter
+    LastBorrowRate,  // u128 (instance) variable rate of the latest accrual
+    RatesReady,      // bool, borrow/rate-sensitive operations enabled
+    IdleCashBufferBps, // u32, target idle cash in basis points (0..=10_000)
+    FlashLoanActive, // bool reentrancy guard for accounting-sensitive paths
+    FlashLoanPending, // (Address, u128, u128, u128) open controller flash leg
+    MarginController, // Address (optional), enforces margin collateral locks
     MarginWithdrawBypass(Address), // bool one-shot bypass for margin-controller-managed withdraw
-    PendingUpgradeHash,            // BytesN<32> target wasm hash for timelocked upgrade
-    PendingUpgradeEta,             // u64 unix timestamp when upgrade becomes executable
+    DeferredHealthCheck(Address), // bool one-shot skip of redeem health gating
+    Shutdown,        // bool (instance) settlement mode set by peridottroller
+    ShutdownSettlement, // ShutdownSettlement (instance) supply snapshot and payouts
+    ShutdownRedeemed(Address), // ShutdownRedemption holder's payouts in the current shutdown
+    AccessMode,      // AccessMode (instance), defaults to Open
+    RateCheckpoint(u32), // RateCheckpoint ring buffer slot
+    RateCheckpointHead, // u32 (instance) slot of the oldest checkpoint
+    RateCheckpointLen, // u32 (instance) checkpoints recorded, up to MAX_RATE_CHECKPOINTS
+    CheckpointAt,    // u64 (instance) timestamp of the latest checkpoint
+    CheckpointInterval, // u64 (instance) min seconds between checkpoints
+    MarketManager,   // Address (optional), manages access allowlists
+    Allowlisted(Address), // bool explicit allowlist membership
+    AllowlistSource, // Address (optional) external membership contract
+    PendingUpgradeHash, // BytesN<32> target wasm hash for timelocked upgrade
+    PendingUpgradeEta, // u64 unix timestamp when upgrade becomes executable
+    WithdrawQueueHead, // u64 id of the oldest live withdraw request
+    WithdrawQueueTail, // u64 id of the newest live withdraw request
+    NextWithdrawRequestId, // u64 id assigned to the next withdraw request
+    WithdrawQueueLen, // u32 live withdraw requests
+    WithdrawQueuePtokens, // u128 pTokens escrowed across live withdraw requests
+    WithdrawRequest(u64), // WithdrawRequest escrowing pTokens until filled
+    UserWithdrawRequest(Address), // u64 id of the user's live withdraw request
+    OutflowLimit,    // OutflowLimit, net outflows unlimited when unset
+    OutflowWindow,   // OutflowWindow net outflow tracked by the limiter
+    DelayedOutflow(Address), // DelayedOutflow held back from a rate-limited payout
+    TotalDelayedOutflows, // u128 underlying held for delayed claims
+    DustLimits,      // DustLimits, no minimums when unset
 }
 
 const TTL_THRESHOLD: u32 = 500_000;
@@ -62,7 +105,7 @@ const MARGIN_HAS_BORROWED_TTL_EXTEND_TO: u32 = 1_000_000;
 pub struct MarketLiquidityHint {
     pub ptoken_balance: u128,
     pub user_borrowed: u128,
-    pub exchange_rate: u128,
+    pub exchange_rate: u128, // scaled 1e18
 }
 
 #[contracttype]
@@ -72,6 +115,197 @@ pub struct BorrowSnapshot {
     pub interest_index: u128,
 }
 
+/// Fixed-rate debt accrues simple interest at `rate` from `updated_at`;
+/// `principal` is the debt as of that time. Every repay, borrow or rebalance
+/// rolls the accrued interest into `principal`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedBorrowSnapshot {
+    pub principal: u128,
+    pub rate: u128, // yearly, scaled 1e6
+    pub updated_at: u64,
+}
+
+/// Sums over fixed-rate snapshots and term loans, which all accrue simple
+/// interest, so the pooled debt at `t` is exactly the sum of their debts:
+/// `principal + (rate_weight * t - time_weight) / (1e6 * year)`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedDebtPool {
+    pub principal: u128,
+    pub rate_weight: u128, // sum of principal * rate
+    pub time_weight: u128, // sum of principal * rate * updated_at
+}
+
+/// Taken when shutdown is entered. Holders share the cash available at any
+/// time plus everything already `paid` pro rata to the pTokens they held at
+/// entry, `ptokens` in total.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShutdownSettlement {
+    pub epoch: u32,
+    pub ptokens: u128,
+    pub paid: u128,
+}
+
+/// pTokens burned and underlying paid to one holder during shutdown `epoch`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShutdownRedemption {
+    pub epoch: u32,
+    pub ptokens: u128,
+    pub underlying: u128,
+}
+
+/// pTokens escrowed by `owner` until incoming cash fills the request. Live
+/// requests form a doubly linked list from `WithdrawQueueHead`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawRequest {
+    pub owner: Address,
+    pub ptokens: u128,
+    pub prev: Option<u64>,
+    pub next: Option<u64>,
+}
+
+/// Queue view for one supplier; underlying amounts use the current exchange rate.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawRequestInfo {
+    pub id: u64,
+    pub ptokens: u128,
+    pub underlying: u128,
+    pub position: u32,          // live requests ahead in the queue
+    pub underlying_ahead: u128, // underlying owed to those requests
+    pub expected_fill: u128,    // this request's pro-rata share of current cash
+}
+
+/// Net outflow (withdrawals and borrows less deposits and repays) allowed
+/// within a rolling `window_secs`. Payouts beyond it are held for
+/// `claim_delay_secs`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutflowLimit {
+    pub max_net_outflow: u128,
+    pub window_secs: u64,
+    pub claim_delay_secs: u64,
+}
+
+/// Tracked net outflow; it decays linearly by `max_net_outflow` per window.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutflowWindow {
+    pub net_outflow: u128,
+    pub last_update: u64,
+}
+
+/// Underlying owed to a recipient once `claimable_at` has passed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DelayedOutflow {
+    pub amount: u128,
+    pub claimable_at: u64,
+}
+
+/// Smallest new borrow, and smallest debt a partial repay or liquidation may
+/// leave behind (in underlying).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DustLimits {
+    pub min_borrow: u128,
+    pub min_debt: u128,
+}
+
+/// Admin parameters that change through the staged, step-limited flow.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VaultParam {
+    ReserveFactor,
+    FlashLoanFee,
+    SupplyCap,
+    BorrowCap,
+    SupplyRate,
+    BorrowRate,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingParamChange {
+    pub value: u128,
+    pub execute_after: u64,
+}
+
+/// Rate model swap staged behind the admin delay; `keyed` marks a registry.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingInterestModel {
+    pub model: Address,
+    pub keyed: bool,
+    pub execute_after: u64,
+}
+
+/// Fixed-rate borrows lock `variable rate + premium`; a position may be
+/// rebalanced once the variable rate exceeds its locked rate by `rebalance_spread`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedRateConfig {
+    pub enabled: bool,
+    pub premium: u128,          // scaled 1e6
+    pub rebalance_spread: u128, // scaled 1e6
+}
+
+/// Term loans quote `variable rate + premium`, with the premium interpolated
+/// linearly between curve points by time to maturity.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TermPoint {
+    pub term_secs: u64,
+    pub premium: u128, // scaled 1e6
+}
+
+/// Term borrowing settings. Terms beyond the last curve point are rejected;
+/// overdue debt accrues at the variable rate (floored at the loan rate)
+/// plus `penalty_spread`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TermConfig {
+    pub enabled: bool,
+    pub curve: Vec<TermPoint>,
+    pub penalty_spread: u128, // scaled 1e6
+}
+
+/// Term debt is counted with fixed-rate debt; `principal` is the debt as of
+/// `updated_at`. Once settled past maturity it accrues at `penalty_rate`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TermLoan {
+    pub id: u64,
+    pub principal: u128,
+    pub rate: u128, // yearly, scaled 1e6
+    pub maturity: u64,
+    pub updated_at: u64,
+    pub penalty_rate: u128, // rate the fixed pool carries once settled past maturity, scaled 1e6
+}
+
+/// Open term loan as reported by `get_term_loans`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TermLoanInfo {
+    pub id: u64,
+    pub debt: u128,
+    pub rate: u128, // yearly, scaled 1e6
+    pub maturity: u64,
+    pub overdue: bool,
+}
+
+/// How `borrow_internal` prices the new debt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum BorrowMode {
+    Variable,
+    Fixed { max_rate: u128 },
+    Term { maturity: u64, max_rate: u128 },
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) struct ControllerAccrualHint {
@@ -81,11 +315,68 @@ pub(crate) struct ControllerAccrualHint {
     pub user_borrowed: Option<u128>,
 }
 
+/// Market snapshot `update_interest` records at most once per checkpoint interval.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateCheckpoint {
+    pub timestamp: u64,
+    pub exchange_rate: u128, // scaled 1e18
+    pub borrow_index: u128,  // scaled 1e18
+    pub utilization: u128,   // scaled 1e6
+    pub borrow_rate: u128,   // yearly, scaled 1e6
+}
+
+/// Time-weighted averages over the checkpoints covering `[start, end]`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateAverages {
+    pub start: u64,
+    pub end: u64,
+    pub exchange_rate: u128, // scaled 1e18
+    pub utilization: u128,   // scaled 1e6
+    pub borrow_rate: u128,   // yearly, scaled 1e6
+    pub supply_rate: u128,   // yearly, scaled 1e6, realized from exchange-rate growth
+}
+
+/// Market totals as `update_interest` would leave them after `elapsed` seconds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PendingAccrual {
+    pub borrow_rate: u128,
+    pub fixed_rate: u128,
+    pub utilization: u128,
+    pub interest: u128,
+    pub total_borrowed: u128,
+    pub total_fixed_borrowed: u128,
+    pub total_reserves: u128,
+    pub total_admin_fees: u128,
+    pub borrow_index: u128,
+}
+
+/// External yield strategy fed from idle market cash.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrategyConfig {
+    pub vault: Address,
+    pub target_bps: u32, // weight among strategies; 0 = withdraw-only
+    pub cap: u128,       // max underlying held by the strategy, 0 = uncapped
+}
+
+/// Who may supply to or borrow from a permissioned market.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AccessMode {
+    Open,
+    SupplyAllowlist,
+    BorrowAllowlist,
+    FullAllowlist,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SeizeContext {
     pub liquidity: u128,
     pub shortfall: u128,
+    pub overdue: bool, // repaying overdue term debt, liquidatable without a shortfall
     pub max_redeem_ptokens: u128,
     pub seize_ptokens: u128,
     pub fee_recipient: Option<Address>,
@@ -139,6 +430,9 @@ pub fn bump_core_ttl(env: &Env) {
     if persistent.has(&DataKey::InterestModel) {
         persistent.extend_ttl(&DataKey::InterestModel, TTL_THRESHOLD, TTL_EXTEND_TO);
     }
+    if persistent.has(&DataKey::InterestModelKeyed) {
+        persistent.extend_ttl(&DataKey::InterestModelKeyed, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
     if persistent.has(&DataKey::ReserveFactorScaled) {
         persistent.extend_ttl(&DataKey::ReserveFactorScaled, TTL_THRESHOLD, TTL_EXTEND_TO);
     }
@@ -183,6 +477,25 @@ pub fn bump_core_ttl(env: &Env) {
     if persistent.has(&DataKey::FlashLoanActive) {
         persistent.extend_ttl(&DataKey::FlashLoanActive, TTL_THRESHOLD, TTL_EXTEND_TO);
     }
+    if persistent.has(&DataKey::FixedRateConfig) {
+        persistent.extend_ttl(&DataKey::FixedRateConfig, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    if persistent.has(&DataKey::TermConfig) {
+        persistent.extend_ttl(&DataKey::TermConfig, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    if persistent.has(&DataKey::NextTermLoanId) {
+        persistent.extend_ttl(&DataKey::NextTermLoanId, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    for key in [
+        DataKey::OutflowLimit,
+        DataKey::OutflowWindow,
+        DataKey::TotalDelayedOutflows,
+        DataKey::DustLimits,
+    ] {
+        if persistent.has(&key) {
+            persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+        }
+    }
 }
 
 pub fn bump_pending_upgrade_ttl(env: &Env) {
@@ -215,8 +528,85 @@ pub fn bump_has_borrowed_ttl(env: &Env, user: &Address) {
     }
 }
 
+pub fn bump_fixed_borrow_snapshot_ttl(env: &Env, user: &Address) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::FixedBorrowSnapshots(user.clone());
+    if persistent.has(&key) {
+        persistent.extend_ttl(
+            &key,
+            BORROW_SNAPSHOT_TTL_THRESHOLD,
+            BORROW_SNAPSHOT_TTL_EXTEND_TO,
+        );
+    }
+}
+
+pub fn bump_pending_param_ttl(env: &Env, param: VaultParam) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::PendingParam(param);
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+pub fn bump_pending_interest_model_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::PendingInterestModel;
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+pub fn bump_withdraw_queue_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    for key in [
+        DataKey::WithdrawQueueHead,
+        DataKey::WithdrawQueueTail,
+        DataKey::NextWithdrawRequestId,
+        DataKey::WithdrawQueueLen,
+        DataKey::WithdrawQueuePtokens,
+    ] {
+        if persistent.has(&key) {
+            persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+        }
+    }
+}
+
+pub fn bump_withdraw_request_ttl(env: &Env, id: u64, owner: &Address) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::WithdrawRequest(id);
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    let user_key = DataKey::UserWithdrawRequest(owner.clone());
+    if persistent.has(&user_key) {
+        persistent.extend_ttl(&user_key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+pub fn bump_delayed_outflow_ttl(env: &Env, recipient: &Address) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::DelayedOutflow(recipient.clone());
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+pub fn bump_term_loans_ttl(env: &Env, user: &Address) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::TermLoans(user.clone());
+    if persistent.has(&key) {
+        persistent.extend_ttl(
+            &key,
+            BORROW_SNAPSHOT_TTL_THRESHOLD,
+            BORROW_SNAPSHOT_TTL_EXTEND_TO,
+        );
+    }
+}
+
 pub fn bump_user_borrow_state_ttl(env: &Env, user: &Address) {
     bump_borrow_snapshot_ttl(env, user);
+    bump_fixed_borrow_snapshot_ttl(env, user);
+    bump_term_loans_ttl(env, user);
     bump_has_borrowed_ttl(env, user);
 }
 
@@ -279,6 +669,9 @@ pub fn bump_borrow_state_ttl(env: &Env) {
     if persistent.has(&DataKey::TotalBorrowPrincipal) {
         persistent.extend_ttl(&DataKey::TotalBorrowPrincipal, TTL_THRESHOLD, TTL_EXTEND_TO);
     }
+    if persistent.has(&DataKey::FixedDebtPool) {
+        persistent.extend_ttl(&DataKey::FixedDebtPool, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
 }
 
 pub fn bump_idle_cash_buffer_ttl(env: &Env) {
@@ -288,6 +681,44 @@ pub fn bump_idle_cash_buffer_ttl(env: &Env) {
     }
 }
 
+pub fn bump_strategies_ttl(env: &Env) {
+    env.storage()
+        .persistent()
+        .extend_ttl(&DataKey::Strategies, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+pub fn bump_strategy_cache_ttl(env: &Env, vault: &Address) {
+    let persistent = env.storage().persistent();
+    let cached_key = DataKey::StrategyCached(vault.clone());
+    if persistent.has(&cached_key) {
+        persistent.extend_ttl(&cached_key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    let updated_key = DataKey::StrategyCachedAt(vault.clone());
+    if persistent.has(&updated_key) {
+        persistent.extend_ttl(&updated_key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    let basis_key = DataKey::StrategyBasis(vault.clone());
+    if persistent.has(&basis_key) {
+        persistent.extend_ttl(&basis_key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+pub fn bump_shutdown_redeemed_ttl(env: &Env, user: &Address) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::ShutdownRedeemed(user.clone());
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+pub fn bump_rate_checkpoint_ttl(env: &Env, slot: u32) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::RateCheckpoint(slot);
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
 pub fn bump_rates_ready_ttl(env: &Env) {
     let persistent = env.storage().persistent();
     if persistent.has(&DataKey::RatesReady) {
@@ -295,6 +726,14 @@ pub fn bump_rates_ready_ttl(env: &Env) {
     }
 }
 
+pub fn bump_allowlist_ttl(env: &Env, account: &Address) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::Allowlisted(account.clone());
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
 pub fn ptoken_balance(env: &Env, addr: &Address) -> u128 {
     let bal = TokenBase::balance(env, addr);
     if bal < 0 {
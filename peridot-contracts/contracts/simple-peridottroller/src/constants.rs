@@ -14,3 +14,6 @@ pub const MAX_FALLBACK_SCALE: u128 = 1_000_000_000_000_000_000u128; // 1e18
 pub const MAX_PAUSE_DURATION_SECS: u64 = 72 * 60 * 60;
 pub const UPGRADE_TIMELOCK_SECS: u64 = 24 * 60 * 60;
 pub const ADMIN_PARAM_CHANGE_DELAY_SECS: u64 = 24 * 60 * 60;
+pub const SHUTDOWN_EXIT_TIMELOCK_SECS: u64 = 48 * 60 * 60;
+// Multi-market flash receivers return keccak256 of this from `on_flash_loan_multi`.
+pub const FLASH_CALLBACK_MESSAGE: &[u8] = b"ERC3156FlashBorrower.onFlashLoan";
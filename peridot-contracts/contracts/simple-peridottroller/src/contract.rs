@@ -1,6 +1,7 @@
 use soroban_sdk::auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation};
 use soroban_sdk::{
-    contract, contractimpl, vec, Address, Env, IntoVal, InvokeError, Map, String, Symbol, Val, Vec,
+    contract, contractimpl, vec, Address, Bytes, BytesN, Env, IntoVal, InvokeError, Map, String,
+    Symbol, Val, Vec, U256,
 };
 
 use crate::constants::*;
@@ -21,6 +22,27 @@ impl SimplePeridottroller {
         }
     }
 
+    // Exchange rates are read from markets scaled 1e18; these mirror the vault's
+    // helpers exactly (round down, panic on overflow or a zero rate).
+    fn ptokens_to_underlying(env: &Env, ptokens: u128, rate: u128) -> u128 {
+        U256::from_u128(env, ptokens)
+            .mul(&U256::from_u128(env, rate))
+            .div(&U256::from_u128(env, INDEX_SCALE_1E18))
+            .to_u128()
+            .expect("exchange rate overflow")
+    }
+
+    fn underlying_to_ptokens(env: &Env, amount: u128, rate: u128) -> u128 {
+        if rate == 0 {
+            panic!("invalid exchange rate");
+        }
+        U256::from_u128(env, amount)
+            .mul(&U256::from_u128(env, INDEX_SCALE_1E18))
+            .div(&U256::from_u128(env, rate))
+            .to_u128()
+            .expect("exchange rate overflow")
+    }
+
     fn are_market_openings_paused(env: &Env, market: &Address) -> bool {
         let borrow_paused =
             Self::is_pause_active(env, DataKey::PauseBorrow, DataKey::PauseBorrowUntil, market);
@@ -259,6 +281,9 @@ impl SimplePeridottroller {
         env.storage()
             .persistent()
             .remove(&DataKey::MarketZeroTotalsVerifiedAt(market.clone()));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::MarketDepositOnly(market.clone()));
 
         // Maintain token allowlist consistency after market removal.
         let mut still_supported = false;
@@ -802,6 +827,25 @@ impl SimplePeridottroller {
         .publish(&env);
     }
 
+    /// Admin: debt at or below `threshold_usd` (in the units of
+    /// `get_user_total_borrows`) may be liquidated in full regardless of the
+    /// close factor. 0 disables the dust close.
+    pub fn set_dust_debt_threshold_usd(env: Env, threshold_usd: u128) {
+        bump_core_ttl(&env);
+        require_admin(env.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::DustDebtThresholdUsd, &threshold_usd);
+        DustDebtThresholdUpdated { threshold_usd }.publish(&env);
+    }
+
+    pub fn get_dust_debt_threshold_usd(env: Env) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DustDebtThresholdUsd)
+            .unwrap_or(0u128)
+    }
+
     pub fn set_oracle_max_age_multiplier(env: Env, k: u64) {
         bump_core_ttl(&env);
         require_admin(env.clone());
@@ -1296,6 +1340,11 @@ impl SimplePeridottroller {
     }
     pub fn is_borrow_paused(env: Env, market: Address) -> bool {
         bump_core_ttl(&env);
+        if Self::is_shutdown_active(&env)
+            || Self::is_market_deposit_only(env.clone(), market.clone())
+        {
+            return true;
+        }
         Self::is_pause_active(
             &env,
             DataKey::PauseBorrow,
@@ -1477,6 +1526,41 @@ impl SimplePeridottroller {
         }
         .publish(&env);
     }
+
+    /// Guardian: make a claim held back by `market`'s outflow limit payable now.
+    pub fn release_delayed_outflow_g(
+        env: Env,
+        guardian: Address,
+        market: Address,
+        recipient: Address,
+    ) {
+        bump_core_ttl(&env);
+        Self::require_market_supported(&env, &market);
+        Self::require_guardian_auth(&env, &guardian);
+        let _: () = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "release_delayed_outflow"),
+            (recipient,).into_val(&env),
+        );
+    }
+
+    /// Guardian: hold a claim on `market` back for another `extra_secs`.
+    pub fn extend_delayed_outflow_g(
+        env: Env,
+        guardian: Address,
+        market: Address,
+        recipient: Address,
+        extra_secs: u64,
+    ) {
+        bump_core_ttl(&env);
+        Self::require_market_supported(&env, &market);
+        Self::require_guardian_auth(&env, &guardian);
+        let _: () = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "extend_delayed_outflow"),
+            (recipient, extra_secs).into_val(&env),
+        );
+    }
     pub fn is_liquidation_paused(env: Env, market: Address) -> bool {
         bump_core_ttl(&env);
         Self::is_pause_active(
@@ -1497,6 +1581,96 @@ impl SimplePeridottroller {
         )
     }
 
+    fn is_shutdown_active(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::EmergencyShutdown)
+            .unwrap_or(false)
+    }
+
+    fn sync_markets_shutdown(env: &Env, active: bool) {
+        let markets: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::SupportedMarkets)
+            .unwrap_or(Map::new(env));
+        for (market, supported) in markets.iter() {
+            if !supported {
+                continue;
+            }
+            // One broken market must not block entering or leaving shutdown.
+            let synced = env.try_invoke_contract::<(), InvokeError>(
+                &market,
+                &Symbol::new(env, "set_shutdown"),
+                (active,).into_val(env),
+            );
+            if !matches!(synced, Ok(Ok(()))) {
+                MarketShutdownSyncFailed { market, active }.publish(env);
+            }
+        }
+    }
+
+    /// Protocol-wide emergency shutdown: prices freeze at their last cached values,
+    /// borrowing stops and every market settles withdrawals pro rata from its cash.
+    /// Requires both the admin and the pause guardian.
+    pub fn enter_shutdown(env: Env, guardian: Address) {
+        bump_core_ttl(&env);
+        require_admin(env.clone());
+        Self::require_guardian_auth(&env, &guardian);
+        if Self::is_shutdown_active(&env) {
+            panic!("already shut down");
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::EmergencyShutdown, &true);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingShutdownExitEta);
+        Self::sync_markets_shutdown(&env, true);
+        EmergencyShutdownUpdated { active: true }.publish(&env);
+    }
+
+    pub fn propose_exit_shutdown(env: Env) {
+        bump_core_ttl(&env);
+        require_admin(env.clone());
+        if !Self::is_shutdown_active(&env) {
+            panic!("not shut down");
+        }
+        let execute_after = env
+            .ledger()
+            .timestamp()
+            .saturating_add(SHUTDOWN_EXIT_TIMELOCK_SECS);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingShutdownExitEta, &execute_after);
+        storage::bump_pending_shutdown_exit_ttl(&env);
+        PendingShutdownExitUpdated { execute_after }.publish(&env);
+    }
+
+    pub fn exit_shutdown(env: Env) {
+        bump_core_ttl(&env);
+        require_admin(env.clone());
+        storage::bump_pending_shutdown_exit_ttl(&env);
+        let execute_after: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingShutdownExitEta)
+            .expect("shutdown exit not proposed");
+        if env.ledger().timestamp() < execute_after {
+            panic!("shutdown exit timelocked");
+        }
+        env.storage().instance().remove(&DataKey::EmergencyShutdown);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingShutdownExitEta);
+        Self::sync_markets_shutdown(&env, false);
+        EmergencyShutdownUpdated { active: false }.publish(&env);
+    }
+
+    pub fn is_shutdown(env: Env) -> bool {
+        Self::is_shutdown_active(&env)
+    }
+
     pub fn add_market(env: Env, market: Address) {
         bump_core_ttl(&env);
         let admin: Address = env
@@ -1505,6 +1679,9 @@ impl SimplePeridottroller {
             .get(&DataKey::Admin)
             .expect("admin not set");
         admin.require_auth();
+        if Self::is_shutdown_active(&env) {
+            panic!("protocol shut down");
+        }
         let mut markets: Map<Address, bool> = env
             .storage()
             .persistent()
@@ -1533,6 +1710,160 @@ impl SimplePeridottroller {
         MarketAdded { market }.publish(&env);
     }
 
+    /// Lists a market and applies its full risk configuration atomically. The vault must
+    /// already point at this controller and be administered by the same admin. The market
+    /// starts deposit-only (borrowing disabled) until `activate_market`.
+    pub fn list_market(env: Env, config: MarketConfig) {
+        bump_core_ttl(&env);
+        require_admin(env.clone());
+        if Self::is_shutdown_active(&env) {
+            panic!("protocol shut down");
+        }
+        let market = config.market.clone();
+        let mut markets: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::SupportedMarkets)
+            .unwrap_or(Map::new(&env));
+        if markets.get(market.clone()).unwrap_or(false) {
+            panic!("market already listed");
+        }
+        if config.collateral_factor < MIN_MARKET_CF || config.collateral_factor > 1_000_000u128 {
+            panic!("invalid collateral factor");
+        }
+        if config.reserve_factor > 1_000_000u128 {
+            panic!("invalid reserve factor");
+        }
+        let has_fallback = config.fallback_price > 0;
+        if has_fallback
+            && (config.fallback_scale == 0
+                || config.fallback_price > MAX_FALLBACK_PRICE
+                || config.fallback_scale > MAX_FALLBACK_SCALE)
+        {
+            panic!("invalid fallback price");
+        }
+        let wired: Option<Address> = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "get_peridottroller"),
+            ().into_val(&env),
+        );
+        if wired != Some(env.current_contract_address()) {
+            panic!("market not wired to peridottroller");
+        }
+        // Vault parameter changes only apply directly while nothing is supplied;
+        // afterwards they are staged, so listing requires an empty market.
+        let total_ptokens: u128 = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "get_total_ptokens"),
+            ().into_val(&env),
+        );
+        if total_ptokens > 0 {
+            panic!("market has suppliers");
+        }
+        let token: Address = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "get_underlying_token"),
+            ().into_val(&env),
+        );
+
+        let persistent = env.storage().persistent();
+        persistent.remove(&DataKey::PendingMarketCF(market.clone()));
+        persistent.remove(&DataKey::PendingMarketCFEta(market.clone()));
+        persistent.set(
+            &DataKey::MarketCF(market.clone()),
+            &config.collateral_factor,
+        );
+        match config.oracle_symbol.clone() {
+            Some(sym) => persistent.set(&DataKey::OracleAssetSymbol(token.clone()), &sym),
+            None => persistent.remove(&DataKey::OracleAssetSymbol(token.clone())),
+        }
+        if has_fallback {
+            persistent.set(
+                &DataKey::FallbackPrice(token.clone()),
+                &FallbackPrice {
+                    price: config.fallback_price,
+                    scale: config.fallback_scale,
+                },
+            );
+            persistent.set(
+                &DataKey::FallbackPriceSetAt(token.clone()),
+                &env.ledger().timestamp(),
+            );
+        } else {
+            persistent.remove(&DataKey::FallbackPrice(token.clone()));
+            persistent.remove(&DataKey::FallbackPriceSetAt(token.clone()));
+        }
+
+        // Vault-side parameters; the shared admin authorizes these sub-invocations.
+        let _: () = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "set_supply_cap"),
+            (config.supply_cap,).into_val(&env),
+        );
+        let _: () = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "set_borrow_cap"),
+            (config.borrow_cap,).into_val(&env),
+        );
+        let _: () = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "set_reserve_factor"),
+            (config.reserve_factor,).into_val(&env),
+        );
+        if let Some(model) = config.interest_model.clone() {
+            let _: () = env.invoke_contract(
+                &market,
+                &Symbol::new(&env, "set_interest_model"),
+                (model,).into_val(&env),
+            );
+        }
+
+        markets.set(market.clone(), true);
+        persistent.set(&DataKey::SupportedMarkets, &markets);
+        persistent.set(&DataKey::SupportedToken(token.clone()), &true);
+        persistent.set(&DataKey::MarketUnderlying(market.clone()), &token);
+        persistent.remove(&DataKey::MarketZeroTotalsVerifiedAt(market.clone()));
+        persistent.set(&DataKey::MarketDepositOnly(market.clone()), &true);
+        storage::bump_supported_token_ttl(&env, &token);
+        storage::bump_market_underlying_ttl(&env, &market);
+        storage::bump_market_cf_ttl(&env, &market);
+        storage::bump_oracle_asset_symbol_ttl(&env, &token);
+        storage::bump_fallback_price_ttl(&env, &token);
+        storage::bump_fallback_price_set_at_ttl(&env, &token);
+        storage::bump_market_deposit_only_ttl(&env, &market);
+        // Fail the whole listing if the asset cannot be priced with the new oracle mapping.
+        if Self::try_require_price(&env, &token).is_none() {
+            panic!("price unavailable");
+        }
+        MarketListed {
+            market,
+            cf_mantissa: config.collateral_factor,
+        }
+        .publish(&env);
+    }
+
+    /// Ends deposit-only mode for a market listed via `list_market`.
+    pub fn activate_market(env: Env, market: Address) {
+        bump_core_ttl(&env);
+        require_admin(env.clone());
+        Self::require_market_supported(&env, &market);
+        if !Self::is_market_deposit_only(env.clone(), market.clone()) {
+            panic!("market already active");
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::MarketDepositOnly(market.clone()));
+        MarketActivated { market }.publish(&env);
+    }
+
+    pub fn is_market_deposit_only(env: Env, market: Address) -> bool {
+        storage::bump_market_deposit_only_ttl(&env, &market);
+        env.storage()
+            .persistent()
+            .get(&DataKey::MarketDepositOnly(market))
+            .unwrap_or(false)
+    }
+
     // Admin-maintained proof that a market has no outstanding supply/borrow totals.
     // This is used to gate emergency force-delisting without relying on reads at delist time.
     pub fn verify_market_zero_totals(env: Env, market: Address) {
@@ -1883,10 +2214,10 @@ impl SimplePeridottroller {
             if pbal > 0 {
                 let rate: u128 = env.invoke_contract(
                     &m,
-                    &Symbol::new(&env, "get_exchange_rate"),
+                    &Symbol::new(&env, "get_exchange_rate_1e18"),
                     ().into_val(&env),
                 );
-                total = total.saturating_add((pbal.saturating_mul(rate)) / 1_000_000u128);
+                total = total.saturating_add(Self::ptokens_to_underlying(&env, pbal, rate));
             }
         }
         total
@@ -1919,11 +2250,11 @@ impl SimplePeridottroller {
             if pbal > 0 {
                 let rate: u128 = env.invoke_contract(
                     &m,
-                    &Symbol::new(&env, "get_exchange_rate"),
+                    &Symbol::new(&env, "get_exchange_rate_1e18"),
                     ().into_val(&env),
                 );
                 let cf: u128 = Self::get_market_cf(env.clone(), m.clone());
-                let underlying = (pbal.saturating_mul(rate)) / 1_000_000u128;
+                let underlying = Self::ptokens_to_underlying(&env, pbal, rate);
                 let discounted = (underlying.saturating_mul(cf)) / 1_000_000u128;
                 total = total.saturating_add(discounted);
             }
@@ -1931,7 +2262,8 @@ impl SimplePeridottroller {
         total
     }
 
-    // Sum borrows across user's entered markets
+    /// Sum of `get_user_borrow_balance` across entered markets, which covers
+    /// variable, fixed-rate and term debt.
     pub fn get_user_total_borrows(env: Env, user: Address) -> u128 {
         bump_core_ttl(&env);
         let mut total: u128 = 0u128;
@@ -2125,7 +2457,7 @@ impl SimplePeridottroller {
         if hint.ptoken_balance > 0 {
             let cf: u128 = Self::get_market_cf(env.clone(), market);
             let underlying_amount =
-                (hint.ptoken_balance.saturating_mul(hint.exchange_rate)) / 1_000_000u128;
+                Self::ptokens_to_underlying(&env, hint.ptoken_balance, hint.exchange_rate);
             let discounted = (underlying_amount.saturating_mul(cf)) / 1_000_000u128;
             let usd = (discounted.saturating_mul(price)) / scale;
             collateral_usd = collateral_usd.saturating_add(usd);
@@ -2143,7 +2475,8 @@ impl SimplePeridottroller {
         }
     }
 
-    // Preview the maximum additional borrow in underlying units for a given market
+    // Preview the maximum additional borrow in underlying units for a given market;
+    // 0 when it falls below the market's minimum borrow
     pub fn preview_borrow_max(env: Env, user: Address, market: Address) -> u128 {
         bump_core_ttl(&env);
         // Account-level cushion in USD
@@ -2167,11 +2500,20 @@ impl SimplePeridottroller {
             &Symbol::new(&env, "get_available_liquidity"),
             ().into_val(&env),
         );
-        if by_collateral < available {
+        let max_borrow = if by_collateral < available {
             by_collateral
         } else {
             available
+        };
+        let min_borrow: u128 = env.invoke_contract(
+            &market,
+            &Symbol::new(&env, "get_min_borrow"),
+            ().into_val(&env),
+        );
+        if max_borrow < min_borrow {
+            return 0u128;
         }
+        max_borrow
     }
 
     // Preview the maximum redeemable pTokens from a given market without creating shortfall
@@ -2202,7 +2544,7 @@ impl SimplePeridottroller {
             // also clamp by available liquidity
             let rate: u128 = env.invoke_contract(
                 &market,
-                &Symbol::new(&env, "get_exchange_rate"),
+                &Symbol::new(&env, "get_exchange_rate_1e18"),
                 ().into_val(&env),
             );
             if rate == 0 {
@@ -2213,7 +2555,7 @@ impl SimplePeridottroller {
                 &Symbol::new(&env, "get_available_liquidity"),
                 ().into_val(&env),
             );
-            let max_ptokens_by_liq = (available.saturating_mul(1_000_000u128)) / rate;
+            let max_ptokens_by_liq = Self::underlying_to_ptokens(&env, available, rate);
             return if pbal < max_ptokens_by_liq {
                 pbal
             } else {
@@ -2240,7 +2582,7 @@ impl SimplePeridottroller {
         }
         let rate: u128 = env.invoke_contract(
             &market,
-            &Symbol::new(&env, "get_exchange_rate"),
+            &Symbol::new(&env, "get_exchange_rate_1e18"),
             ().into_val(&env),
         );
         if rate == 0 {
@@ -2254,7 +2596,7 @@ impl SimplePeridottroller {
         );
         let (price, scale) = Self::require_price(env.clone(), underlying_token);
         // Current local underlying
-        let underlying_local = (pbal.saturating_mul(rate)) / 1_000_000u128;
+        let underlying_local = Self::ptokens_to_underlying(&env, pbal, rate);
         // Compute max redeemable underlying so that remaining discounted >= required
         // remaining_discounted_usd = (underlying_local - x) * cf/1e6 * price/scale >= required
         // => x <= underlying_local - required * 1e6 * scale / (cf * price)
@@ -2286,8 +2628,8 @@ impl SimplePeridottroller {
         } else {
             available_underlying
         };
-        // Convert to pTokens: p = underlying * 1e6 / rate
-        (clamped_underlying.saturating_mul(1_000_000u128)) / rate
+        // Convert to pTokens: p = underlying * 1e18 / rate
+        Self::underlying_to_ptokens(&env, clamped_underlying, rate)
     }
 
     // Preview maximum repay amount for a borrower on a given market (close factor cap)
@@ -2343,13 +2685,13 @@ impl SimplePeridottroller {
         let seize_underlying = (seize_underlying_usd.saturating_mul(sc)) / pc;
         let rate: u128 = env.invoke_contract(
             &collateral_market,
-            &Symbol::new(&env, "get_exchange_rate"),
+            &Symbol::new(&env, "get_exchange_rate_1e18"),
             ().into_val(&env),
         );
         if rate == 0 {
             panic!("invalid exchange rate");
         }
-        (seize_underlying.saturating_mul(1_000_000u128)) / rate
+        Self::underlying_to_ptokens(&env, seize_underlying, rate)
     }
 
     // Liquidation entrypoint: liquidator repays on behalf and seizes collateral pTokens.
@@ -2454,24 +2796,33 @@ impl SimplePeridottroller {
         if !collateral_entered {
             panic!("collateral market not entered");
         }
+        // Term debt past maturity is liquidatable regardless of account health.
+        let mut overdue_term_debt = 0u128;
         let (shortfall_for_ctx, liquidity_for_ctx) = if require_account_shortfall {
             let (known_collateral_usd, known_borrow_usd, indeterminate, collateral_indeterminate) =
                 Self::sum_positions_usd(env.clone(), borrower.clone(), None);
             let account_shortfall = known_borrow_usd.saturating_sub(known_collateral_usd);
             let account_liquidity = known_collateral_usd.saturating_sub(known_borrow_usd);
-            // Ensure borrower is undercollateralized using known (deterministic) positions.
-            // If health is indeterminate due to unrelated failing markets, allow liquidation
-            // only when known positions are already in shortfall.
-            if account_shortfall == 0 && indeterminate {
-                panic!("health indeterminate");
-            }
-            if account_shortfall > 0 && collateral_indeterminate {
-                panic!("health indeterminate");
-            }
             if account_shortfall == 0 {
-                panic!("no shortfall");
+                overdue_term_debt = Self::overdue_term_debt(&env, &repay_market, &borrower);
+            }
+            if overdue_term_debt > 0 {
+                (0u128, account_liquidity)
+            } else {
+                // Ensure borrower is undercollateralized using known (deterministic) positions.
+                // If health is indeterminate due to unrelated failing markets, allow liquidation
+                // only when known positions are already in shortfall.
+                if account_shortfall == 0 && indeterminate {
+                    panic!("health indeterminate");
+                }
+                if account_shortfall > 0 && collateral_indeterminate {
+                    panic!("health indeterminate");
+                }
+                if account_shortfall == 0 {
+                    panic!("no shortfall");
+                }
+                (account_shortfall, account_liquidity)
             }
-            (account_shortfall, account_liquidity)
         } else {
             let position_shortfall = position_shortfall_usd.unwrap_or(0u128);
             if position_shortfall == 0 {
@@ -2504,15 +2855,6 @@ impl SimplePeridottroller {
         if debt == 0 {
             panic!("no debt");
         }
-        let max_repay = (debt.saturating_mul(close_factor)) / 1_000_000u128;
-        let mut repay = if repay_amount > max_repay {
-            max_repay
-        } else {
-            repay_amount
-        };
-        if repay == 0 {
-            panic!("repay too small");
-        }
 
         // tokens and prices
         let borrow_token: Address = env.invoke_contract(
@@ -2527,18 +2869,38 @@ impl SimplePeridottroller {
         );
         let (pb, sb) = Self::require_price(env.clone(), borrow_token.clone());
         let (pc, sc) = Self::require_price(env.clone(), coll_token.clone());
+
+        let dust_threshold_usd = Self::get_dust_debt_threshold_usd(env.clone());
+        let max_repay = if overdue_term_debt > 0 {
+            // The vault applies repayments to overdue term loans first.
+            overdue_term_debt.min(debt)
+        } else if dust_threshold_usd > 0 && (debt.saturating_mul(pb)) / sb <= dust_threshold_usd {
+            // Dust debt costs more to liquidate in slices than it pays; close it in full.
+            debt
+        } else {
+            (debt.saturating_mul(close_factor)) / 1_000_000u128
+        };
+        let repay = if repay_amount > max_repay {
+            max_repay
+        } else {
+            repay_amount
+        };
+        let mut repay = Self::clamp_to_min_debt(&env, &repay_market, debt, repay, repay_amount);
+        if repay == 0 {
+            panic!("repay too small");
+        }
         let repay_usd = (repay.saturating_mul(pb)) / sb;
         let seize_underlying_usd = (repay_usd.saturating_mul(li_scaled)) / 1_000_000u128;
         let seize_underlying = (seize_underlying_usd.saturating_mul(sc)) / pc;
         let rate: u128 = env.invoke_contract(
             &collateral_market,
-            &Symbol::new(&env, "get_exchange_rate"),
+            &Symbol::new(&env, "get_exchange_rate_1e18"),
             ().into_val(&env),
         );
         if rate == 0 {
             panic!("invalid exchange rate");
         }
-        let mut seize_ptokens = (seize_underlying.saturating_mul(1_000_000u128)) / rate;
+        let mut seize_ptokens = Self::underlying_to_ptokens(&env, seize_underlying, rate);
 
         // Clamp to available collateral and proportionally scale repay down first,
         // so liquidators never pay for collateral that cannot be seized.
@@ -2600,6 +2962,7 @@ impl SimplePeridottroller {
         let seize_ctx = SeizeContext {
             liquidity: liquidity_for_ctx,
             shortfall: shortfall_for_ctx,
+            overdue: overdue_term_debt > 0,
             max_redeem_ptokens,
             seize_ptokens,
             fee_recipient,
@@ -2664,6 +3027,127 @@ impl SimplePeridottroller {
         seize_ptokens
     }
 
+    /// Shrink a partial repay so it leaves at least `market`'s minimum debt.
+    /// When no partial repay can (debt at or below the minimum), the position
+    /// closes in full if the liquidator offered enough, so it never strands.
+    fn clamp_to_min_debt(
+        env: &Env,
+        market: &Address,
+        debt: u128,
+        repay: u128,
+        requested: u128,
+    ) -> u128 {
+        if repay >= debt {
+            return repay;
+        }
+        let min_debt = Self::market_min_debt(env, market);
+        if debt - repay >= min_debt {
+            return repay;
+        }
+        let shrunk = debt.saturating_sub(min_debt);
+        if shrunk > 0 {
+            return shrunk;
+        }
+        if requested < debt {
+            panic!("repay must close debt");
+        }
+        debt
+    }
+
+    /// `market`'s minimum remaining debt; 0 for markets without dust limits.
+    fn market_min_debt(env: &Env, market: &Address) -> u128 {
+        use soroban_sdk::{IntoVal, InvokeError};
+        match env.try_invoke_contract::<u128, InvokeError>(
+            market,
+            &Symbol::new(env, "get_min_debt"),
+            ().into_val(env),
+        ) {
+            Ok(Ok(v)) => v,
+            _ => 0u128,
+        }
+    }
+
+    /// Overdue term debt of `borrower` on `market`; 0 for markets without term loans.
+    fn overdue_term_debt(env: &Env, market: &Address, borrower: &Address) -> u128 {
+        use soroban_sdk::{IntoVal, InvokeError};
+        match env.try_invoke_contract::<u128, InvokeError>(
+            market,
+            &Symbol::new(env, "get_overdue_term_debt"),
+            (borrower.clone(),).into_val(env),
+        ) {
+            Ok(Ok(v)) => v,
+            _ => 0u128,
+        }
+    }
+
+    /// Flash-borrow from several markets at once on behalf of `initiator`. Every
+    /// market lends to `receiver` and stays locked while `receiver` handles a single
+    /// `on_flash_loan_multi` callback with `(market, amount, fee)` per leg, which
+    /// must answer with the ERC-3156 success value; each market then checks its
+    /// own `amount + fee` came back.
+    pub fn flash_loan_multi(
+        env: Env,
+        initiator: Address,
+        receiver: Address,
+        loans: Vec<(Address, u128)>,
+        data: Bytes,
+    ) {
+        bump_core_ttl(&env);
+        // The initiator is forwarded to the receiver, so it must be authenticated.
+        initiator.require_auth();
+        if loans.is_empty() {
+            panic!("no flash loans");
+        }
+        let mut markets: Vec<Address> = Vec::new(&env);
+        let mut legs: Vec<(Address, u128, u128)> = Vec::new(&env);
+        for (market, amount) in loans.iter() {
+            Self::require_market_supported(&env, &market);
+            if markets.contains(&market) {
+                panic!("duplicate flash market");
+            }
+            // Markets cannot call back into the controller mid-invocation, so
+            // borrow pauses are checked here rather than by the vault.
+            if Self::is_borrow_paused(env.clone(), market.clone()) {
+                panic!("borrow paused");
+            }
+            let fee: u128 = env.invoke_contract(
+                &market,
+                &Symbol::new(&env, "flash_loan_open"),
+                (initiator.clone(), receiver.clone(), amount).into_val(&env),
+            );
+            markets.push_back(market.clone());
+            legs.push_back((market, amount, fee));
+        }
+
+        let accepted: BytesN<32> = env.invoke_contract(
+            &receiver,
+            &Symbol::new(&env, "on_flash_loan_multi"),
+            (initiator.clone(), legs, data).into_val(&env),
+        );
+        let success = env
+            .crypto()
+            .keccak256(&Bytes::from_slice(&env, FLASH_CALLBACK_MESSAGE))
+            .to_bytes();
+        if accepted != success {
+            panic!("invalid flash callback");
+        }
+
+        for market in markets.iter() {
+            let _: () = env.invoke_contract(
+                &market,
+                &Symbol::new(&env, "flash_loan_close"),
+                ().into_val(&env),
+            );
+        }
+
+        FlashLoanMulti {
+            initiator,
+            receiver,
+            markets,
+        }
+        .publish(&env);
+    }
+
     /// Repay on behalf via peridottroller auth (no seize).
     pub fn repay_on_behalf_for_liquidator(
         env: Env,
@@ -2710,12 +3194,18 @@ impl SimplePeridottroller {
         if debt == 0 {
             panic!("no debt");
         }
-        let max_repay = (debt.saturating_mul(close_factor)) / 1_000_000u128;
+        let max_repay = if overdue_term_debt > 0 {
+            // The vault applies repayments to overdue term loans first.
+            overdue_term_debt.min(debt)
+        } else {
+            (debt.saturating_mul(close_factor)) / 1_000_000u128
+        };
         let repay = if repay_amount > max_repay {
             max_repay
         } else {
             repay_amount
         };
+        let repay = Self::clamp_to_min_debt(&env, &repay_market, debt, repay, repay_amount);
         if repay == 0 {
             panic!("repay too small");
         }
@@ -3020,13 +3510,13 @@ impl SimplePeridottroller {
                 // Exchange rate failure → treat as 0 collateral, still count debt
                 let rate: u128 = match env.try_invoke_contract::<u128, InvokeError>(
                     &m,
-                    &Symbol::new(&env, "get_exchange_rate"),
+                    &Symbol::new(&env, "get_exchange_rate_1e18"),
                     ().into_val(&env),
                 ) {
                     Ok(Ok(r)) if r > 0 => r,
                     _ => 0u128,
                 };
-                let underlying_amount = (pbal.saturating_mul(rate)) / 1_000_000u128;
+                let underlying_amount = Self::ptokens_to_underlying(&env, pbal, rate);
                 let discounted = (underlying_amount.saturating_mul(market_cf)) / 1_000_000u128;
                 let usd = (discounted.saturating_mul(price)) / scale;
                 collateral_total = collateral_total.saturating_add(usd);
@@ -3093,7 +3583,7 @@ impl SimplePeridottroller {
         } else {
             available_underlying
         };
-        let max_ptokens = (clamped_underlying.saturating_mul(1_000_000u128)) / rate;
+        let max_ptokens = Self::underlying_to_ptokens(&env, clamped_underlying, rate);
         if max_ptokens < borrower_pbal {
             max_ptokens
         } else {
@@ -3106,6 +3596,9 @@ impl SimplePeridottroller {
     // Price quotation via cached oracle data or fallback (no on-chain oracle call).
     pub fn get_price_usd(env: Env, token: Address) -> Option<(u128, u128)> {
         bump_core_ttl(&env);
+        if Self::is_shutdown_active(&env) {
+            return Self::frozen_price(&env, &token);
+        }
         storage::bump_price_cache_ttl(&env, &token);
         if let Some(cached) = env
             .storage()
@@ -3151,6 +3644,9 @@ impl SimplePeridottroller {
     // Public helper to pre-warm the price cache (intended for UI/keepers).
     pub fn cache_price(env: Env, token: Address) -> Option<(u128, u128)> {
         bump_core_ttl(&env);
+        if Self::is_shutdown_active(&env) {
+            return Self::frozen_price(&env, &token);
+        }
         if !env
             .storage()
             .persistent()
@@ -3243,6 +3739,9 @@ impl SimplePeridottroller {
     // Returns None instead of panicking when price unavailable
     fn try_require_price(env: &Env, token: &Address) -> Option<(u128, u128)> {
         bump_core_ttl(env);
+        if Self::is_shutdown_active(env) {
+            return Self::frozen_price(env, token);
+        }
         storage::bump_price_cache_ttl(env, token);
         if let Some(cached) = env
             .storage()
@@ -3287,6 +3786,9 @@ impl SimplePeridottroller {
 
     fn require_price(env: Env, token: Address) -> (u128, u128) {
         bump_core_ttl(&env);
+        if Self::is_shutdown_active(&env) {
+            return Self::frozen_price(&env, &token).expect("price unavailable");
+        }
         storage::bump_price_cache_ttl(&env, &token);
         if let Some(cached) = env
             .storage()
@@ -3329,6 +3831,26 @@ impl SimplePeridottroller {
         panic!("price unavailable");
     }
 
+    // During shutdown prices stay at the last good cached (or fallback) value regardless of age.
+    fn frozen_price(env: &Env, token: &Address) -> Option<(u128, u128)> {
+        storage::bump_price_cache_ttl(env, token);
+        if let Some(cached) = env
+            .storage()
+            .persistent()
+            .get::<_, CachedPrice>(&DataKey::PriceCache(token.clone()))
+        {
+            if cached.price > 0 && cached.scale > 0 {
+                return Some((cached.price, cached.scale));
+            }
+        }
+        storage::bump_fallback_price_ttl(env, token);
+        env.storage()
+            .persistent()
+            .get::<_, FallbackPrice>(&DataKey::FallbackPrice(token.clone()))
+            .filter(|fallback| fallback.price > 0 && fallback.scale > 0)
+            .map(|fallback| (fallback.price, fallback.scale))
+    }
+
     fn cached_price_fresh(env: &Env, cached_timestamp: u64, cached_resolution: u32) -> bool {
         let now = env.ledger().timestamp();
         let k: u64 = env
@@ -3646,11 +4168,11 @@ impl SimplePeridottroller {
                 if pbal > 0 {
                     let rate: u128 = env.invoke_contract(
                         &m,
-                        &Symbol::new(&env, "get_exchange_rate"),
+                        &Symbol::new(&env, "get_exchange_rate_1e18"),
                         ().into_val(&env),
                     );
                     let cf: u128 = Self::get_market_cf(env.clone(), m.clone());
-                    let underlying = (pbal.saturating_mul(rate)) / 1_000_000u128;
+                    let underlying = Self::ptokens_to_underlying(&env, pbal, rate);
                     let discounted = (underlying.saturating_mul(cf)) / 1_000_000u128;
                     coll_usd = (discounted.saturating_mul(price)) / scale;
                 }
@@ -1,4 +1,4 @@
-use soroban_sdk::{contractevent, Address, Symbol};
+use soroban_sdk::{contractevent, Address, Symbol, Vec};
 
 #[contractevent]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -78,6 +78,12 @@ pub struct LiquidationFeeUpdated {
     pub fee_mantissa: u128,
 }
 
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DustDebtThresholdUpdated {
+    pub threshold_usd: u128,
+}
+
 #[contractevent(topics = ["oracle_max_age_multiplier"])]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct OracleMaxAgeMultiplierUpdated {
@@ -152,6 +158,21 @@ pub struct MarketAdded {
     pub market: Address,
 }
 
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketListed {
+    #[topic]
+    pub market: Address,
+    pub cf_mantissa: u128,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketActivated {
+    #[topic]
+    pub market: Address,
+}
+
 #[contractevent]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MarketRemoved {
@@ -200,6 +221,16 @@ pub struct LiquidateBorrow {
     pub seize_tokens: u128,
 }
 
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FlashLoanMulti {
+    #[topic]
+    pub initiator: Address,
+    #[topic]
+    pub receiver: Address,
+    pub markets: Vec<Address>,
+}
+
 #[contractevent]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ClaimExternalCallFailed {
@@ -231,3 +262,24 @@ pub struct BorrowSpeedOverflowDisabled {
     #[topic]
     pub market: Address,
 }
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmergencyShutdownUpdated {
+    pub active: bool,
+}
+
+/// Emits when a market rejects the shutdown flag; the others are still updated.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketShutdownSyncFailed {
+    #[topic]
+    pub market: Address,
+    pub active: bool,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingShutdownExitUpdated {
+    pub execute_after: u64,
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address, Env};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
 
 #[contracttype(export = false)]
 pub enum DataKey {
@@ -57,6 +57,10 @@ pub enum DataKey {
     PendingLiqIncentiveEta,      // u64: earliest timestamp for staged liquidation incentive update
     PendingMarketCF(Address),    // u128: staged market collateral factor
     PendingMarketCFEta(Address), // u64: earliest timestamp for staged market CF update
+    MarketDepositOnly(Address),  // bool: listed via list_market, borrowing off until activated
+    EmergencyShutdown,           // bool (instance): protocol-wide settlement mode
+    PendingShutdownExitEta,      // u64: earliest timestamp to leave shutdown
+    DustDebtThresholdUsd,        // u128: debt at or below this USD value may be closed in full
 }
 
 #[contracttype]
@@ -81,6 +85,7 @@ pub struct MarketLiquidityHint {
 pub struct SeizeContext {
     pub liquidity: u128,
     pub shortfall: u128,
+    pub overdue: bool, // repaying overdue term debt, liquidatable without a shortfall
     pub max_redeem_ptokens: u128,
     pub seize_ptokens: u128,
     pub fee_recipient: Option<Address>,
@@ -104,6 +109,21 @@ pub struct FallbackPrice {
     pub scale: u128,
 }
 
+/// Complete risk configuration applied by `list_market` in a single call.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketConfig {
+    pub market: Address,
+    pub collateral_factor: u128, // scaled 1e6
+    pub oracle_symbol: Option<Symbol>,
+    pub fallback_price: u128, // 0 leaves no fallback
+    pub fallback_scale: u128,
+    pub supply_cap: u128,     // 0 disables
+    pub borrow_cap: u128,     // 0 disables
+    pub reserve_factor: u128, // scaled 1e6
+    pub interest_model: Option<Address>,
+}
+
 pub fn require_admin(env: Env) {
     let admin: Address = env
         .storage()
@@ -145,6 +165,9 @@ pub fn bump_core_ttl(env: &Env) {
     if persistent.has(&DataKey::LiquidationFeeScaled) {
         persistent.extend_ttl(&DataKey::LiquidationFeeScaled, TTL_THRESHOLD, TTL_EXTEND_TO);
     }
+    if persistent.has(&DataKey::DustDebtThresholdUsd) {
+        persistent.extend_ttl(&DataKey::DustDebtThresholdUsd, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
     if persistent.has(&DataKey::OracleMaxAgeMultiplier) {
         persistent.extend_ttl(
             &DataKey::OracleMaxAgeMultiplier,
@@ -230,6 +253,17 @@ pub fn bump_pending_market_cf_ttl(env: &Env, market: &Address) {
     }
 }
 
+pub fn bump_pending_shutdown_exit_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    if persistent.has(&DataKey::PendingShutdownExitEta) {
+        persistent.extend_ttl(
+            &DataKey::PendingShutdownExitEta,
+            TTL_THRESHOLD,
+            TTL_EXTEND_TO,
+        );
+    }
+}
+
 pub fn bump_pending_admin_ttl(env: &Env) {
     let persistent = env.storage().persistent();
     if persistent.has(&DataKey::PendingAdmin) {
@@ -263,6 +297,14 @@ pub fn bump_user_markets_ttl(env: &Env, user: &Address) {
     }
 }
 
+pub fn bump_market_deposit_only_ttl(env: &Env, market: &Address) {
+    let persistent = env.storage().persistent();
+    let key = DataKey::MarketDepositOnly(market.clone());
+    if persistent.has(&key) {
+        persistent.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
 pub fn bump_market_cf_ttl(env: &Env, market: &Address) {
     let persistent = env.storage().persistent();
     let key = DataKey::MarketCF(market.clone());
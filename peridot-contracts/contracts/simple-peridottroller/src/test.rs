@@ -807,7 +807,7 @@ fn test_hypothetical_liquidity_with_hint_refreshes_cross_market_interest() {
     let hint = MarketLiquidityHint {
         ptoken_balance: 100u128,
         user_borrowed: 0u128,
-        exchange_rate: 1_000_000u128,
+        exchange_rate: 1_000_000_000_000_000_000u128,
     };
     let (_liq, shortfall) = env.as_contract(&current_market_id, || {
         comp.hypothetical_liquidity_with_hint(&user, &current_market_id, &1u128, &token, &hint)
@@ -894,7 +894,7 @@ fn test_hypothetical_liquidity_with_hint_ignores_refresh_failure_for_zero_positi
     let hint = MarketLiquidityHint {
         ptoken_balance: 100u128,
         user_borrowed: 0u128,
-        exchange_rate: 1_000_000u128,
+        exchange_rate: 1_000_000_000_000_000_000u128,
     };
 
     let (_liq, shortfall) = env.as_contract(&current_market_id, || {
@@ -1281,7 +1281,7 @@ impl BrokenMarket {
         panic!("storage: missing value for key");
     }
 
-    pub fn get_exchange_rate(_env: Env) -> u128 {
+    pub fn get_exchange_rate_1e18(_env: Env) -> u128 {
         // Storage expired - simulate missing key panic
         panic!("storage: missing value for key");
     }
@@ -1318,8 +1318,8 @@ impl PbalReadFailMarket {
         0u128
     }
 
-    pub fn get_exchange_rate(_env: Env) -> u128 {
-        1_000_000u128
+    pub fn get_exchange_rate_1e18(_env: Env) -> u128 {
+        1_000_000_000_000_000_000u128
     }
 }
 
@@ -1427,8 +1427,8 @@ impl UpdateFailNoPositionMarket {
         0u128
     }
 
-    pub fn get_exchange_rate(_env: Env) -> u128 {
-        1_000_000u128
+    pub fn get_exchange_rate_1e18(_env: Env) -> u128 {
+        1_000_000_000_000_000_000u128
     }
 }
 
@@ -2434,6 +2434,104 @@ fn test_liquidation_no_shortfall_panics() {
     comp.liquidate(&borrower, &vault_a_id, &vault_b_id, &10u128, &liquidator);
 }
 
+#[test]
+fn test_liquidation_of_overdue_term_loan_without_shortfall() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let admin = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+
+    // Tokens
+    let token_admin_a = Address::generate(&env);
+    let token_a = env
+        .register_stellar_asset_contract_v2(token_admin_a.clone())
+        .address();
+    let token_admin_b = Address::generate(&env);
+    let token_b = env
+        .register_stellar_asset_contract_v2(token_admin_b.clone())
+        .address();
+
+    // Vaults
+    let vault_a_id = env.register(rv::ReceiptVault, ()); // term borrow market
+    let vault_a = rv::ReceiptVaultClient::new(&env, &vault_a_id);
+    let vault_b_id = env.register(rv::ReceiptVault, ()); // collateral market
+    let vault_b = rv::ReceiptVaultClient::new(&env, &vault_b_id);
+    vault_a.initialize(&token_a, &0u128, &0u128, &admin);
+    vault_a.enable_static_rates(&admin);
+    vault_b.initialize(&token_b, &0u128, &0u128, &admin);
+    vault_b.enable_static_rates(&admin);
+    let mut curve = Vec::new(&env);
+    curve.push_back(rv::TermPoint {
+        term_secs: 86_400u64,
+        premium: 0u128,
+    });
+    vault_a.set_term_config(
+        &admin,
+        &rv::TermConfig {
+            enabled: true,
+            curve,
+            penalty_spread: 0u128,
+        },
+    );
+
+    // Peridottroller
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(&env, &comp_id);
+    comp.initialize(&admin);
+    comp.add_market(&vault_a_id);
+    comp.add_market(&vault_b_id);
+    comp.enter_market(&borrower, &vault_a_id);
+    comp.enter_market(&borrower, &vault_b_id);
+    comp.set_market_cf(&vault_b_id, &500_000u128);
+
+    // Oracle
+    let oracle_id = env.register(MockOracle, ());
+    let oracle = MockOracleClient::new(&env, &oracle_id);
+    oracle.initialize(&6u32);
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token_a, 1_000_000i128);
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token_b, 1_000_000i128);
+    comp.set_oracle(&oracle_id);
+
+    // Wire peridottroller
+    vault_a.set_peridottroller(&comp_id);
+    vault_b.set_peridottroller(&comp_id);
+
+    // Mint tokens
+    let admin_a = token::StellarAssetClient::new(&env, &token_a);
+    let admin_b = token::StellarAssetClient::new(&env, &token_b);
+    admin_b.mint(&borrower, &1_000i128);
+    admin_a.mint(&liquidator, &1_000i128);
+    approve_token_to_vault(&env, &token_a, &liquidator, &vault_a_id, 1_000i128);
+
+    // Healthy position: 200 collateral at CF 50% backs a 50 term loan due in an hour.
+    vault_b.set_collateral_factor(&500_000u128);
+    vault_b.deposit(&borrower, &200u128);
+    vault_a.deposit(&liquidator, &300u128);
+    let maturity = env.ledger().timestamp() + 3_600;
+    let loan_id = vault_a.borrow_term(&borrower, &50u128, &maturity, &0u128);
+    assert_eq!(comp.get_user_total_borrows(&borrower), 50u128);
+    assert_eq!(vault_a.get_overdue_term_debt(&borrower), 0u128);
+
+    env.ledger().set_timestamp(maturity);
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token_a, 1_000_000i128);
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token_b, 1_000_000i128);
+    let loans = vault_a.get_term_loans(&borrower);
+    assert_eq!(loans.len(), 1);
+    assert_eq!(loans.get(0).unwrap().id, loan_id);
+    assert!(loans.get(0).unwrap().overdue);
+    assert_eq!(vault_a.get_overdue_term_debt(&borrower), 50u128);
+    let (_liquidity, shortfall) = comp.account_liquidity(&borrower);
+    assert_eq!(shortfall, 0u128);
+
+    // The close factor does not cap overdue term debt.
+    comp.liquidate(&borrower, &vault_a_id, &vault_b_id, &50u128, &liquidator);
+    assert_eq!(vault_a.get_user_borrow_balance(&borrower), 0u128);
+    assert_eq!(vault_a.get_term_loans(&borrower).len(), 0u32);
+    assert!(vault_b.get_ptoken_balance(&liquidator) > 0u128);
+}
+
 #[test]
 #[should_panic(expected = "repay too small")]
 fn test_liquidation_zero_repay_panics() {
@@ -3232,7 +3330,7 @@ fn test_liquidation_fee_routed_to_reserves() {
     assert!(p_res > 0u128);
     assert!(p_liq > 0u128);
     // Ensure total seized equals expected split of total seize
-    let rate: u128 = vb.get_exchange_rate();
+    let rate: u128 = vb.get_exchange_rate_1e18();
     let pb = 1_000_000u128; // $1 with 1e6 scale
     let sb = 1_000_000u128;
     let repay_usd = (25u128.saturating_mul(pb)) / sb;
@@ -3241,7 +3339,7 @@ fn test_liquidation_fee_routed_to_reserves() {
     let pc = 500_000u128;
     let sc = 1_000_000u128;
     let seize_underlying = (seize_underlying_usd.saturating_mul(sc)) / pc;
-    let seize_ptokens = (seize_underlying.saturating_mul(1_000_000u128)) / rate;
+    let seize_ptokens = (seize_underlying.saturating_mul(1_000_000_000_000_000_000u128)) / rate;
     assert_eq!(p_res + p_liq, seize_ptokens);
 }
 
@@ -5086,3 +5184,776 @@ fn test_find_039_liquidation_rejects_when_only_indeterminate_signal() {
     // Known positions are not underwater; indeterminate alone must not authorize liquidation.
     comp.liquidate(&alice, &vault_a_id, &vault_b_id, &10u128, &liquidator);
 }
+
+fn listing_config(market: &Address) -> MarketConfig {
+    MarketConfig {
+        market: market.clone(),
+        collateral_factor: 800_000u128,
+        oracle_symbol: None,
+        fallback_price: 0u128,
+        fallback_scale: 0u128,
+        supply_cap: 1_000u128,
+        borrow_cap: 500u128,
+        reserve_factor: 100_000u128,
+        interest_model: None,
+    }
+}
+
+#[test]
+fn test_list_market_applies_config_and_starts_deposit_only() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let vault_id = env.register(rv::ReceiptVault, ());
+    let vault = rv::ReceiptVaultClient::new(&env, &vault_id);
+    vault.initialize(&token, &0u128, &0u128, &admin);
+    vault.enable_static_rates(&admin);
+    vault.set_collateral_factor(&1_000_000u128);
+
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(&env, &comp_id);
+    comp.initialize(&admin);
+    let oracle_id = env.register(MockOracle, ());
+    let oracle = MockOracleClient::new(&env, &oracle_id);
+    oracle.initialize(&6u32);
+    comp.set_oracle(&oracle_id);
+    oracle.set_price(&token, &1_000_000i128);
+    vault.set_peridottroller(&comp_id);
+
+    comp.list_market(&listing_config(&vault_id));
+
+    assert_eq!(comp.get_market_cf(&vault_id), 800_000u128);
+    assert!(comp.is_market_deposit_only(&vault_id));
+    assert!(comp.is_borrow_paused(&vault_id));
+    let (supply_cap, borrow_cap, reserve_factor) = env.as_contract(&vault_id, || {
+        let persistent = env.storage().persistent();
+        (
+            persistent.get::<_, u128>(&rv::DataKey::SupplyCap).unwrap(),
+            persistent.get::<_, u128>(&rv::DataKey::BorrowCap).unwrap(),
+            persistent
+                .get::<_, u128>(&rv::DataKey::ReserveFactorScaled)
+                .unwrap(),
+        )
+    });
+    assert_eq!(supply_cap, 1_000u128);
+    assert_eq!(borrow_cap, 500u128);
+    assert_eq!(reserve_factor, 100_000u128);
+
+    // Deposits work while listed; borrowing opens only after activation.
+    let mint = token::StellarAssetClient::new(&env, &token);
+    mint.mint(&user, &1_000i128);
+    comp.enter_market(&user, &vault_id);
+    vault.deposit(&user, &200u128);
+    comp.activate_market(&vault_id);
+    assert!(!comp.is_market_deposit_only(&vault_id));
+    vault.borrow(&user, &50u128);
+    assert_eq!(vault.get_user_borrow_balance(&user), 50u128);
+}
+
+#[test]
+#[should_panic(expected = "borrow paused")]
+fn test_list_market_blocks_borrow_until_activated() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let vault_id = env.register(rv::ReceiptVault, ());
+    let vault = rv::ReceiptVaultClient::new(&env, &vault_id);
+    vault.initialize(&token, &0u128, &0u128, &admin);
+    vault.enable_static_rates(&admin);
+    vault.set_collateral_factor(&1_000_000u128);
+
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(&env, &comp_id);
+    comp.initialize(&admin);
+    vault.set_peridottroller(&comp_id);
+    let mut config = listing_config(&vault_id);
+    config.fallback_price = 1_000_000u128;
+    config.fallback_scale = 1_000_000u128;
+    comp.list_market(&config);
+
+    let mint = token::StellarAssetClient::new(&env, &token);
+    mint.mint(&user, &1_000i128);
+    comp.enter_market(&user, &vault_id);
+    vault.deposit(&user, &200u128);
+    // Clearing a regular pause must not end deposit-only mode.
+    comp.set_pause_borrow(&vault_id, &false);
+    vault.borrow(&user, &50u128);
+}
+
+#[test]
+#[should_panic(expected = "market not wired to peridottroller")]
+fn test_list_market_requires_vault_wired_to_peridottroller() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let vault_id = env.register(rv::ReceiptVault, ());
+    let vault = rv::ReceiptVaultClient::new(&env, &vault_id);
+    vault.initialize(&token, &0u128, &0u128, &admin);
+
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(&env, &comp_id);
+    comp.initialize(&admin);
+    comp.list_market(&listing_config(&vault_id));
+}
+
+#[test]
+#[should_panic(expected = "market has suppliers")]
+fn test_list_market_requires_market_without_suppliers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let vault_id = env.register(rv::ReceiptVault, ());
+    let vault = rv::ReceiptVaultClient::new(&env, &vault_id);
+    vault.initialize(&token, &0u128, &0u128, &admin);
+    vault.enable_static_rates(&admin);
+    let mint = token::StellarAssetClient::new(&env, &token);
+    mint.mint(&user, &1_000i128);
+    vault.deposit(&user, &200u128);
+
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(&env, &comp_id);
+    comp.initialize(&admin);
+    vault.set_peridottroller(&comp_id);
+    let mut config = listing_config(&vault_id);
+    config.fallback_price = 1_000_000u128;
+    config.fallback_scale = 1_000_000u128;
+    // The vault would only stage the caps and reserve factor.
+    comp.list_market(&config);
+}
+
+#[test]
+fn test_list_market_without_price_leaves_market_unlisted() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let vault_id = env.register(rv::ReceiptVault, ());
+    let vault = rv::ReceiptVaultClient::new(&env, &vault_id);
+    vault.initialize(&token, &0u128, &0u128, &admin);
+    vault.enable_static_rates(&admin);
+
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(&env, &comp_id);
+    comp.initialize(&admin);
+    vault.set_peridottroller(&comp_id);
+
+    // No oracle and no fallback: the listing reverts and applies none of the config.
+    assert!(comp.try_list_market(&listing_config(&vault_id)).is_err());
+    assert_eq!(comp.get_market_cf(&vault_id), 0u128);
+    assert!(!comp.is_market_deposit_only(&vault_id));
+    let user = Address::generate(&env);
+    assert!(comp.try_enter_market(&user, &vault_id).is_err());
+}
+
+fn setup_shutdown_market(
+    env: &Env,
+) -> (
+    SimplePeridottrollerClient<'_>,
+    rv::ReceiptVaultClient<'_>,
+    MockOracleClient<'_>,
+    Address,
+    Address,
+) {
+    env.mock_all_auths();
+    let admin = Address::generate(env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    let vault_id = env.register(rv::ReceiptVault, ());
+    let vault = rv::ReceiptVaultClient::new(env, &vault_id);
+    // 10% yearly borrow rate so frozen accrual is observable.
+    vault.initialize(&token, &0u128, &100_000u128, &admin);
+    vault.enable_static_rates(&admin);
+    vault.set_collateral_factor(&1_000_000u128);
+
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(env, &comp_id);
+    comp.initialize(&admin);
+    comp.add_market(&vault_id);
+    comp.set_market_cf(&vault_id, &1_000_000u128);
+    vault.set_peridottroller(&comp_id);
+    let oracle_id = env.register(MockOracle, ());
+    let oracle = MockOracleClient::new(env, &oracle_id);
+    oracle.initialize(&6u32);
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token, 1_000_000i128);
+    let guardian = Address::generate(env);
+    comp.set_pause_guardian(&guardian);
+    (comp, vault, oracle, token, guardian)
+}
+
+#[test]
+fn test_emergency_shutdown_freezes_prices_interest_and_settles_pro_rata() {
+    let env = Env::default();
+    let (comp, vault, oracle, token, guardian) = setup_shutdown_market(&env);
+    let supplier = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let mint = token::StellarAssetClient::new(&env, &token);
+    mint.mint(&supplier, &1_000i128);
+    mint.mint(&borrower, &1_000i128);
+    comp.enter_market(&borrower, &vault.address);
+    vault.deposit(&supplier, &1_000u128);
+    vault.deposit(&borrower, &1_000u128);
+    vault.borrow(&borrower, &400u128);
+
+    comp.enter_shutdown(&guardian);
+    assert!(comp.is_shutdown());
+    assert!(vault.is_shutdown());
+    assert!(comp.is_borrow_paused(&vault.address));
+
+    // A year later the oracle has moved and the cache is stale; nothing changes.
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    oracle.set_price(&token, &2_000_000i128);
+    assert_eq!(
+        comp.get_price_usd(&token),
+        Some((1_000_000u128, 1_000_000u128))
+    );
+    vault.update_interest();
+    assert_eq!(vault.get_user_borrow_balance(&borrower), 400u128);
+
+    // 1000 of 2000 pTokens may take half of the 1600 cash left in the market,
+    // paid at the full exchange rate.
+    assert!(vault.try_withdraw(&supplier, &1_000u128).is_err());
+    assert_eq!(vault.max_withdraw(&supplier), 800u128);
+    vault.withdraw(&supplier, &u128::MAX);
+    let token_client = token::Client::new(&env, &token);
+    assert_eq!(token_client.balance(&supplier), 800i128);
+    assert_eq!(vault.get_ptoken_balance(&supplier), 200u128);
+
+    // Repayments still settle normally, and the pTokens left behind keep their
+    // claim on the repaid cash.
+    vault.repay(&borrower, &400u128);
+    assert_eq!(vault.get_user_borrow_balance(&borrower), 0u128);
+    vault.withdraw(&supplier, &u128::MAX);
+    assert_eq!(token_client.balance(&supplier), 1_000i128);
+    assert_eq!(vault.get_ptoken_balance(&supplier), 0u128);
+}
+
+#[test]
+fn test_emergency_shutdown_share_holds_across_repeated_withdraws() {
+    let env = Env::default();
+    let (comp, vault, _oracle, token, guardian) = setup_shutdown_market(&env);
+    let token_client = token::Client::new(&env, &token);
+    let mint = token::StellarAssetClient::new(&env, &token);
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    mint.mint(&first, &1_000i128);
+    mint.mint(&second, &1_000i128);
+    mint.mint(&borrower, &500i128);
+    comp.enter_market(&borrower, &vault.address);
+    vault.deposit(&first, &1_000u128);
+    vault.deposit(&second, &1_000u128);
+    vault.deposit(&borrower, &500u128);
+    vault.borrow(&borrower, &400u128);
+    comp.enter_shutdown(&guardian);
+
+    // 2100 cash over 2500 pTokens at entry: 840 each, however often they withdraw.
+    vault.withdraw(&first, &u128::MAX);
+    assert_eq!(token_client.balance(&first), 840i128);
+    assert_eq!(vault.max_withdraw(&first), 0u128);
+    assert!(vault.try_withdraw(&first, &1u128).is_err());
+    assert_eq!(vault.max_withdraw(&second), 840u128);
+    vault.withdraw(&second, &u128::MAX);
+    assert_eq!(token_client.balance(&second), 840i128);
+
+    // Repaid cash is shared the same way.
+    vault.repay(&borrower, &400u128);
+    vault.withdraw(&first, &u128::MAX);
+    vault.withdraw(&second, &u128::MAX);
+    assert_eq!(token_client.balance(&first), 1_000i128);
+    assert_eq!(token_client.balance(&second), 1_000i128);
+}
+
+#[test]
+fn test_emergency_shutdown_skips_markets_that_reject_it() {
+    let env = Env::default();
+    let (comp, vault, _oracle, _token, guardian) = setup_shutdown_market(&env);
+    // Listed but never wired to this peridottroller, so `set_shutdown` reverts.
+    let admin = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let broken_id = env.register(rv::ReceiptVault, ());
+    let broken = rv::ReceiptVaultClient::new(&env, &broken_id);
+    broken.initialize(&token, &0u128, &0u128, &admin);
+    comp.add_market(&broken_id);
+
+    comp.enter_shutdown(&guardian);
+    assert!(comp.is_shutdown());
+    assert!(vault.is_shutdown());
+    assert!(!broken.is_shutdown());
+
+    comp.propose_exit_shutdown();
+    env.ledger()
+        .with_mut(|li| li.timestamp += SHUTDOWN_EXIT_TIMELOCK_SECS);
+    comp.exit_shutdown();
+    assert!(!comp.is_shutdown());
+    assert!(!vault.is_shutdown());
+}
+
+#[test]
+#[should_panic(expected = "borrow paused")]
+fn test_emergency_shutdown_blocks_borrow() {
+    let env = Env::default();
+    let (comp, vault, _oracle, token, guardian) = setup_shutdown_market(&env);
+    let user = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &token).mint(&user, &1_000i128);
+    comp.enter_market(&user, &vault.address);
+    vault.deposit(&user, &1_000u128);
+
+    comp.enter_shutdown(&guardian);
+    vault.borrow(&user, &100u128);
+}
+
+#[test]
+#[should_panic(expected = "invalid guardian")]
+fn test_enter_shutdown_requires_guardian() {
+    let env = Env::default();
+    let (comp, _vault, _oracle, _token, _guardian) = setup_shutdown_market(&env);
+    comp.enter_shutdown(&Address::generate(&env));
+}
+
+#[test]
+fn test_exit_shutdown_is_timelocked() {
+    let env = Env::default();
+    let (comp, vault, _oracle, _token, guardian) = setup_shutdown_market(&env);
+    comp.enter_shutdown(&guardian);
+
+    assert!(comp.try_exit_shutdown().is_err());
+    comp.propose_exit_shutdown();
+    assert!(comp.try_exit_shutdown().is_err());
+
+    env.ledger()
+        .with_mut(|li| li.timestamp += SHUTDOWN_EXIT_TIMELOCK_SECS);
+    comp.exit_shutdown();
+    assert!(!comp.is_shutdown());
+    assert!(!vault.is_shutdown());
+    assert!(!comp.is_borrow_paused(&vault.address));
+}
+
+#[contract]
+struct MultiFlashReceiver;
+
+#[contracttype]
+enum MultiFlashKey {
+    Mode,      // 0 = repay all, 1 = short the last leg, 2 = deposit into a lent market, 3 = reject
+    Initiator, // initiator seen by the last callback
+}
+
+#[contractimpl]
+impl MultiFlashReceiver {
+    pub fn set_mode(env: Env, mode: u32) {
+        env.storage().instance().set(&MultiFlashKey::Mode, &mode);
+    }
+
+    pub fn get_initiator(env: Env) -> Option<Address> {
+        env.storage().instance().get(&MultiFlashKey::Initiator)
+    }
+
+    pub fn on_flash_loan_multi(
+        env: Env,
+        initiator: Address,
+        legs: Vec<(Address, u128, u128)>,
+        _data: soroban_sdk::Bytes,
+    ) -> BytesN<32> {
+        env.storage()
+            .instance()
+            .set(&MultiFlashKey::Initiator, &initiator);
+        let mode: u32 = env
+            .storage()
+            .instance()
+            .get(&MultiFlashKey::Mode)
+            .unwrap_or(0);
+        let me = env.current_contract_address();
+        for (i, (market, amount, fee)) in legs.iter().enumerate() {
+            let vault = rv::ReceiptVaultClient::new(&env, &market);
+            if mode == 2 {
+                vault.deposit(&me, &amount);
+            }
+            let owed = if mode == 1 && i as u32 + 1 == legs.len() {
+                amount
+            } else {
+                amount + fee
+            };
+            token::Client::new(&env, &vault.get_underlying_token()).transfer(
+                &me,
+                &market,
+                &(owed as i128),
+            );
+        }
+        if mode == 3 {
+            return BytesN::from_array(&env, &[0u8; 32]);
+        }
+        env.crypto()
+            .keccak256(&soroban_sdk::Bytes::from_slice(
+                &env,
+                FLASH_CALLBACK_MESSAGE,
+            ))
+            .to_bytes()
+    }
+}
+
+fn setup_multi_flash(
+    env: &Env,
+) -> (
+    SimplePeridottrollerClient<'_>,
+    rv::ReceiptVaultClient<'_>,
+    rv::ReceiptVaultClient<'_>,
+    MultiFlashReceiverClient<'_>,
+) {
+    env.mock_all_auths_allowing_non_root_auth();
+    let admin = Address::generate(env);
+    let supplier = Address::generate(env);
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(env, &comp_id);
+    comp.initialize(&admin);
+    let receiver_id = env.register(MultiFlashReceiver, ());
+    let receiver = MultiFlashReceiverClient::new(env, &receiver_id);
+
+    let mut vaults = Vec::new(env);
+    for fee in [1_000u128, 3_000u128] {
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(env))
+            .address();
+        let mint = token::StellarAssetClient::new(env, &token);
+        mint.mint(&supplier, &10_000i128);
+        mint.mint(&receiver_id, &100i128);
+        let vault_id = env.register(rv::ReceiptVault, ());
+        let vault = rv::ReceiptVaultClient::new(env, &vault_id);
+        vault.initialize(&token, &0u128, &0u128, &admin);
+        vault.enable_static_rates(&admin);
+        vault.set_flash_loan_fee(&fee);
+        comp.add_market(&vault_id);
+        vault.set_peridottroller(&comp_id);
+        vault.deposit(&supplier, &10_000u128);
+        vaults.push_back(vault_id);
+    }
+    let first = rv::ReceiptVaultClient::new(env, &vaults.get(0).unwrap());
+    let second = rv::ReceiptVaultClient::new(env, &vaults.get(1).unwrap());
+    (comp, first, second, receiver)
+}
+
+#[test]
+fn test_flash_loan_multi_lends_every_market_and_books_fees() {
+    let env = Env::default();
+    let (comp, first, second, receiver) = setup_multi_flash(&env);
+    let initiator = Address::generate(&env);
+
+    let mut loans = Vec::new(&env);
+    loans.push_back((first.address.clone(), 5_000u128));
+    loans.push_back((second.address.clone(), 8_000u128));
+    comp.flash_loan_multi(
+        &initiator,
+        &receiver.address,
+        &loans,
+        &soroban_sdk::Bytes::new(&env),
+    );
+    assert_eq!(receiver.get_initiator(), Some(initiator.clone()));
+
+    // 0.1% of 5_000 and 0.3% of 8_000 land in each market's reserves.
+    assert_eq!(first.get_total_reserves(), 5u128);
+    assert_eq!(second.get_total_reserves(), 24u128);
+    assert_eq!(first.get_available_liquidity(), 10_000u128);
+    assert_eq!(second.get_available_liquidity(), 10_000u128);
+
+    // Both markets are unlocked again, so the same legs can run twice.
+    comp.flash_loan_multi(
+        &initiator,
+        &receiver.address,
+        &loans,
+        &soroban_sdk::Bytes::new(&env),
+    );
+    assert_eq!(first.get_total_reserves(), 10u128);
+    assert!(second.try_flash_loan_close().is_err());
+
+    // Each market applies its own fee discount for the initiator.
+    first.set_flash_fee_discount(&initiator, &1_000_000u128);
+    comp.flash_loan_multi(
+        &initiator,
+        &receiver.address,
+        &loans,
+        &soroban_sdk::Bytes::new(&env),
+    );
+    assert_eq!(first.get_total_reserves(), 10u128);
+    assert_eq!(second.get_total_reserves(), 72u128);
+}
+
+#[test]
+#[should_panic(expected = "invalid flash callback")]
+fn test_flash_loan_multi_rejects_callback_without_success_value() {
+    let env = Env::default();
+    let (comp, first, second, receiver) = setup_multi_flash(&env);
+    let initiator = Address::generate(&env);
+    receiver.set_mode(&3u32);
+
+    let mut loans = Vec::new(&env);
+    loans.push_back((first.address.clone(), 5_000u128));
+    loans.push_back((second.address.clone(), 8_000u128));
+    comp.flash_loan_multi(
+        &initiator,
+        &receiver.address,
+        &loans,
+        &soroban_sdk::Bytes::new(&env),
+    );
+}
+
+#[test]
+#[should_panic(expected = "flash loan not repaid")]
+fn test_flash_loan_multi_requires_every_leg_repaid_with_fee() {
+    let env = Env::default();
+    let (comp, first, second, receiver) = setup_multi_flash(&env);
+    let initiator = Address::generate(&env);
+    receiver.set_mode(&1u32);
+
+    let mut loans = Vec::new(&env);
+    loans.push_back((first.address.clone(), 5_000u128));
+    loans.push_back((second.address.clone(), 8_000u128));
+    comp.flash_loan_multi(
+        &initiator,
+        &receiver.address,
+        &loans,
+        &soroban_sdk::Bytes::new(&env),
+    );
+}
+
+#[test]
+#[should_panic(expected = "operation blocked during flash loan")]
+fn test_flash_loan_multi_locks_every_lent_market_during_callback() {
+    let env = Env::default();
+    let (comp, first, second, receiver) = setup_multi_flash(&env);
+    let initiator = Address::generate(&env);
+    receiver.set_mode(&2u32);
+
+    let mut loans = Vec::new(&env);
+    loans.push_back((first.address.clone(), 5_000u128));
+    loans.push_back((second.address.clone(), 8_000u128));
+    comp.flash_loan_multi(
+        &initiator,
+        &receiver.address,
+        &loans,
+        &soroban_sdk::Bytes::new(&env),
+    );
+}
+
+#[test]
+#[should_panic(expected = "duplicate flash market")]
+fn test_flash_loan_multi_rejects_duplicate_market() {
+    let env = Env::default();
+    let (comp, first, _second, receiver) = setup_multi_flash(&env);
+    let initiator = Address::generate(&env);
+
+    let mut loans = Vec::new(&env);
+    loans.push_back((first.address.clone(), 1_000u128));
+    loans.push_back((first.address.clone(), 1_000u128));
+    comp.flash_loan_multi(
+        &initiator,
+        &receiver.address,
+        &loans,
+        &soroban_sdk::Bytes::new(&env),
+    );
+}
+
+#[test]
+fn test_vault_param_changes_are_staged_once_market_has_suppliers() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&user, &1_000i128);
+    let vault_id = env.register(rv::ReceiptVault, ());
+    let vault = rv::ReceiptVaultClient::new(&env, &vault_id);
+    vault.initialize(&token, &0u128, &100_000u128, &admin);
+    vault.enable_static_rates(&admin);
+
+    // Setup on an empty market applies directly.
+    vault.set_borrow_rate(&200_000u128);
+    assert_eq!(vault.get_borrow_rate_per_year(), 200_000u128);
+    vault.deposit(&user, &1_000u128);
+
+    vault.set_borrow_rate(&300_000u128);
+    assert_eq!(vault.get_borrow_rate_per_year(), 200_000u128);
+    let pending = vault
+        .get_pending_param_change(&rv::VaultParam::BorrowRate)
+        .unwrap();
+    assert_eq!(pending.value, 300_000u128);
+    assert!(vault.try_set_borrow_rate(&300_000u128).is_err());
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = pending.execute_after);
+    vault.set_borrow_rate(&300_000u128);
+    assert_eq!(vault.get_borrow_rate_per_year(), 300_000u128);
+    assert_eq!(
+        vault.get_pending_param_change(&rv::VaultParam::BorrowRate),
+        None
+    );
+
+    vault.set_reserve_factor(&200_000u128);
+    assert!(vault
+        .get_pending_param_change(&rv::VaultParam::ReserveFactor)
+        .is_some());
+    vault.cancel_param_change(&rv::VaultParam::ReserveFactor);
+    assert_eq!(
+        vault.get_pending_param_change(&rv::VaultParam::ReserveFactor),
+        None
+    );
+}
+
+#[test]
+fn test_guardian_releases_and_extends_delayed_outflows() {
+    let env = Env::default();
+    let (comp, vault, _oracle, token, guardian) = setup_shutdown_market(&env);
+    let supplier = Address::generate(&env);
+    let token_client = token::Client::new(&env, &token);
+    token::StellarAssetClient::new(&env, &token).mint(&supplier, &1_000i128);
+    vault.deposit(&supplier, &1_000u128);
+    vault.set_outflow_limit(
+        &vault.get_admin(),
+        &Some(rv::OutflowLimit {
+            max_net_outflow: 400u128,
+            window_secs: 3_600u64,
+            claim_delay_secs: 86_400u64,
+        }),
+    );
+
+    // The excess over the limit is held rather than reverting the withdraw.
+    vault.withdraw(&supplier, &1_000u128);
+    assert_eq!(token_client.balance(&supplier), 400i128);
+    let claim = vault.get_delayed_outflow(&supplier).unwrap();
+    assert_eq!(claim.amount, 600u128);
+    assert!(vault.try_claim_delayed_outflow(&supplier).is_err());
+
+    comp.extend_delayed_outflow_g(&guardian, &vault.address, &supplier, &100u64);
+    assert_eq!(
+        vault.get_delayed_outflow(&supplier).unwrap().claimable_at,
+        claim.claimable_at + 100
+    );
+    let outsider = Address::generate(&env);
+    assert!(comp
+        .try_release_delayed_outflow_g(&outsider, &vault.address, &supplier)
+        .is_err());
+
+    comp.release_delayed_outflow_g(&guardian, &vault.address, &supplier);
+    assert_eq!(vault.claim_delayed_outflow(&supplier), 600u128);
+    assert_eq!(token_client.balance(&supplier), 1_000i128);
+    assert_eq!(vault.get_total_delayed_outflows(), 0u128);
+}
+
+/// Borrower with 1_000 collateral in B and 400 debt in A (min borrow 400),
+/// made liquidatable by dropping B to $0.70.
+fn setup_dust_liquidation(
+    env: &Env,
+    min_debt: u128,
+) -> (
+    SimplePeridottrollerClient<'_>,
+    rv::ReceiptVaultClient<'_>,
+    Address,
+    Address,
+    Address,
+) {
+    env.mock_all_auths_allowing_non_root_auth();
+    let admin = Address::generate(env);
+    let borrower = Address::generate(env);
+    let liquidator = Address::generate(env);
+    let token_a = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    let token_b = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+
+    let vault_a_id = env.register(rv::ReceiptVault, ());
+    let vault_a = rv::ReceiptVaultClient::new(env, &vault_a_id);
+    let vault_b_id = env.register(rv::ReceiptVault, ());
+    let vault_b = rv::ReceiptVaultClient::new(env, &vault_b_id);
+    vault_a.initialize(&token_a, &0u128, &0u128, &admin);
+    vault_a.enable_static_rates(&admin);
+    vault_b.initialize(&token_b, &0u128, &0u128, &admin);
+    vault_b.enable_static_rates(&admin);
+
+    let comp_id = env.register(SimplePeridottroller, ());
+    let comp = SimplePeridottrollerClient::new(env, &comp_id);
+    comp.initialize(&admin);
+    comp.add_market(&vault_a_id);
+    comp.add_market(&vault_b_id);
+    comp.enter_market(&borrower, &vault_a_id);
+    comp.enter_market(&borrower, &vault_b_id);
+    comp.set_market_cf(&vault_b_id, &500_000u128);
+
+    let oracle_id = env.register(MockOracle, ());
+    let oracle = MockOracleClient::new(env, &oracle_id);
+    oracle.initialize(&6u32);
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token_a, 1_000_000i128);
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token_b, 1_000_000i128);
+    comp.set_oracle(&oracle_id);
+    vault_a.set_peridottroller(&comp_id);
+    vault_b.set_peridottroller(&comp_id);
+
+    token::StellarAssetClient::new(env, &token_b).mint(&borrower, &1_000i128);
+    token::StellarAssetClient::new(env, &token_a).mint(&liquidator, &1_000i128);
+    approve_token_to_vault(env, &token_a, &liquidator, &vault_a_id, 1_000i128);
+    vault_b.set_collateral_factor(&500_000u128);
+    vault_b.deposit(&borrower, &1_000u128);
+    vault_a.deposit(&liquidator, &500u128);
+    vault_a.set_dust_limits(&admin, &400u128, &min_debt);
+    assert!(vault_a.try_borrow(&borrower, &399u128).is_err());
+    vault_a.borrow(&borrower, &400u128);
+    // The 100 of headroom left is below the minimum borrow.
+    assert_eq!(vault_a.max_borrow(&borrower), 0u128);
+    assert_eq!(comp.preview_borrow_max(&borrower, &vault_a_id), 0u128);
+
+    // Collateral at $0.70 leaves the borrower short.
+    set_price_and_cache(&comp, &oracle, &oracle_id, &token_b, 700_000i128);
+    (comp, vault_a, vault_b_id, borrower, liquidator)
+}
+
+#[test]
+fn test_liquidation_keeps_min_debt_and_closes_dust_in_full() {
+    let env = Env::default();
+    let (comp, vault_a, vault_b_id, borrower, liquidator) = setup_dust_liquidation(&env, 300u128);
+    let vault_a_id = vault_a.address.clone();
+
+    // The close factor allows 200, but that would leave less than the 300 minimum.
+    comp.liquidate(&borrower, &vault_a_id, &vault_b_id, &300u128, &liquidator);
+    assert_eq!(vault_a.get_user_borrow_balance(&borrower), 300u128);
+
+    // Once the debt is worth no more than the dust threshold it closes in full.
+    comp.set_dust_debt_threshold_usd(&300u128);
+    comp.liquidate(&borrower, &vault_a_id, &vault_b_id, &u128::MAX, &liquidator);
+    assert_eq!(vault_a.get_user_borrow_balance(&borrower), 0u128);
+}
+
+#[test]
+fn test_liquidation_closes_debt_at_min_debt_without_dust_threshold() {
+    let env = Env::default();
+    let (comp, vault_a, vault_b_id, borrower, liquidator) = setup_dust_liquidation(&env, 400u128);
+    let vault_a_id = vault_a.address.clone();
+    assert_eq!(comp.get_dust_debt_threshold_usd(), 0u128);
+
+    // No partial repay can leave the 400 minimum, so only a full close goes through.
+    assert!(comp
+        .try_liquidate(&borrower, &vault_a_id, &vault_b_id, &300u128, &liquidator)
+        .is_err());
+    comp.liquidate(&borrower, &vault_a_id, &vault_b_id, &u128::MAX, &liquidator);
+    assert_eq!(vault_a.get_user_borrow_balance(&borrower), 0u128);
+}
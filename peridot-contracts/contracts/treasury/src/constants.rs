@@ -0,0 +1,6 @@
+pub const DEFAULT_INIT_ADMIN: &str = "GATFXAP3AVUYRJJCXZ65EPVJEWRW6QYE3WOAFEXAIASFGZV7V7HMABPJ";
+pub const MAX_MARKETS: u32 = 32;
+pub const MAX_RECIPIENTS: u32 = 16;
+pub const TTL_THRESHOLD: u32 = 500_000;
+pub const TTL_EXTEND_TO: u32 = 1_000_000;
+pub const UPGRADE_TIMELOCK_SECS: u64 = 24 * 60 * 60;
@@ -0,0 +1,280 @@
+use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env, Symbol, Vec};
+
+use crate::constants::*;
+use crate::events::*;
+use crate::storage::*;
+
+#[contract]
+pub struct Treasury;
+
+#[contractimpl]
+impl Treasury {
+    pub fn initialize(env: Env, admin: Address) {
+        if env.storage().persistent().has(&DataKey::Admin) {
+            panic!("already initialized");
+        }
+        assert_expected_admin(&env, &admin);
+        admin.require_auth();
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        bump_core_ttl(&env);
+        TreasuryInitialized { admin }.publish(&env);
+    }
+
+    /// Admin: include a ReceiptVault in keeper sweeps. The vault must name this
+    /// contract via its own `set_treasury` before sweeps can pull from it.
+    pub fn add_market(env: Env, admin: Address, market: Address) {
+        require_admin(&env, &admin);
+        let mut markets = get_markets(&env);
+        if markets.contains(&market) {
+            panic!("market exists");
+        }
+        if markets.len() >= MAX_MARKETS {
+            panic!("too many markets");
+        }
+        let asset = ReceiptVaultClient::new(&env, &market).get_underlying_token();
+        markets.push_back(market.clone());
+        env.storage().persistent().set(&DataKey::Markets, &markets);
+        TreasuryMarketAdded { market, asset }.publish(&env);
+    }
+
+    pub fn remove_market(env: Env, admin: Address, market: Address) {
+        require_admin(&env, &admin);
+        let mut markets = get_markets(&env);
+        let index = markets.first_index_of(&market).expect("market not found");
+        markets.remove(index);
+        env.storage().persistent().set(&DataKey::Markets, &markets);
+        TreasuryMarketRemoved { market }.publish(&env);
+    }
+
+    /// Admin: replace the payout split. Weights are relative, so every asset
+    /// is divided by `weight / sum(weights)`.
+    pub fn set_recipients(env: Env, admin: Address, recipients: Vec<Recipient>) {
+        require_admin(&env, &admin);
+        if recipients.is_empty() {
+            panic!("no recipients");
+        }
+        if recipients.len() > MAX_RECIPIENTS {
+            panic!("too many recipients");
+        }
+        let mut seen: Vec<Address> = Vec::new(&env);
+        for entry in recipients.iter() {
+            if entry.weight == 0 {
+                panic!("invalid recipient weight");
+            }
+            if seen.contains(&entry.recipient) {
+                panic!("duplicate recipient");
+            }
+            seen.push_back(entry.recipient.clone());
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::Recipients, &recipients);
+        RecipientsUpdated { recipients }.publish(&env);
+    }
+
+    /// Keeper: sweep reserves, admin fees and fee pTokens from every market,
+    /// then split each asset among the recipients. Callable by anyone.
+    pub fn sweep(env: Env) {
+        bump_core_ttl(&env);
+        for market in get_markets(&env).iter() {
+            Self::sweep_one(&env, &market);
+        }
+    }
+
+    /// Keeper: `sweep` for a single configured market.
+    pub fn sweep_market(env: Env, market: Address) {
+        bump_core_ttl(&env);
+        if !get_markets(&env).contains(&market) {
+            panic!("market not found");
+        }
+        Self::sweep_one(&env, &market);
+    }
+
+    /// Keeper: split whatever balance of `asset` the treasury holds.
+    pub fn distribute(env: Env, asset: Address) {
+        bump_core_ttl(&env);
+        Self::distribute_asset(&env, &asset);
+    }
+
+    pub fn get_admin(env: Env) -> Address {
+        bump_core_ttl(&env);
+        env.storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("admin not set")
+    }
+
+    pub fn get_markets(env: Env) -> Vec<Address> {
+        bump_core_ttl(&env);
+        get_markets(&env)
+    }
+
+    pub fn get_recipients(env: Env) -> Vec<Recipient> {
+        bump_core_ttl(&env);
+        get_recipients(&env)
+    }
+
+    /// Total underlying of `asset` swept or redeemed into the treasury.
+    pub fn get_collected(env: Env, asset: Address) -> u128 {
+        get_collected(&env, &asset)
+    }
+
+    /// Total of `asset` paid out to `recipient`.
+    pub fn get_distributed(env: Env, asset: Address, recipient: Address) -> u128 {
+        get_distributed(&env, &asset, &recipient)
+    }
+
+    pub fn propose_upgrade_wasm(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        require_admin(&env, &admin);
+        let execute_after = env
+            .ledger()
+            .timestamp()
+            .saturating_add(UPGRADE_TIMELOCK_SECS);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingUpgradeHash, &new_wasm_hash);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingUpgradeEta, &execute_after);
+        bump_pending_upgrade_ttl(&env);
+    }
+
+    pub fn upgrade_wasm(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        require_admin(&env, &admin);
+        bump_pending_upgrade_ttl(&env);
+        let pending_hash: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingUpgradeHash)
+            .expect("pending upgrade not set");
+        let execute_after: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingUpgradeEta)
+            .expect("pending upgrade eta not set");
+        if pending_hash != new_wasm_hash {
+            panic!("upgrade hash mismatch");
+        }
+        if env.ledger().timestamp() < execute_after {
+            panic!("upgrade timelocked");
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingUpgradeHash);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingUpgradeEta);
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    /// Pull everything one market owes the treasury. Each external step is
+    /// tried on its own so a paused or misconfigured vault only logs a failure.
+    fn sweep_one(env: &Env, market: &Address) {
+        let vault = ReceiptVaultClient::new(env, market);
+        let asset = match vault.try_get_underlying_token() {
+            Ok(Ok(asset)) => asset,
+            _ => {
+                Self::sweep_failed(env, market, "asset");
+                return;
+            }
+        };
+        let (reserves, admin_fees) = match vault.try_sweep_to_treasury() {
+            Ok(Ok(swept)) => swept,
+            _ => {
+                Self::sweep_failed(env, market, "sweep");
+                (0u128, 0u128)
+            }
+        };
+
+        let this = env.current_contract_address();
+        let ptokens = match vault.try_get_ptoken_balance(&this) {
+            Ok(Ok(balance)) => balance,
+            _ => 0u128,
+        };
+        let mut ptokens_redeemed = 0u128;
+        let mut redeemed_underlying = 0u128;
+        if ptokens > 0 {
+            let before = Self::balance_of(env, &asset);
+            if matches!(vault.try_withdraw(&this, &ptokens), Ok(Ok(()))) {
+                ptokens_redeemed = ptokens;
+                redeemed_underlying = Self::balance_of(env, &asset).saturating_sub(before);
+            } else {
+                Self::sweep_failed(env, market, "redeem");
+            }
+        }
+
+        let proceeds = reserves
+            .saturating_add(admin_fees)
+            .saturating_add(redeemed_underlying);
+        if proceeds > 0 || ptokens_redeemed > 0 {
+            add_collected(env, &asset, proceeds);
+            MarketSwept {
+                market: market.clone(),
+                asset: asset.clone(),
+                reserves,
+                admin_fees,
+                ptokens_redeemed,
+                redeemed_underlying,
+            }
+            .publish(env);
+        }
+        Self::distribute_asset(env, &asset);
+    }
+
+    /// Split the held balance by weight; the last recipient takes rounding
+    /// dust so nothing is stranded. Balances stay put until recipients exist.
+    fn distribute_asset(env: &Env, asset: &Address) {
+        let recipients = get_recipients(env);
+        if recipients.is_empty() {
+            return;
+        }
+        let balance = Self::balance_of(env, asset);
+        if balance == 0 {
+            return;
+        }
+        let mut total_weight = 0u128;
+        for entry in recipients.iter() {
+            total_weight = total_weight.saturating_add(entry.weight as u128);
+        }
+        let token_client = token::Client::new(env, asset);
+        let this = env.current_contract_address();
+        let last = recipients.len() - 1;
+        let mut remaining = balance;
+        for (i, entry) in recipients.iter().enumerate() {
+            let amount = if i as u32 == last {
+                remaining
+            } else {
+                balance.saturating_mul(entry.weight as u128) / total_weight
+            };
+            if amount == 0 {
+                continue;
+            }
+            remaining = remaining.saturating_sub(amount);
+            token_client.transfer(&this, &entry.recipient, &(amount as i128));
+            add_distributed(env, asset, &entry.recipient, amount);
+            ProceedsDistributed {
+                asset: asset.clone(),
+                recipient: entry.recipient.clone(),
+                amount,
+            }
+            .publish(env);
+        }
+    }
+
+    fn balance_of(env: &Env, asset: &Address) -> u128 {
+        let balance = token::Client::new(env, asset).balance(&env.current_contract_address());
+        if balance < 0 {
+            0u128
+        } else {
+            balance as u128
+        }
+    }
+
+    fn sweep_failed(env: &Env, market: &Address, step: &str) {
+        MarketSweepFailed {
+            market: market.clone(),
+            step: Symbol::new(env, step),
+        }
+        .publish(env);
+    }
+}
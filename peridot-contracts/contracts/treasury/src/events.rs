@@ -0,0 +1,63 @@
+use soroban_sdk::{contractevent, Address, Symbol, Vec};
+
+use crate::storage::Recipient;
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreasuryInitialized {
+    pub admin: Address,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreasuryMarketAdded {
+    #[topic]
+    pub market: Address,
+    pub asset: Address,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreasuryMarketRemoved {
+    #[topic]
+    pub market: Address,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecipientsUpdated {
+    pub recipients: Vec<Recipient>,
+}
+
+/// Emits once per market and sweep with everything pulled out of it.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketSwept {
+    #[topic]
+    pub market: Address,
+    #[topic]
+    pub asset: Address,
+    pub reserves: u128,
+    pub admin_fees: u128,
+    pub ptokens_redeemed: u128,
+    pub redeemed_underlying: u128,
+}
+
+/// Logs a market step that reverted so one bad market cannot block a sweep.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketSweepFailed {
+    #[topic]
+    pub market: Address,
+    pub step: Symbol,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProceedsDistributed {
+    #[topic]
+    pub asset: Address,
+    #[topic]
+    pub recipient: Address,
+    pub amount: u128,
+}
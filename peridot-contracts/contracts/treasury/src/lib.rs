@@ -0,0 +1,14 @@
+#![no_std]
+
+mod constants;
+mod contract;
+mod events;
+mod storage;
+
+pub use constants::*;
+pub use contract::*;
+pub use events::*;
+pub use storage::*;
+
+#[cfg(test)]
+mod test;
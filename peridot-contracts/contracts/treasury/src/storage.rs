@@ -0,0 +1,134 @@
+use soroban_sdk::{contracttype, Address, Env, String, Vec};
+
+use crate::constants::*;
+
+#[soroban_sdk::contractclient(name = "ReceiptVaultClient")]
+pub trait ReceiptVaultContract {
+    fn sweep_to_treasury(env: Env) -> (u128, u128);
+    fn withdraw(env: Env, user: Address, ptoken_amount: u128);
+    fn get_underlying_token(env: Env) -> Address;
+    fn get_ptoken_balance(env: Env, user: Address) -> u128;
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Recipient {
+    pub recipient: Address,
+    pub weight: u32, // relative share of every asset's proceeds
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,                         // Address
+    Markets,                       // Vec<Address> receipt vaults swept by `sweep`
+    Recipients,                    // Vec<Recipient> payout split
+    Collected(Address),            // u128 cumulative proceeds per underlying asset
+    Distributed(Address, Address), // u128 cumulative payout per (asset, recipient)
+    PendingUpgradeHash,            // BytesN<32>
+    PendingUpgradeEta,             // u64 unix timestamp
+}
+
+pub fn get_markets(env: &Env) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Markets)
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn get_recipients(env: &Env) -> Vec<Recipient> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Recipients)
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn get_collected(env: &Env, asset: &Address) -> u128 {
+    let key = DataKey::Collected(asset.clone());
+    let value: Option<u128> = env.storage().persistent().get(&key);
+    if value.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    value.unwrap_or(0u128)
+}
+
+pub fn add_collected(env: &Env, asset: &Address, amount: u128) {
+    let total = get_collected(env, asset).saturating_add(amount);
+    env.storage()
+        .persistent()
+        .set(&DataKey::Collected(asset.clone()), &total);
+}
+
+pub fn get_distributed(env: &Env, asset: &Address, recipient: &Address) -> u128 {
+    let key = DataKey::Distributed(asset.clone(), recipient.clone());
+    let value: Option<u128> = env.storage().persistent().get(&key);
+    if value.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    value.unwrap_or(0u128)
+}
+
+pub fn add_distributed(env: &Env, asset: &Address, recipient: &Address, amount: u128) {
+    let total = get_distributed(env, asset, recipient).saturating_add(amount);
+    env.storage().persistent().set(
+        &DataKey::Distributed(asset.clone(), recipient.clone()),
+        &total,
+    );
+}
+
+pub fn require_admin(env: &Env, admin: &Address) {
+    let stored: Address = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Admin)
+        .expect("admin not set");
+    bump_core_ttl(env);
+    if stored != *admin {
+        panic!("not admin");
+    }
+    admin.require_auth();
+}
+
+pub fn assert_expected_admin(env: &Env, admin: &Address) {
+    let expected_admin = Address::from_string(&String::from_str(env, expected_admin_config()));
+    if admin != &expected_admin {
+        panic!("unexpected admin");
+    }
+}
+
+fn expected_admin_config() -> &'static str {
+    if cfg!(any(
+        test,
+        all(debug_assertions, feature = "test-default-admin")
+    )) {
+        option_env!("TREASURY_INIT_ADMIN").unwrap_or(DEFAULT_INIT_ADMIN)
+    } else {
+        option_env!("TREASURY_INIT_ADMIN").expect("TREASURY_INIT_ADMIN must be set at build time")
+    }
+}
+
+pub fn bump_core_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    if persistent.has(&DataKey::Admin) {
+        persistent.extend_ttl(&DataKey::Admin, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    if persistent.has(&DataKey::Markets) {
+        persistent.extend_ttl(&DataKey::Markets, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    if persistent.has(&DataKey::Recipients) {
+        persistent.extend_ttl(&DataKey::Recipients, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+pub fn bump_pending_upgrade_ttl(env: &Env) {
+    let persistent = env.storage().persistent();
+    if persistent.has(&DataKey::PendingUpgradeHash) {
+        persistent.extend_ttl(&DataKey::PendingUpgradeHash, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+    if persistent.has(&DataKey::PendingUpgradeEta) {
+        persistent.extend_ttl(&DataKey::PendingUpgradeEta, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
@@ -0,0 +1,150 @@
+use super::*;
+use mock_token::MockTokenClient;
+use receipt_vault::{ReceiptVault, ReceiptVaultClient as VaultClient};
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{Address, Env, String, Vec};
+
+fn setup_vault<'a>(env: &'a Env, admin: &Address, user: &Address) -> (VaultClient<'a>, Address) {
+    let token_address = env.register(mock_token::MockToken, ());
+    let token = MockTokenClient::new(env, &token_address);
+    token.initialize(
+        &String::from_str(env, "Mock Token"),
+        &String::from_str(env, "MOCK"),
+        &7u32,
+    );
+    token.mint(user, &100_000i128);
+
+    let vault_id = env.register(ReceiptVault, ());
+    let vault = VaultClient::new(env, &vault_id);
+    vault.initialize(&token_address, &0u128, &1_000_000u128, admin);
+    vault.enable_static_rates(admin);
+    vault.set_reserve_factor(&200_000u128);
+    vault.set_admin_fee(&100_000u128);
+    vault.set_collateral_factor(&1_000_000u128);
+    vault.deposit(user, &20_000u128);
+    vault.borrow(user, &10_000u128);
+    (vault, token_address)
+}
+
+fn setup_treasury(env: &Env) -> (TreasuryClient<'_>, Address) {
+    let admin = Address::from_string(&String::from_str(env, DEFAULT_INIT_ADMIN));
+    let id = env.register(Treasury, ());
+    let treasury = TreasuryClient::new(env, &id);
+    treasury.initialize(&admin);
+    (treasury, admin)
+}
+
+fn recipients(env: &Env, entries: &[(&Address, u32)]) -> Vec<Recipient> {
+    let mut out = Vec::new(env);
+    for (recipient, weight) in entries {
+        out.push_back(Recipient {
+            recipient: (*recipient).clone(),
+            weight: *weight,
+        });
+    }
+    out
+}
+
+#[test]
+fn sweep_collects_reserves_fees_and_fee_ptokens_then_splits() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+    let (treasury, admin) = setup_treasury(&env);
+    let vault_admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (vault, token_address) = setup_vault(&env, &vault_admin, &user);
+    vault.set_treasury(&vault_admin, &Some(treasury.address.clone()));
+    treasury.add_market(&admin, &vault.address);
+
+    let ops = Address::generate(&env);
+    let stakers = Address::generate(&env);
+    treasury.set_recipients(&admin, &recipients(&env, &[(&ops, 3), (&stakers, 1)]));
+
+    env.ledger()
+        .set_timestamp(env.ledger().timestamp() + 365 * 24 * 60 * 60);
+    vault.update_interest();
+    let reserves = vault.get_total_reserves();
+    let fees = vault.get_total_admin_fees();
+    assert!(reserves > 0 && fees > 0);
+
+    // Liquidation fee pTokens land on the treasury as the reserve recipient.
+    vault.transfer(&user, &treasury.address, &1_000i128);
+    let rate = vault.get_exchange_rate_1e18();
+    let fee_underlying = 1_000u128 * rate / 1_000_000_000_000_000_000u128;
+
+    treasury.sweep();
+
+    assert_eq!(vault.get_total_reserves(), 0);
+    assert_eq!(vault.get_total_admin_fees(), 0);
+    assert_eq!(vault.get_ptoken_balance(&treasury.address), 0);
+
+    let collected = treasury.get_collected(&token_address);
+    assert_eq!(collected, reserves + fees + fee_underlying);
+    let token = MockTokenClient::new(&env, &token_address);
+    let ops_paid = treasury.get_distributed(&token_address, &ops);
+    let stakers_paid = treasury.get_distributed(&token_address, &stakers);
+    assert_eq!(ops_paid, collected * 3 / 4);
+    assert_eq!(ops_paid + stakers_paid, collected);
+    assert_eq!(token.balance(&ops) as u128, ops_paid);
+    assert_eq!(token.balance(&stakers) as u128, stakers_paid);
+    assert_eq!(token.balance(&treasury.address), 0);
+
+    // Nothing new accrued in the same ledger, so a second sweep is a no-op.
+    treasury.sweep();
+    assert_eq!(treasury.get_collected(&token_address), collected);
+}
+
+#[test]
+fn sweep_skips_failing_market_and_holds_until_recipients_set() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+    let (treasury, admin) = setup_treasury(&env);
+    let vault_admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (wired, wired_token) = setup_vault(&env, &vault_admin, &user);
+    let (unwired, unwired_token) = setup_vault(&env, &vault_admin, &user);
+    wired.set_treasury(&vault_admin, &Some(treasury.address.clone()));
+    treasury.add_market(&admin, &unwired.address);
+    treasury.add_market(&admin, &wired.address);
+
+    env.ledger()
+        .set_timestamp(env.ledger().timestamp() + 365 * 24 * 60 * 60);
+    treasury.sweep();
+
+    // The vault without `set_treasury` reverts its sweep, the other still pays.
+    unwired.update_interest();
+    assert!(unwired.get_total_reserves() > 0);
+    assert_eq!(treasury.get_collected(&unwired_token), 0);
+    let collected = treasury.get_collected(&wired_token);
+    assert!(collected > 0);
+    let token = MockTokenClient::new(&env, &wired_token);
+    assert_eq!(token.balance(&treasury.address) as u128, collected);
+
+    let dao = Address::generate(&env);
+    treasury.set_recipients(&admin, &recipients(&env, &[(&dao, 1)]));
+    treasury.distribute(&wired_token);
+    assert_eq!(token.balance(&dao) as u128, collected);
+    assert_eq!(treasury.get_distributed(&wired_token, &dao), collected);
+
+    treasury.remove_market(&admin, &unwired.address);
+    assert_eq!(treasury.get_markets().len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "duplicate recipient")]
+fn set_recipients_rejects_duplicates() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (treasury, admin) = setup_treasury(&env);
+    let dao = Address::generate(&env);
+    treasury.set_recipients(&admin, &recipients(&env, &[(&dao, 1), (&dao, 2)]));
+}
+
+#[test]
+#[should_panic(expected = "unexpected admin")]
+fn initialize_rejects_unexpected_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let id = env.register(Treasury, ());
+    TreasuryClient::new(&env, &id).initialize(&Address::generate(&env));
+}
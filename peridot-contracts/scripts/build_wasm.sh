@@ -11,6 +11,9 @@ declare -A CRATE_TO_WASM=(
   [receipt-vault]=receipt_vault
   [simple-peridottroller]=simple_peridottroller
   [jump-rate-model]=jump_rate_model
+  [adaptive-rate-model]=adaptive_rate_model
+  [interest-model-registry]=interest_model_registry
+  [treasury]=treasury
   [peridot-token]=peridot_token
   [mock-token]=mock_token
   [mock-lending-vault]=mock_lending_vault
@@ -18,7 +21,7 @@ declare -A CRATE_TO_WASM=(
   [margin-controller]=margin_controller
 )
 
-for crate in receipt-vault simple-peridottroller jump-rate-model peridot-token mock-token mock-lending-vault swap-adapter margin-controller; do
+for crate in receipt-vault simple-peridottroller jump-rate-model adaptive-rate-model interest-model-registry treasury peridot-token mock-token mock-lending-vault swap-adapter margin-controller; do
   echo "→ $crate"
   stellar contract build --package "$crate"
   wasm_name=${CRATE_TO_WASM[$crate]}